@@ -2,15 +2,13 @@ use bevy::{
     asset::{AssetLoader, AsyncReadExt},
     prelude::*,
 };
-use jc2_hashing::HashList;
+use jc2_hashing::{HashEntryKind, HashList};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub(crate) enum FileListError {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
-    #[error("hash collision: {path:?}")]
-    HashCollision { path: String },
 }
 
 #[derive(Asset, Debug, Clone, TypePath)]
@@ -30,16 +28,14 @@ impl AssetLoader for FileListLoader {
         &'a self,
         reader: &'a mut bevy::asset::io::Reader<'_>,
         _settings: &'a Self::Settings,
-        _load_context: &'a mut bevy::asset::LoadContext<'_>,
+        load_context: &'a mut bevy::asset::LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut buffer = String::new();
         reader.read_to_string(&mut buffer).await?;
 
-        let mut paths = HashList::new();
-        for path in buffer.lines() {
-            if paths.insert_path(path).is_some() {
-                return Err(FileListError::HashCollision { path: path.into() });
-            }
+        let (paths, collisions) = HashList::read_text(buffer.as_bytes(), HashEntryKind::Path)?;
+        for collision in collisions {
+            warn!("hash collision in {:?}: {collision}", load_context.path());
         }
 
         Ok(FileList { paths })
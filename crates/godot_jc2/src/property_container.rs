@@ -1,5 +1,8 @@
 use godot::prelude::*;
-use jc2_file_formats::property_container::{PropertyContainer, PropertyEntry, PropertyValue};
+use jc2_file_formats::{
+    math::{Mat3, Mat3x4},
+    property_container::{PropertyContainer, PropertyEntry, PropertyPath, PropertyValue},
+};
 use jc2_hashing::HashString;
 
 #[derive(GodotClass)]
@@ -84,123 +87,12 @@ impl JcPropertyContainer {
             return Variant::nil();
         };
 
-        match value {
-            PropertyEntry::Container(container) => {
-                Variant::from(JcPropertyContainer::new(container))
-            }
-            PropertyEntry::Value(value) => match value {
-                PropertyValue::Empty => Variant::nil(),
-                PropertyValue::I32(value) => Variant::from(value),
-                PropertyValue::F32(value) => Variant::from(value),
-                PropertyValue::String(value) => Variant::from(value),
-                PropertyValue::Vec2(value) => Variant::from(Vector2::from_array(value.into())),
-                PropertyValue::Vec3(value) => Variant::from(Vector3::from_array(value.into())),
-                PropertyValue::Vec4(value) => Variant::from(Vector4::from_array(value.into())),
-                PropertyValue::Mat3x3(value) => Variant::from(Basis::from_cols(
-                    Vector3::new(value[0], value[1], value[2]),
-                    Vector3::new(value[3], value[4], value[5]),
-                    Vector3::new(value[6], value[7], value[8]),
-                )),
-                PropertyValue::Mat3x4(value) => Variant::from(Transform3D::from_cols(
-                    Vector3::new(value[0], value[1], value[2]),
-                    Vector3::new(value[3], value[4], value[5]),
-                    Vector3::new(value[6], value[7], value[8]),
-                    Vector3::new(value[9], value[10], value[11]),
-                )),
-                PropertyValue::VecI32(value) => Variant::from(PackedInt32Array::from(value)),
-                PropertyValue::VecF32(value) => Variant::from(PackedFloat32Array::from(value)),
-            },
-        }
+        entry_to_variant(value)
     }
 
     #[func]
     pub fn add(&mut self, key: GString, value: Variant) {
-        fn maybe<T: FromGodot, F: FnOnce(T) -> Option<PropertyEntry>>(
-            value: Variant,
-            f: F,
-        ) -> Option<PropertyEntry> {
-            value.try_to::<T>().ok().and_then(f)
-        }
-
-        fn some<T: FromGodot, F: FnOnce(T) -> PropertyEntry>(
-            value: Variant,
-            f: F,
-        ) -> Option<PropertyEntry> {
-            Some(f(value.to::<T>()))
-        }
-
-        let Some(entry) = (match value.get_type() {
-            VariantType::OBJECT => maybe(value, |value: Gd<JcPropertyContainer>| {
-                value
-                    .bind()
-                    .value
-                    .as_ref()
-                    .map(|container| PropertyEntry::Container(container.clone()))
-            }),
-            VariantType::BOOL => some(value, |value: bool| (value as i32).into()),
-            VariantType::INT => some(value, |value: i32| value.into()),
-            VariantType::FLOAT => some(value, |value: f32| value.into()),
-            VariantType::STRING => some(value, |value: GString| value.to_string().into()),
-            VariantType::VECTOR2 => some(value, |value: Vector2| value.to_array().into()),
-            VariantType::VECTOR2I => some(value, |value: Vector2i| {
-                [value.x as f32, value.y as f32].into()
-            }),
-            VariantType::VECTOR3 => some(value, |value: Vector3| value.to_array().into()),
-            VariantType::VECTOR3I => some(value, |value: Vector3i| {
-                [value.x as f32, value.y as f32, value.z as f32].into()
-            }),
-            VariantType::VECTOR4 => some(value, |value: Vector4| value.to_array().into()),
-            VariantType::VECTOR4I => some(value, |value: Vector4i| {
-                [
-                    value.x as f32,
-                    value.y as f32,
-                    value.z as f32,
-                    value.w as f32,
-                ]
-                .into()
-            }),
-            VariantType::QUATERNION => some(value, |value: Quaternion| {
-                [value.x, value.y, value.z, value.w].into()
-            }),
-            VariantType::BASIS => some(value, |value: Basis| {
-                [
-                    value.rows[0].x,
-                    value.rows[0].y,
-                    value.rows[0].z,
-                    value.rows[1].x,
-                    value.rows[1].y,
-                    value.rows[1].z,
-                    value.rows[2].x,
-                    value.rows[2].y,
-                    value.rows[2].z,
-                ]
-                .into()
-            }),
-            VariantType::TRANSFORM3D => some(value, |value: Transform3D| {
-                [
-                    value.basis.rows[0].x,
-                    value.basis.rows[0].y,
-                    value.basis.rows[0].z,
-                    value.basis.rows[1].x,
-                    value.basis.rows[1].y,
-                    value.basis.rows[1].z,
-                    value.basis.rows[2].x,
-                    value.basis.rows[2].y,
-                    value.basis.rows[2].z,
-                    value.origin.x,
-                    value.origin.y,
-                    value.origin.z,
-                ]
-                .into()
-            }),
-            VariantType::PACKED_INT32_ARRAY => {
-                some(value, |value: PackedInt32Array| value.to_vec().into())
-            }
-            VariantType::PACKED_FLOAT32_ARRAY => {
-                some(value, |value: PackedFloat32Array| value.to_vec().into())
-            }
-            _ => None,
-        }) else {
+        let Some(entry) = variant_to_entry(value) else {
             return;
         };
 
@@ -230,8 +122,145 @@ impl JcPropertyContainer {
                 .map(|container| JcPropertyContainer::new(container)),
         )
     }
+
+    /// Returns the first entry matching a path such as `objects/*[_class=CLight]/world`.
+    #[func]
+    pub fn find(&self, path: GString) -> Variant {
+        let (Some(container), Some(path)) = (&self.value, parse_path(path)) else {
+            return Variant::nil();
+        };
+
+        container
+            .find(&path)
+            .cloned()
+            .map_or_else(Variant::nil, entry_to_variant)
+    }
+
+    #[func]
+    pub fn find_all(&self, path: GString) -> VariantArray {
+        let (Some(container), Some(path)) = (&self.value, parse_path(path)) else {
+            return VariantArray::default();
+        };
+
+        container
+            .find_all(&path)
+            .into_iter()
+            .cloned()
+            .map(entry_to_variant)
+            .collect()
+    }
+
+    /// Replaces every existing entry matching the path, returning the number replaced.
+    #[func]
+    pub fn set_all(&mut self, path: GString, value: Variant) -> i32 {
+        let (Some(container), Some(path), Some(entry)) =
+            (&mut self.value, parse_path(path), variant_to_entry(value))
+        else {
+            return 0;
+        };
+
+        container.set_all(&path, entry) as i32
+    }
+
+    #[func]
+    pub fn remove_all(&mut self, path: GString) -> i32 {
+        let (Some(container), Some(path)) = (&mut self.value, parse_path(path)) else {
+            return 0;
+        };
+
+        container.remove_all(&path) as i32
+    }
+}
+
+fn parse_path(path: GString) -> Option<PropertyPath> {
+    PropertyPath::new(&path.to_string())
+        .inspect_err(|error| godot_error!("JcPropertyContainer: invalid path {path} ({error})"))
+        .ok()
 }
 
 fn hash(key: GString) -> HashString {
     HashString::from_bytes(key.to_utf8_buffer().as_slice())
 }
+
+fn entry_to_variant(value: PropertyEntry) -> Variant {
+    match value {
+        PropertyEntry::Container(container) => Variant::from(JcPropertyContainer::new(container)),
+        PropertyEntry::Value(value) => match value {
+            PropertyValue::Empty => Variant::nil(),
+            PropertyValue::I32(value) => Variant::from(value),
+            PropertyValue::F32(value) => Variant::from(value),
+            PropertyValue::String(value) => Variant::from(value),
+            PropertyValue::Vec2(value) => Variant::from(Vector2::from_array(value.into())),
+            PropertyValue::Vec3(value) => Variant::from(Vector3::from_array(value.into())),
+            PropertyValue::Vec4(value) => Variant::from(Vector4::from_array(value.into())),
+            PropertyValue::Mat3x3(value) => Variant::from(Basis::from(value)),
+            PropertyValue::Mat3x4(value) => Variant::from(Transform3D::from(value)),
+            PropertyValue::VecI32(value) => Variant::from(PackedInt32Array::from(value)),
+            PropertyValue::VecF32(value) => Variant::from(PackedFloat32Array::from(value)),
+            PropertyValue::Raw(value) => Variant::from(PackedByteArray::from(value)),
+        },
+    }
+}
+
+fn variant_to_entry(value: Variant) -> Option<PropertyEntry> {
+    fn maybe<T: FromGodot, F: FnOnce(T) -> Option<PropertyEntry>>(
+        value: Variant,
+        f: F,
+    ) -> Option<PropertyEntry> {
+        value.try_to::<T>().ok().and_then(f)
+    }
+
+    fn some<T: FromGodot, F: FnOnce(T) -> PropertyEntry>(
+        value: Variant,
+        f: F,
+    ) -> Option<PropertyEntry> {
+        Some(f(value.to::<T>()))
+    }
+
+    match value.get_type() {
+        VariantType::OBJECT => maybe(value, |value: Gd<JcPropertyContainer>| {
+            value
+                .bind()
+                .value
+                .as_ref()
+                .map(|container| PropertyEntry::Container(container.clone()))
+        }),
+        VariantType::BOOL => some(value, |value: bool| (value as i32).into()),
+        VariantType::INT => some(value, |value: i32| value.into()),
+        VariantType::FLOAT => some(value, |value: f32| value.into()),
+        VariantType::STRING => some(value, |value: GString| value.to_string().into()),
+        VariantType::VECTOR2 => some(value, |value: Vector2| value.to_array().into()),
+        VariantType::VECTOR2I => some(value, |value: Vector2i| {
+            [value.x as f32, value.y as f32].into()
+        }),
+        VariantType::VECTOR3 => some(value, |value: Vector3| value.to_array().into()),
+        VariantType::VECTOR3I => some(value, |value: Vector3i| {
+            [value.x as f32, value.y as f32, value.z as f32].into()
+        }),
+        VariantType::VECTOR4 => some(value, |value: Vector4| value.to_array().into()),
+        VariantType::VECTOR4I => some(value, |value: Vector4i| {
+            [
+                value.x as f32,
+                value.y as f32,
+                value.z as f32,
+                value.w as f32,
+            ]
+            .into()
+        }),
+        VariantType::QUATERNION => some(value, |value: Quaternion| {
+            [value.x, value.y, value.z, value.w].into()
+        }),
+        VariantType::BASIS => some(value, |value: Basis| Mat3::from(value).into()),
+        VariantType::TRANSFORM3D => some(value, |value: Transform3D| Mat3x4::from(value).into()),
+        VariantType::PACKED_INT32_ARRAY => {
+            some(value, |value: PackedInt32Array| value.to_vec().into())
+        }
+        VariantType::PACKED_FLOAT32_ARRAY => {
+            some(value, |value: PackedFloat32Array| value.to_vec().into())
+        }
+        VariantType::PACKED_BYTE_ARRAY => {
+            some(value, |value: PackedByteArray| value.to_vec().into())
+        }
+        _ => None,
+    }
+}
@@ -1,7 +1,10 @@
 use binrw::BinRead;
 use godot::prelude::*;
-use jc2_file_formats::property_container::{
-    FromPropertyValue, PropertyBlockFile, PropertyContainer, PropertyFile, PropertyValue,
+use jc2_file_formats::{
+    math::Mat3x4,
+    property_container::{
+        PropertyBlockFile, PropertyContainer, PropertyFile, location::LocationObject,
+    },
 };
 
 use super::{JcResourceError, JcResourceFormat, JcResourceResult, JcResourceThread};
@@ -60,25 +63,9 @@ fn create_objects(
     objects: &mut Vec<Gd<Node3D>>,
     thread: &mut JcResourceThread,
 ) -> JcResourceResult<()> {
-    struct JcTransform(Transform3D);
-
-    impl<'a> FromPropertyValue<'a> for JcTransform {
-        fn from_property_value(value: &'a PropertyValue) -> Option<Self> {
-            match value {
-                PropertyValue::Mat3x4(value) => Some(Self(Transform3D::from_cols(
-                    Vector3::new(value[0], value[1], value[2]),
-                    Vector3::new(value[3], value[4], value[5]),
-                    Vector3::new(value[6], value[7], value[8]),
-                    Vector3::new(value[9], value[10], value[11]),
-                ))),
-                _ => None,
-            }
-        }
-    }
-
-    if let Some(world) = properties.get_value::<JcTransform>("world") {
+    if let Some(world) = properties.get_value::<Mat3x4<f32>>("world") {
         let mut child: Option<Gd<Node3D>> = match properties.get_value::<&str>("_class") {
-            Some("CDamageableObject" | "CLandmark" | "CPlantedTree" | "CSimpleRigidObject") => {
+            Some(class) if LocationObject::MODEL_CLASSES.contains(&class) => {
                 match properties.get_value::<&str>("filename") {
                     None | Some("") => None,
                     Some(path) => Some(thread.create_resource_from_path(path.into())?.cast()),
@@ -87,7 +74,7 @@ fn create_objects(
             None | Some(_) => None,
         };
 
-        let transform = transform * world.0;
+        let transform = transform * Transform3D::from(world);
         if let Some(child) = &mut child {
             parent.add_child(&*child);
             child.set_transform(transform);
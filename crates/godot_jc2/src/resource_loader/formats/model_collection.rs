@@ -39,13 +39,7 @@ impl JcResourceFormat for JcModelCollection {
                         let index = GString::from(&index.to_string());
                         model.set_name(GString::new().join(&[name, index].into()).arg());
 
-                        let transform = &instance.transform;
-                        model.set_transform(Transform3D::from_cols(
-                            Vector3::new(transform[0], transform[1], transform[2]),
-                            Vector3::new(transform[4], transform[5], transform[6]),
-                            Vector3::new(transform[8], transform[9], transform[10]),
-                            Vector3::new(transform[12], transform[13], transform[14]),
-                        ));
+                        model.set_transform(instance.transform.to_mat3x4().into());
 
                         result.add_child(&model);
                     };
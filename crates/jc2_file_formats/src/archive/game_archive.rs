@@ -0,0 +1,158 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use jc2_hashing::{HashCollision, HashEntryKind, HashList, HashString};
+
+use super::{ArchiveError, ArchiveTable, ArchiveTableEntry};
+
+/// A `.tab` file along with the `.arc` file holding its data.
+pub struct GameArchive {
+    pub path: PathBuf,
+    pub table: ArchiveTable,
+    data: PathBuf,
+}
+
+/// Reads text and binary dictionaries into `names`, returning the collisions found while
+/// merging them. Binary dictionaries are told apart by their magic, and anything else is read
+/// as text with one path per line.
+pub fn read_dictionaries(
+    names: &mut HashList,
+    paths: &[PathBuf],
+) -> Result<Vec<HashCollision>, ArchiveError> {
+    let mut collisions = vec![];
+    for path in paths {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 4];
+        let binary = reader.read_exact(&mut magic).is_ok() && &magic == b"HDIC";
+        reader.rewind()?;
+
+        let list = if binary {
+            HashList::read_binary(&mut reader)?
+        } else {
+            HashList::read_text(reader, HashEntryKind::Path)?.0
+        };
+        collisions.extend(names.merge(list));
+    }
+    Ok(collisions)
+}
+
+/// Converts an entry name to a relative path, accepting either separator and dropping anything
+/// that would leave the directory it is joined to.
+pub fn relative_path(name: &str) -> PathBuf {
+    name.split(['/', '\\'])
+        .filter(|part| !matches!(*part, "" | "." | ".."))
+        .collect()
+}
+
+impl GameArchive {
+    pub fn open(path: &Path) -> Result<Self, ArchiveError> {
+        let table = ArchiveTable::read(&mut BufReader::new(File::open(path)?))?;
+        Ok(Self {
+            path: path.into(),
+            table,
+            data: path.with_extension("arc"),
+        })
+    }
+
+    /// Finds every `.tab` file in the given paths, searching directories recursively. Each is
+    /// returned along with its path relative to the directory it was found in.
+    pub fn find(paths: &[PathBuf]) -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
+        fn visit(
+            root: &Path,
+            path: &Path,
+            result: &mut Vec<(PathBuf, PathBuf)>,
+        ) -> std::io::Result<()> {
+            if path.is_dir() {
+                let mut entries = std::fs::read_dir(path)?
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                entries.sort();
+                for entry in entries {
+                    visit(root, &entry, result)?;
+                }
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("tab"))
+            {
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                result.push((path.into(), relative.into()));
+            }
+            Ok(())
+        }
+
+        let mut result = vec![];
+        for path in paths {
+            let root = if path.is_dir() {
+                path
+            } else {
+                path.parent().unwrap_or(path)
+            };
+            visit(root, path, &mut result)?;
+        }
+        Ok(result)
+    }
+
+    /// Reads the `.filelist` accompanying the archive, if there is one.
+    pub fn file_list(&self) -> std::io::Result<HashList> {
+        let path = self.path.with_extension("filelist");
+        if !path.is_file() {
+            return Ok(HashList::new());
+        }
+
+        let reader = BufReader::new(File::open(path)?);
+        let (list, _) = HashList::read_text(reader, HashEntryKind::Path)?;
+        Ok(list)
+    }
+
+    /// Reads the `.filelist` accompanying the archive, falling back to the one bundled for it
+    /// as the archive loader does.
+    #[cfg(feature = "dictionary")]
+    pub fn file_list_or_bundled(&self) -> std::io::Result<HashList> {
+        if self.path.with_extension("filelist").is_file() {
+            self.file_list()
+        } else {
+            Ok(HashList::jc2_archive(&self.path).unwrap_or_default())
+        }
+    }
+
+    /// Returns every entry in the archive, in the order they are stored.
+    pub fn entries(&self) -> Vec<(HashString, &ArchiveTableEntry)> {
+        let mut entries = self
+            .table
+            .entries
+            .iter()
+            .map(|(hash, entry)| (*hash, entry))
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(_, entry)| entry.offset);
+        entries
+    }
+
+    /// Opens the `.arc` file, for reading entries with [`Self::read_entry`].
+    pub fn open_data(&self) -> std::io::Result<BufReader<File>> {
+        Ok(BufReader::new(File::open(&self.data)?))
+    }
+
+    pub fn read_entry(
+        reader: &mut (impl Read + Seek),
+        entry: &ArchiveTableEntry,
+        buffer: &mut Vec<u8>,
+    ) -> std::io::Result<()> {
+        buffer.resize(entry.size as usize, 0);
+        reader.seek(SeekFrom::Start(u64::from(entry.offset)))?;
+        reader.read_exact(buffer)
+    }
+
+    /// Calls `f` with the data of every entry in the archive, in the order they are stored.
+    pub fn for_each_entry(&self, mut f: impl FnMut(HashString, &[u8])) -> std::io::Result<()> {
+        let mut reader = self.open_data()?;
+        let mut buffer = vec![];
+        for (hash, entry) in self.entries() {
+            Self::read_entry(&mut reader, entry, &mut buffer)?;
+            f(hash, &buffer);
+        }
+        Ok(())
+    }
+}
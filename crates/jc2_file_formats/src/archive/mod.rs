@@ -1,13 +1,26 @@
 use std::{
     collections::HashMap,
     io::{Read, Seek, Write},
+    path::Path,
 };
 
 use binrw::{BinRead, BinResult, BinWrite, binrw, parser, writer};
 use jc2_hashing::HashString;
+use thiserror::Error;
 
 use crate::common::LengthString;
 
+mod game_archive;
+pub use game_archive::*;
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error(transparent)]
+    Binary(#[from] binrw::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
 #[binrw]
 #[repr(C)]
 #[derive(Clone, Debug)]
@@ -113,11 +126,27 @@ pub struct StreamArchive {
 }
 
 impl StreamArchive {
-    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, binrw::Error> {
+    /// The extensions stream archives are stored with, including the compressed variants.
+    pub const EXTENSIONS: [&'static str; 8] = ["bl", "blz", "ee", "eez", "fl", "flz", "nl", "nlz"];
+
+    /// Whether a file is a stream archive, going by its extension or its magic.
+    pub fn is_stream_archive(path: &Path, data: &[u8]) -> bool {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        extension.is_some_and(|extension| Self::EXTENSIONS.contains(&extension.as_str()))
+            || data.get(4..8) == Some(b"SARC")
+    }
+
+    /// Whether the archive is zlib compressed, as the .blz, .eez, .flz and .nlz variants are.
+    pub fn is_compressed<R: Read + Seek>(reader: &mut R) -> Result<bool, binrw::Error> {
         let compressed = u8::read_options(reader, binrw::Endian::Little, ())? == 0x78;
         reader.seek(std::io::SeekFrom::Start(0))?;
+        Ok(compressed)
+    }
 
-        if compressed {
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, binrw::Error> {
+        if Self::is_compressed(reader)? {
             let mut buffer = Vec::new();
             flate2::read::ZlibDecoder::new(reader).read_to_end(&mut buffer)?;
             let mut reader = std::io::Cursor::new(buffer);
@@ -144,6 +173,16 @@ impl StreamArchive {
         return self.write_be(writer);
     }
 
+    pub fn write_compressed<W: Write>(&self, writer: &mut W) -> Result<(), binrw::Error> {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        self.write(&mut buffer)?;
+
+        let mut encoder = flate2::write::ZlibEncoder::new(writer, flate2::Compression::default());
+        encoder.write_all(buffer.get_ref())?;
+        encoder.finish()?;
+        Ok(())
+    }
+
     #[parser(reader, endian)]
     fn parse_entries() -> BinResult<HashMap<String, Vec<u8>>> {
         let table_size = u32::read_options(reader, endian, ())?;
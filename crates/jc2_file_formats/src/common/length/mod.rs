@@ -27,7 +27,7 @@ pub trait LengthType: BinReadWrite + NumCast + Unsigned + Copy {
         } else {
             Err(binrw::Error::Custom {
                 pos: writer.stream_position()?,
-                err: Box::new(LengthError::InvalidLength),
+                err: Box::new(LengthError::TooLong(value)),
             })
         }
     }
@@ -39,6 +39,8 @@ impl<T> LengthType for T where T: BinReadWrite + NumCast + Unsigned + Copy {}
 pub enum LengthError {
     #[error("invalid length")]
     InvalidLength,
+    #[error("length {0} does not fit in the length prefix")]
+    TooLong(usize),
 }
 
 mod bitvec;
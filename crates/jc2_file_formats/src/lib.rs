@@ -1,5 +1,9 @@
 pub use binrw::{BinRead, BinWrite};
 
+// Allows derived implementations to refer to this crate by name from within it
+#[cfg(feature = "derive")]
+extern crate self as jc2_file_formats;
+
 pub trait BinReadWrite: BinRead<Args<'static> = ()> + BinWrite<Args<'static> = ()> {}
 
 impl<T> BinReadWrite for T where T: BinRead<Args<'static> = ()> + BinWrite<Args<'static> = ()> {}
@@ -11,3 +15,5 @@ pub mod model_collection;
 pub mod property_container;
 pub mod render_block_model;
 pub mod terrain;
+#[cfg(feature = "texture")]
+pub mod texture;
@@ -0,0 +1,100 @@
+use binrw::binrw;
+
+use super::{Mat3x4, Vec3, VecType, VecTypeFloat};
+
+/// An axis-aligned bounding box, stored as its minimum and maximum corners.
+#[binrw]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+pub struct Aabb<T: VecType> {
+    pub min: Vec3<T>,
+    pub max: Vec3<T>,
+}
+
+impl<T: VecType> From<(Vec3<T>, Vec3<T>)> for Aabb<T> {
+    #[inline]
+    fn from((min, max): (Vec3<T>, Vec3<T>)) -> Self {
+        Self::new(min, max)
+    }
+}
+
+impl<T: VecType> From<Aabb<T>> for (Vec3<T>, Vec3<T>) {
+    #[inline]
+    fn from(aabb: Aabb<T>) -> Self {
+        (aabb.min, aabb.max)
+    }
+}
+
+impl<T: VecType> Aabb<T> {
+    #[inline]
+    pub const fn new(min: Vec3<T>, max: Vec3<T>) -> Self {
+        Self { min, max }
+    }
+
+    #[inline]
+    pub fn size(&self) -> Vec3<T> {
+        self.max - self.min
+    }
+
+    #[inline]
+    pub fn center(&self) -> Vec3<T> {
+        (self.min + self.max) / (T::one() + T::one())
+    }
+
+    #[inline]
+    pub fn contains(&self, point: Vec3<T>) -> bool
+    where
+        T: PartialOrd,
+    {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    /// Returns the eight corners of the box.
+    pub fn corners(&self) -> [Vec3<T>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+        ]
+    }
+}
+
+impl<T: VecTypeFloat> Aabb<T> {
+    /// Returns the smallest box containing every point, or `None` if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3<T>>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, point| aabb.extend(point)))
+    }
+
+    /// Grows the box to contain a point.
+    #[inline]
+    pub fn extend(&self, point: Vec3<T>) -> Self {
+        Self::new(self.min.min(point), self.max.max(point))
+    }
+
+    /// Returns the smallest box containing both boxes.
+    #[inline]
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Returns the smallest axis-aligned box containing this box once transformed.
+    pub fn transform(&self, transform: &Mat3x4<T>) -> Self {
+        let corners = self
+            .corners()
+            .map(|corner| transform.transform_point3(corner));
+        let first = corners[0];
+        corners[1..]
+            .iter()
+            .fold(Self::new(first, first), |aabb, corner| aabb.extend(*corner))
+    }
+}
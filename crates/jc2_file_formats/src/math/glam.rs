@@ -0,0 +1,112 @@
+use super::{Mat3, Mat3x4, Mat4, Quat, Vec2, Vec3, Vec4};
+
+impl From<glam::Vec2> for Vec2<f32> {
+    #[inline]
+    fn from(v: glam::Vec2) -> Self {
+        Self::new(v.x, v.y)
+    }
+}
+
+impl From<Vec2<f32>> for glam::Vec2 {
+    #[inline]
+    fn from(v: Vec2<f32>) -> Self {
+        Self::new(v.x, v.y)
+    }
+}
+
+impl From<glam::Vec3> for Vec3<f32> {
+    #[inline]
+    fn from(v: glam::Vec3) -> Self {
+        Self::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<Vec3<f32>> for glam::Vec3 {
+    #[inline]
+    fn from(v: Vec3<f32>) -> Self {
+        Self::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<glam::Vec4> for Vec4<f32> {
+    #[inline]
+    fn from(v: glam::Vec4) -> Self {
+        Self::new(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl From<Vec4<f32>> for glam::Vec4 {
+    #[inline]
+    fn from(v: Vec4<f32>) -> Self {
+        Self::new(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl From<glam::Quat> for Quat<f32> {
+    #[inline]
+    fn from(q: glam::Quat) -> Self {
+        Self::new(q.x, q.y, q.z, q.w)
+    }
+}
+
+impl From<Quat<f32>> for glam::Quat {
+    #[inline]
+    fn from(q: Quat<f32>) -> Self {
+        Self::from_xyzw(q.x, q.y, q.z, q.w)
+    }
+}
+
+impl From<glam::Mat3> for Mat3<f32> {
+    #[inline]
+    fn from(m: glam::Mat3) -> Self {
+        Self::from_cols(m.x_axis.into(), m.y_axis.into(), m.z_axis.into())
+    }
+}
+
+impl From<Mat3<f32>> for glam::Mat3 {
+    #[inline]
+    fn from(m: Mat3<f32>) -> Self {
+        Self::from_cols(m.x_axis.into(), m.y_axis.into(), m.z_axis.into())
+    }
+}
+
+impl From<glam::Affine3A> for Mat3x4<f32> {
+    #[inline]
+    fn from(m: glam::Affine3A) -> Self {
+        Self::from_mat3_translation(
+            glam::Mat3::from(m.matrix3).into(),
+            glam::Vec3::from(m.translation).into(),
+        )
+    }
+}
+
+impl From<Mat3x4<f32>> for glam::Affine3A {
+    #[inline]
+    fn from(m: Mat3x4<f32>) -> Self {
+        Self::from_mat3_translation(m.matrix3().into(), m.translation().into())
+    }
+}
+
+impl From<glam::Mat4> for Mat4<f32> {
+    #[inline]
+    fn from(m: glam::Mat4) -> Self {
+        Self::from_cols(
+            m.x_axis.into(),
+            m.y_axis.into(),
+            m.z_axis.into(),
+            m.w_axis.into(),
+        )
+    }
+}
+
+impl From<Mat4<f32>> for glam::Mat4 {
+    #[inline]
+    fn from(m: Mat4<f32>) -> Self {
+        Self::from_cols(
+            m.x_axis.into(),
+            m.y_axis.into(),
+            m.z_axis.into(),
+            m.w_axis.into(),
+        )
+    }
+}
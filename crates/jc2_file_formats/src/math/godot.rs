@@ -0,0 +1,124 @@
+use godot::builtin::{
+    Aabb as GdAabb, Basis, Projection, Quaternion, Transform3D, Vector2, Vector3, Vector4,
+};
+
+use super::{Aabb, Mat3, Mat3x4, Mat4, Quat, Vec2, Vec3, Vec4};
+
+impl From<Vector2> for Vec2<f32> {
+    #[inline]
+    fn from(v: Vector2) -> Self {
+        Self::new(v.x, v.y)
+    }
+}
+
+impl From<Vec2<f32>> for Vector2 {
+    #[inline]
+    fn from(v: Vec2<f32>) -> Self {
+        Self::new(v.x, v.y)
+    }
+}
+
+impl From<Vector3> for Vec3<f32> {
+    #[inline]
+    fn from(v: Vector3) -> Self {
+        Self::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<Vec3<f32>> for Vector3 {
+    #[inline]
+    fn from(v: Vec3<f32>) -> Self {
+        Self::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<Vector4> for Vec4<f32> {
+    #[inline]
+    fn from(v: Vector4) -> Self {
+        Self::new(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl From<Vec4<f32>> for Vector4 {
+    #[inline]
+    fn from(v: Vec4<f32>) -> Self {
+        Self::new(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl From<Quaternion> for Quat<f32> {
+    #[inline]
+    fn from(q: Quaternion) -> Self {
+        Self::new(q.x, q.y, q.z, q.w)
+    }
+}
+
+impl From<Quat<f32>> for Quaternion {
+    #[inline]
+    fn from(q: Quat<f32>) -> Self {
+        Self::new(q.x, q.y, q.z, q.w)
+    }
+}
+
+impl From<Basis> for Mat3<f32> {
+    #[inline]
+    fn from(m: Basis) -> Self {
+        Self::from_cols(m.col_a().into(), m.col_b().into(), m.col_c().into())
+    }
+}
+
+impl From<Mat3<f32>> for Basis {
+    #[inline]
+    fn from(m: Mat3<f32>) -> Self {
+        Self::from_cols(m.x_axis.into(), m.y_axis.into(), m.z_axis.into())
+    }
+}
+
+impl From<Transform3D> for Mat3x4<f32> {
+    #[inline]
+    fn from(m: Transform3D) -> Self {
+        Self::from_mat3_translation(m.basis.into(), m.origin.into())
+    }
+}
+
+impl From<Mat3x4<f32>> for Transform3D {
+    #[inline]
+    fn from(m: Mat3x4<f32>) -> Self {
+        Self::new(m.matrix3().into(), m.translation().into())
+    }
+}
+
+impl From<Projection> for Mat4<f32> {
+    #[inline]
+    fn from(m: Projection) -> Self {
+        let [x, y, z, w] = m.cols;
+        Self::from_cols(x.into(), y.into(), z.into(), w.into())
+    }
+}
+
+impl From<Mat4<f32>> for Projection {
+    #[inline]
+    fn from(m: Mat4<f32>) -> Self {
+        Self::from_cols(
+            m.x_axis.into(),
+            m.y_axis.into(),
+            m.z_axis.into(),
+            m.w_axis.into(),
+        )
+    }
+}
+
+impl From<GdAabb> for Aabb<f32> {
+    #[inline]
+    fn from(aabb: GdAabb) -> Self {
+        let min = Vec3::from(aabb.position);
+        Self::new(min, min + aabb.size.into())
+    }
+}
+
+impl From<Aabb<f32>> for GdAabb {
+    #[inline]
+    fn from(aabb: Aabb<f32>) -> Self {
+        Self::new(aabb.min.into(), aabb.size().into())
+    }
+}
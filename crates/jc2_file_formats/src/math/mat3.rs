@@ -0,0 +1,154 @@
+use std::ops::Mul;
+
+use binrw::binrw;
+
+use super::{
+    Quat, Vec3, VecType, VecTypeFloat,
+    ops::{VecCross, VecDot},
+};
+
+/// A column-major 3x3 matrix, used for rotation and scale. The default is all zeroes, as property
+/// files expect of a null value; see [`Mat3::identity`].
+#[binrw]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+pub struct Mat3<T: VecType> {
+    pub x_axis: Vec3<T>,
+    pub y_axis: Vec3<T>,
+    pub z_axis: Vec3<T>,
+}
+
+impl<T: VecType> From<[T; 9]> for Mat3<T> {
+    #[inline]
+    fn from(a: [T; 9]) -> Self {
+        Self::from_cols(
+            Vec3::new(a[0], a[1], a[2]),
+            Vec3::new(a[3], a[4], a[5]),
+            Vec3::new(a[6], a[7], a[8]),
+        )
+    }
+}
+
+impl<T: VecType> From<Mat3<T>> for [T; 9] {
+    #[inline]
+    fn from(m: Mat3<T>) -> Self {
+        let (x, y, z) = (m.x_axis, m.y_axis, m.z_axis);
+        [x.x, x.y, x.z, y.x, y.y, y.z, z.x, z.y, z.z]
+    }
+}
+
+impl<T: VecTypeFloat> From<Quat<T>> for Mat3<T> {
+    #[inline]
+    fn from(q: Quat<T>) -> Self {
+        Self::from_quat(q)
+    }
+}
+
+impl<T: VecType> Mat3<T> {
+    #[inline]
+    pub const fn from_cols(x_axis: Vec3<T>, y_axis: Vec3<T>, z_axis: Vec3<T>) -> Self {
+        Self {
+            x_axis,
+            y_axis,
+            z_axis,
+        }
+    }
+
+    #[inline]
+    pub fn identity() -> Self {
+        Self::from_scale(Vec3::splat(T::one()))
+    }
+
+    #[inline]
+    pub fn from_scale(scale: Vec3<T>) -> Self {
+        let zero = T::zero();
+        Self::from_cols(
+            Vec3::new(scale.x, zero, zero),
+            Vec3::new(zero, scale.y, zero),
+            Vec3::new(zero, zero, scale.z),
+        )
+    }
+
+    #[inline]
+    pub fn transpose(&self) -> Self {
+        let (x, y, z) = (self.x_axis, self.y_axis, self.z_axis);
+        Self::from_cols(
+            Vec3::new(x.x, y.x, z.x),
+            Vec3::new(x.y, y.y, z.y),
+            Vec3::new(x.z, y.z, z.z),
+        )
+    }
+
+    #[inline]
+    pub fn mul_vec3(&self, v: Vec3<T>) -> Vec3<T> {
+        self.x_axis * v.x + self.y_axis * v.y + self.z_axis * v.z
+    }
+
+    #[inline]
+    pub fn mul_mat3(&self, rhs: &Self) -> Self {
+        Self::from_cols(
+            self.mul_vec3(rhs.x_axis),
+            self.mul_vec3(rhs.y_axis),
+            self.mul_vec3(rhs.z_axis),
+        )
+    }
+}
+
+impl<T: VecTypeFloat> Mat3<T> {
+    /// Creates a rotation matrix from a normalized quaternion.
+    pub fn from_quat(q: Quat<T>) -> Self {
+        let one = T::one();
+        let two = one + one;
+        let (x2, y2, z2) = (q.x * two, q.y * two, q.z * two);
+        let (xx, xy, xz) = (q.x * x2, q.x * y2, q.x * z2);
+        let (yy, yz, zz) = (q.y * y2, q.y * z2, q.z * z2);
+        let (wx, wy, wz) = (q.w * x2, q.w * y2, q.w * z2);
+
+        Self::from_cols(
+            Vec3::new(one - (yy + zz), xy + wz, xz - wy),
+            Vec3::new(xy - wz, one - (xx + zz), yz + wx),
+            Vec3::new(xz + wy, yz - wx, one - (xx + yy)),
+        )
+    }
+
+    #[inline]
+    pub fn determinant(&self) -> T {
+        self.z_axis.dot(self.x_axis.cross(self.y_axis))
+    }
+
+    /// Returns the inverse of the matrix, or `None` if it is not invertible.
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant == T::zero() {
+            return None;
+        }
+
+        let x = self.y_axis.cross(self.z_axis);
+        let y = self.z_axis.cross(self.x_axis);
+        let z = self.x_axis.cross(self.y_axis);
+        let inverse = Self::from_cols(x, y, z).transpose();
+        Some(Self::from_cols(
+            inverse.x_axis / determinant,
+            inverse.y_axis / determinant,
+            inverse.z_axis / determinant,
+        ))
+    }
+}
+
+impl<T: VecType> Mul<Mat3<T>> for Mat3<T> {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        self.mul_mat3(&rhs)
+    }
+}
+
+impl<T: VecType> Mul<Vec3<T>> for Mat3<T> {
+    type Output = Vec3<T>;
+
+    #[inline]
+    fn mul(self, rhs: Vec3<T>) -> Vec3<T> {
+        self.mul_vec3(rhs)
+    }
+}
@@ -0,0 +1,178 @@
+use std::ops::Mul;
+
+use binrw::binrw;
+
+use super::{Mat3, Mat4, Quat, Vec3, Vec4, VecType, VecTypeFloat};
+
+/// A column-major affine transform, made of a 3x3 matrix followed by a translation. The default
+/// is all zeroes, as property files expect of a null value; see [`Mat3x4::identity`].
+#[binrw]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+pub struct Mat3x4<T: VecType> {
+    pub x_axis: Vec3<T>,
+    pub y_axis: Vec3<T>,
+    pub z_axis: Vec3<T>,
+    pub w_axis: Vec3<T>,
+}
+
+impl<T: VecType> From<[T; 12]> for Mat3x4<T> {
+    #[inline]
+    fn from(a: [T; 12]) -> Self {
+        Self::from_cols(
+            Vec3::new(a[0], a[1], a[2]),
+            Vec3::new(a[3], a[4], a[5]),
+            Vec3::new(a[6], a[7], a[8]),
+            Vec3::new(a[9], a[10], a[11]),
+        )
+    }
+}
+
+impl<T: VecType> From<Mat3x4<T>> for [T; 12] {
+    #[inline]
+    fn from(m: Mat3x4<T>) -> Self {
+        let (x, y, z, w) = (m.x_axis, m.y_axis, m.z_axis, m.w_axis);
+        [x.x, x.y, x.z, y.x, y.y, y.z, z.x, z.y, z.z, w.x, w.y, w.z]
+    }
+}
+
+impl<T: VecType> From<Mat3<T>> for Mat3x4<T> {
+    #[inline]
+    fn from(m: Mat3<T>) -> Self {
+        Self::from_mat3_translation(m, Vec3::splat(T::zero()))
+    }
+}
+
+/// Drops the last row of the matrix, which is assumed to be `[0, 0, 0, 1]`.
+impl<T: VecType> From<Mat4<T>> for Mat3x4<T> {
+    #[inline]
+    fn from(m: Mat4<T>) -> Self {
+        Self::from_cols(
+            m.x_axis.into(),
+            m.y_axis.into(),
+            m.z_axis.into(),
+            m.w_axis.into(),
+        )
+    }
+}
+
+impl<T: VecType> From<Mat3x4<T>> for Mat4<T> {
+    #[inline]
+    fn from(m: Mat3x4<T>) -> Self {
+        let (zero, one) = (T::zero(), T::one());
+        Self::from_cols(
+            m.x_axis.extend(zero),
+            m.y_axis.extend(zero),
+            m.z_axis.extend(zero),
+            m.w_axis.extend(one),
+        )
+    }
+}
+
+impl<T: VecType> Mat3x4<T> {
+    #[inline]
+    pub const fn from_cols(
+        x_axis: Vec3<T>,
+        y_axis: Vec3<T>,
+        z_axis: Vec3<T>,
+        w_axis: Vec3<T>,
+    ) -> Self {
+        Self {
+            x_axis,
+            y_axis,
+            z_axis,
+            w_axis,
+        }
+    }
+
+    #[inline]
+    pub fn identity() -> Self {
+        Mat3::identity().into()
+    }
+
+    #[inline]
+    pub fn from_translation(translation: Vec3<T>) -> Self {
+        Self::from_mat3_translation(Mat3::identity(), translation)
+    }
+
+    #[inline]
+    pub const fn from_mat3_translation(m: Mat3<T>, translation: Vec3<T>) -> Self {
+        Self::from_cols(m.x_axis, m.y_axis, m.z_axis, translation)
+    }
+
+    #[inline]
+    pub const fn matrix3(&self) -> Mat3<T> {
+        Mat3::from_cols(self.x_axis, self.y_axis, self.z_axis)
+    }
+
+    #[inline]
+    pub const fn translation(&self) -> Vec3<T> {
+        self.w_axis
+    }
+
+    /// Transforms a point, applying the translation.
+    #[inline]
+    pub fn transform_point3(&self, point: Vec3<T>) -> Vec3<T> {
+        self.matrix3().mul_vec3(point) + self.w_axis
+    }
+
+    /// Transforms a direction, ignoring the translation.
+    #[inline]
+    pub fn transform_vector3(&self, vector: Vec3<T>) -> Vec3<T> {
+        self.matrix3().mul_vec3(vector)
+    }
+
+    #[inline]
+    pub fn mul_mat3x4(&self, rhs: &Self) -> Self {
+        Self::from_mat3_translation(
+            self.matrix3().mul_mat3(&rhs.matrix3()),
+            self.transform_point3(rhs.w_axis),
+        )
+    }
+
+    #[inline]
+    pub fn to_mat4(&self) -> Mat4<T> {
+        (*self).into()
+    }
+}
+
+impl<T: VecTypeFloat> Mat3x4<T> {
+    #[inline]
+    pub fn from_rotation_translation(rotation: Quat<T>, translation: Vec3<T>) -> Self {
+        Self::from_mat3_translation(Mat3::from_quat(rotation), translation)
+    }
+
+    /// Returns the inverse of the transform, or `None` if it is not invertible.
+    pub fn inverse(&self) -> Option<Self> {
+        let matrix3 = self.matrix3().inverse()?;
+        let translation = matrix3.mul_vec3(self.w_axis) * -T::one();
+        Some(Self::from_mat3_translation(matrix3, translation))
+    }
+}
+
+impl<T: VecType> Mul<Mat3x4<T>> for Mat3x4<T> {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        self.mul_mat3x4(&rhs)
+    }
+}
+
+impl<T: VecType> Mul<Vec3<T>> for Mat3x4<T> {
+    type Output = Vec3<T>;
+
+    #[inline]
+    fn mul(self, rhs: Vec3<T>) -> Vec3<T> {
+        self.transform_point3(rhs)
+    }
+}
+
+impl<T: VecType> Mul<Vec4<T>> for Mat3x4<T> {
+    type Output = Vec3<T>;
+
+    #[inline]
+    fn mul(self, rhs: Vec4<T>) -> Vec3<T> {
+        self.matrix3().mul_vec3(rhs.into()) + self.w_axis * rhs.w
+    }
+}
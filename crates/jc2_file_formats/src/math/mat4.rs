@@ -0,0 +1,204 @@
+use std::ops::Mul;
+
+use binrw::binrw;
+
+use super::{Mat3x4, Vec3, Vec4, VecType, VecTypeFloat};
+
+/// A column-major 4x4 matrix.
+#[binrw]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd)]
+pub struct Mat4<T: VecType> {
+    pub x_axis: Vec4<T>,
+    pub y_axis: Vec4<T>,
+    pub z_axis: Vec4<T>,
+    pub w_axis: Vec4<T>,
+}
+
+impl<T: VecType> Default for Mat4<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<T: VecType> From<[T; 16]> for Mat4<T> {
+    #[inline]
+    fn from(a: [T; 16]) -> Self {
+        Self::from_cols(
+            Vec4::new(a[0], a[1], a[2], a[3]),
+            Vec4::new(a[4], a[5], a[6], a[7]),
+            Vec4::new(a[8], a[9], a[10], a[11]),
+            Vec4::new(a[12], a[13], a[14], a[15]),
+        )
+    }
+}
+
+impl<T: VecType> From<Mat4<T>> for [T; 16] {
+    #[inline]
+    fn from(m: Mat4<T>) -> Self {
+        let (x, y, z, w) = (m.x_axis, m.y_axis, m.z_axis, m.w_axis);
+        [
+            x.x, x.y, x.z, x.w, y.x, y.y, y.z, y.w, z.x, z.y, z.z, z.w, w.x, w.y, w.z, w.w,
+        ]
+    }
+}
+
+impl<T: VecType> Mat4<T> {
+    #[inline]
+    pub const fn from_cols(
+        x_axis: Vec4<T>,
+        y_axis: Vec4<T>,
+        z_axis: Vec4<T>,
+        w_axis: Vec4<T>,
+    ) -> Self {
+        Self {
+            x_axis,
+            y_axis,
+            z_axis,
+            w_axis,
+        }
+    }
+
+    #[inline]
+    pub fn identity() -> Self {
+        Mat3x4::identity().into()
+    }
+
+    #[inline]
+    pub fn transpose(&self) -> Self {
+        let (x, y, z, w) = (self.x_axis, self.y_axis, self.z_axis, self.w_axis);
+        Self::from_cols(
+            Vec4::new(x.x, y.x, z.x, w.x),
+            Vec4::new(x.y, y.y, z.y, w.y),
+            Vec4::new(x.z, y.z, z.z, w.z),
+            Vec4::new(x.w, y.w, z.w, w.w),
+        )
+    }
+
+    #[inline]
+    pub fn mul_vec4(&self, v: Vec4<T>) -> Vec4<T> {
+        let (x, y, z, w) = (self.x_axis, self.y_axis, self.z_axis, self.w_axis);
+        Vec4::new(
+            x.x * v.x + y.x * v.y + z.x * v.z + w.x * v.w,
+            x.y * v.x + y.y * v.y + z.y * v.z + w.y * v.w,
+            x.z * v.x + y.z * v.y + z.z * v.z + w.z * v.w,
+            x.w * v.x + y.w * v.y + z.w * v.z + w.w * v.w,
+        )
+    }
+
+    #[inline]
+    pub fn mul_mat4(&self, rhs: &Self) -> Self {
+        Self::from_cols(
+            self.mul_vec4(rhs.x_axis),
+            self.mul_vec4(rhs.y_axis),
+            self.mul_vec4(rhs.z_axis),
+            self.mul_vec4(rhs.w_axis),
+        )
+    }
+
+    /// Transforms a point, applying the translation but not the projection.
+    #[inline]
+    pub fn transform_point3(&self, point: Vec3<T>) -> Vec3<T> {
+        self.mul_vec4(point.extend(T::one())).into()
+    }
+
+    /// Transforms a direction, ignoring the translation.
+    #[inline]
+    pub fn transform_vector3(&self, vector: Vec3<T>) -> Vec3<T> {
+        self.mul_vec4(vector.extend(T::zero())).into()
+    }
+
+    /// Drops the last row of the matrix, see [`Mat3x4`].
+    #[inline]
+    pub fn to_mat3x4(&self) -> Mat3x4<T> {
+        (*self).into()
+    }
+}
+
+impl<T: VecTypeFloat> Mat4<T> {
+    /// Returns the inverse of the matrix, or `None` if it is not invertible.
+    pub fn inverse(&self) -> Option<Self> {
+        // Laplace expansion over pairs of columns, as the inverse of the transpose is the
+        // transpose of the inverse this works on columns just as well as it does on rows
+        let a: [[T; 4]; 4] = [
+            self.x_axis.into(),
+            self.y_axis.into(),
+            self.z_axis.into(),
+            self.w_axis.into(),
+        ];
+
+        let s0 = a[0][0] * a[1][1] - a[1][0] * a[0][1];
+        let s1 = a[0][0] * a[1][2] - a[1][0] * a[0][2];
+        let s2 = a[0][0] * a[1][3] - a[1][0] * a[0][3];
+        let s3 = a[0][1] * a[1][2] - a[1][1] * a[0][2];
+        let s4 = a[0][1] * a[1][3] - a[1][1] * a[0][3];
+        let s5 = a[0][2] * a[1][3] - a[1][2] * a[0][3];
+
+        let c0 = a[2][0] * a[3][1] - a[3][0] * a[2][1];
+        let c1 = a[2][0] * a[3][2] - a[3][0] * a[2][2];
+        let c2 = a[2][0] * a[3][3] - a[3][0] * a[2][3];
+        let c3 = a[2][1] * a[3][2] - a[3][1] * a[2][2];
+        let c4 = a[2][1] * a[3][3] - a[3][1] * a[2][3];
+        let c5 = a[2][2] * a[3][3] - a[3][2] * a[2][3];
+
+        let determinant = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+        if determinant == T::zero() {
+            return None;
+        }
+
+        let column = |x: T, y: T, z: T, w: T| {
+            Vec4::new(
+                x / determinant,
+                y / determinant,
+                z / determinant,
+                w / determinant,
+            )
+        };
+
+        Some(Self::from_cols(
+            column(
+                a[1][1] * c5 - a[1][2] * c4 + a[1][3] * c3,
+                -a[0][1] * c5 + a[0][2] * c4 - a[0][3] * c3,
+                a[3][1] * s5 - a[3][2] * s4 + a[3][3] * s3,
+                -a[2][1] * s5 + a[2][2] * s4 - a[2][3] * s3,
+            ),
+            column(
+                -a[1][0] * c5 + a[1][2] * c2 - a[1][3] * c1,
+                a[0][0] * c5 - a[0][2] * c2 + a[0][3] * c1,
+                -a[3][0] * s5 + a[3][2] * s2 - a[3][3] * s1,
+                a[2][0] * s5 - a[2][2] * s2 + a[2][3] * s1,
+            ),
+            column(
+                a[1][0] * c4 - a[1][1] * c2 + a[1][3] * c0,
+                -a[0][0] * c4 + a[0][1] * c2 - a[0][3] * c0,
+                a[3][0] * s4 - a[3][1] * s2 + a[3][3] * s0,
+                -a[2][0] * s4 + a[2][1] * s2 - a[2][3] * s0,
+            ),
+            column(
+                -a[1][0] * c3 + a[1][1] * c1 - a[1][2] * c0,
+                a[0][0] * c3 - a[0][1] * c1 + a[0][2] * c0,
+                -a[3][0] * s3 + a[3][1] * s1 - a[3][2] * s0,
+                a[2][0] * s3 - a[2][1] * s1 + a[2][2] * s0,
+            ),
+        ))
+    }
+}
+
+impl<T: VecType> Mul<Mat4<T>> for Mat4<T> {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        self.mul_mat4(&rhs)
+    }
+}
+
+impl<T: VecType> Mul<Vec4<T>> for Mat4<T> {
+    type Output = Vec4<T>;
+
+    #[inline]
+    fn mul(self, rhs: Vec4<T>) -> Vec4<T> {
+        self.mul_vec4(rhs)
+    }
+}
@@ -25,3 +25,154 @@ pub use vec3::*;
 
 mod vec4;
 pub use vec4::*;
+
+mod mat3;
+pub use mat3::*;
+
+mod mat3x4;
+pub use mat3x4::*;
+
+mod mat4;
+pub use mat4::*;
+
+mod quat;
+pub use quat::*;
+
+mod aabb;
+pub use aabb::*;
+
+#[cfg(feature = "glam")]
+mod glam;
+
+#[cfg(feature = "godot")]
+mod godot;
+
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::FRAC_PI_2, io::Cursor};
+
+    use binrw::BinReaderExt;
+
+    use super::{ops::VecLength, *};
+
+    fn close<const N: usize>(a: impl Into<[f32; N]>, b: impl Into<[f32; N]>) -> bool {
+        a.into()
+            .iter()
+            .zip(b.into())
+            .all(|(a, b)| (a - b).abs() < 1e-5)
+    }
+
+    fn rotation() -> Quat<f32> {
+        let axis = Vec3::new(1.0, 2.0, 3.0);
+        Quat::from_axis_angle(axis / axis.length(), 0.8)
+    }
+
+    #[test]
+    fn vec_length() {
+        assert_eq!(Vec2::new(3.0f32, 4.0).length(), 5.0);
+        assert_eq!(Vec3::new(2.0f32, 3.0, 6.0).length(), 7.0);
+        assert_eq!(Vec3::new(2.0f32, 3.0, 6.0).length_squared(), 49.0);
+        assert_eq!(Vec4::new(1.0f32, 1.0, 1.0, 1.0).length(), 2.0);
+    }
+
+    #[test]
+    fn mat3_inverse() {
+        let matrix = Mat3::from_quat(rotation()) * Mat3::from_scale(Vec3::new(2.0, 3.0, 4.0));
+        let inverse = matrix.inverse();
+        assert!(inverse.is_some_and(|inverse| close(inverse * matrix, Mat3::identity())));
+
+        let singular = Mat3::from_scale(Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(singular.inverse(), None);
+    }
+
+    #[test]
+    fn mat3x4_transform() {
+        let translation = Vec3::new(10.0, -5.0, 2.0);
+        let transform = Mat3x4::from_rotation_translation(rotation(), translation);
+        let point = Vec3::new(1.0, 1.0, 1.0);
+        assert!(close(
+            transform.transform_point3(point),
+            rotation() * point + translation
+        ));
+        assert!(close(
+            transform.transform_vector3(point),
+            rotation() * point
+        ));
+
+        let inverse = transform.inverse();
+        assert!(inverse.is_some_and(|inverse| close(inverse * transform, Mat3x4::identity())));
+        assert!(inverse.is_some_and(|inverse| {
+            close(
+                inverse.transform_point3(transform.transform_point3(point)),
+                point,
+            )
+        }));
+    }
+
+    #[test]
+    fn mat4_inverse() {
+        let transform = Mat3x4::from_rotation_translation(rotation(), Vec3::new(1.0, 2.0, 3.0));
+        let matrix = transform.to_mat4();
+        assert_eq!(matrix.to_mat3x4(), transform);
+
+        let inverse = matrix.inverse();
+        assert!(inverse.is_some_and(|inverse| close(inverse * matrix, Mat4::identity())));
+        assert!(inverse.is_some_and(|inverse| {
+            transform
+                .inverse()
+                .is_some_and(|expected| close(inverse.to_mat3x4(), expected))
+        }));
+    }
+
+    #[test]
+    fn quat_rotation() {
+        let quat = Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), FRAC_PI_2);
+        assert!(close(
+            quat * Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0)
+        ));
+
+        // A rotation and its negation are the same, so compare them by their dot product
+        let quat = rotation();
+        let result = Quat::from_mat3(&Mat3::from_quat(quat));
+        assert!((result.dot(quat).abs() - 1.0).abs() < 1e-5);
+
+        let inverse = quat.inverse();
+        assert!(inverse.is_some_and(|inverse| close(inverse * quat, Quat::identity())));
+    }
+
+    #[test]
+    fn aabb_bounds() {
+        let points = [
+            Vec3::new(1.0, -2.0, 0.0),
+            Vec3::new(-1.0, 4.0, 2.0),
+            Vec3::new(0.0, 0.0, -3.0),
+        ];
+        let aabb = Aabb::from_points(points);
+        let expected = Aabb::new(Vec3::new(-1.0, -2.0, -3.0), Vec3::new(1.0, 4.0, 2.0));
+        assert_eq!(aabb, Some(expected));
+        assert!(points.iter().all(|point| expected.contains(*point)));
+        assert_eq!(Aabb::<f32>::from_points([]), None);
+
+        let translation = Vec3::new(10.0, 0.0, 0.0);
+        let moved = expected.transform(&Mat3x4::from_translation(translation));
+        assert_eq!(
+            moved,
+            Aabb::new(expected.min + translation, expected.max + translation)
+        );
+    }
+
+    #[test]
+    fn read_column_major() -> binrw::BinResult<()> {
+        let values: [f32; 12] = std::array::from_fn(|i| i as f32);
+        let bytes = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let matrix: Mat3x4<f32> = Cursor::new(bytes).read_le()?;
+        assert_eq!(matrix, Mat3x4::from(values));
+        assert_eq!(matrix.translation(), Vec3::new(9.0, 10.0, 11.0));
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use std::ops::Mul;
+
+use binrw::binrw;
+
+use super::{Mat3, Vec3, Vec4, VecType, VecTypeFloat, ops::VecCross};
+
+/// A rotation, stored as `x`, `y`, `z` and `w` like the game does.
+#[binrw]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd)]
+pub struct Quat<T: VecType> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
+impl<T: VecType> Default for Quat<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<T: VecType> From<[T; 4]> for Quat<T> {
+    #[inline]
+    fn from(a: [T; 4]) -> Self {
+        Self::new(a[0], a[1], a[2], a[3])
+    }
+}
+
+impl<T: VecType> From<Quat<T>> for [T; 4] {
+    #[inline]
+    fn from(q: Quat<T>) -> Self {
+        [q.x, q.y, q.z, q.w]
+    }
+}
+
+impl<T: VecType> From<Vec4<T>> for Quat<T> {
+    #[inline]
+    fn from(v: Vec4<T>) -> Self {
+        Self::new(v.x, v.y, v.z, v.w)
+    }
+}
+
+impl<T: VecType> From<Quat<T>> for Vec4<T> {
+    #[inline]
+    fn from(q: Quat<T>) -> Self {
+        Self::new(q.x, q.y, q.z, q.w)
+    }
+}
+
+impl<T: VecType> Quat<T> {
+    #[inline]
+    pub const fn new(x: T, y: T, z: T, w: T) -> Self {
+        Self { x, y, z, w }
+    }
+
+    #[inline]
+    pub fn identity() -> Self {
+        Self::new(T::zero(), T::zero(), T::zero(), T::one())
+    }
+
+    #[inline]
+    pub fn xyz(self) -> Vec3<T> {
+        Vec3::new(self.x, self.y, self.z)
+    }
+}
+
+impl<T: VecTypeFloat> Quat<T> {
+    /// Creates a rotation of `angle` radians around a normalized `axis`.
+    pub fn from_axis_angle(axis: Vec3<T>, angle: T) -> Self {
+        let half = angle / (T::one() + T::one());
+        let (sin, cos) = half.sin_cos();
+        Self::new(axis.x * sin, axis.y * sin, axis.z * sin, cos)
+    }
+
+    /// Creates a rotation from a matrix without scale.
+    pub fn from_mat3(m: &Mat3<T>) -> Self {
+        let one = T::one();
+        let half = one / (one + one);
+        let (x, y, z) = (m.x_axis, m.y_axis, m.z_axis);
+        let trace = x.x + y.y + z.z;
+        if trace > T::zero() {
+            let s = (trace + one).sqrt() * (one + one);
+            Self::new(
+                (y.z - z.y) / s,
+                (z.x - x.z) / s,
+                (x.y - y.x) / s,
+                s * half * half,
+            )
+        } else if x.x > y.y && x.x > z.z {
+            let s = (one + x.x - y.y - z.z).sqrt() * (one + one);
+            Self::new(
+                s * half * half,
+                (y.x + x.y) / s,
+                (z.x + x.z) / s,
+                (y.z - z.y) / s,
+            )
+        } else if y.y > z.z {
+            let s = (one + y.y - x.x - z.z).sqrt() * (one + one);
+            Self::new(
+                (y.x + x.y) / s,
+                s * half * half,
+                (z.y + y.z) / s,
+                (z.x - x.z) / s,
+            )
+        } else {
+            let s = (one + z.z - x.x - y.y).sqrt() * (one + one);
+            Self::new(
+                (z.x + x.z) / s,
+                (z.y + y.z) / s,
+                s * half * half,
+                (x.y - y.x) / s,
+            )
+        }
+    }
+
+    #[inline]
+    pub fn dot(self, rhs: Self) -> T {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    #[inline]
+    pub fn conjugate(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    /// Returns the inverse rotation, or `None` if the quaternion has no length.
+    pub fn inverse(self) -> Option<Self> {
+        let length_squared = self.dot(self);
+        if length_squared == T::zero() {
+            return None;
+        }
+        let q = self.conjugate();
+        Some(Self::new(
+            q.x / length_squared,
+            q.y / length_squared,
+            q.z / length_squared,
+            q.w / length_squared,
+        ))
+    }
+
+    pub fn normalize(self) -> Self {
+        let length = self.dot(self).sqrt();
+        Self::new(
+            self.x / length,
+            self.y / length,
+            self.z / length,
+            self.w / length,
+        )
+    }
+
+    /// Rotates a vector, assuming the quaternion is normalized.
+    pub fn mul_vec3(self, v: Vec3<T>) -> Vec3<T> {
+        let two = T::one() + T::one();
+        let axis = self.xyz();
+        let t = axis.cross(v) * two;
+        v + t * self.w + axis.cross(t)
+    }
+}
+
+impl<T: VecTypeFloat> Mul<Quat<T>> for Quat<T> {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+impl<T: VecTypeFloat> Mul<Vec3<T>> for Quat<T> {
+    type Output = Vec3<T>;
+
+    #[inline]
+    fn mul(self, rhs: Vec3<T>) -> Vec3<T> {
+        self.mul_vec3(rhs)
+    }
+}
@@ -56,12 +56,12 @@ impl<T: VecType> Vec2<T> {
 impl<T: VecTypeFloat> VecLength<T> for Vec2<T> {
     #[inline]
     fn length(self) -> T {
-        self.x * self.x + self.y * self.y
+        (self.x * self.x + self.y * self.y).sqrt()
     }
 
     #[inline]
     fn length_squared(self) -> T {
-        (self.x * self.x + self.y * self.y).sqrt()
+        self.x * self.x + self.y * self.y
     }
 }
 
@@ -80,6 +80,18 @@ impl<T: VecType> Vec3<T> {
     }
 }
 
+impl<T: VecTypeFloat> Vec3<T> {
+    #[inline]
+    pub fn min(self, rhs: Self) -> Self {
+        Self::new(self.x.min(rhs.x), self.y.min(rhs.y), self.z.min(rhs.z))
+    }
+
+    #[inline]
+    pub fn max(self, rhs: Self) -> Self {
+        Self::new(self.x.max(rhs.x), self.y.max(rhs.y), self.z.max(rhs.z))
+    }
+}
+
 impl<T: VecType> Add<Vec3<T>> for Vec3<T> {
     type Output = Self;
 
@@ -135,12 +147,12 @@ impl<T: VecType> Div<T> for Vec3<T> {
 impl<T: VecTypeFloat> VecLength<T> for Vec3<T> {
     #[inline]
     fn length(self) -> T {
-        self.x * self.x + self.y * self.y + self.z * self.z
+        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
     }
 
     #[inline]
     fn length_squared(self) -> T {
-        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+        self.x * self.x + self.y * self.y + self.z * self.z
     }
 }
 
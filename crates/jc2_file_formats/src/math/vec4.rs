@@ -74,12 +74,12 @@ impl<T: VecType> Vec4<T> {
 impl<T: VecTypeFloat> VecLength<T> for Vec4<T> {
     #[inline]
     fn length(self) -> T {
-        self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w
+        (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt()
     }
 
     #[inline]
     fn length_squared(self) -> T {
-        (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt()
+        self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w
     }
 }
 
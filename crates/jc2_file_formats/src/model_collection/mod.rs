@@ -4,7 +4,10 @@ use binrw::{BinRead, BinWrite, binrw};
 use bitflags::bitflags;
 use jc2_hashing::HashString;
 
-use crate::{common::NullString, math::Vec3};
+use crate::{
+    common::NullString,
+    math::{Aabb, Mat4, Vec3},
+};
 
 #[binrw]
 #[derive(Clone, Debug)]
@@ -144,7 +147,7 @@ impl ModelCollectionVersion {
 pub struct ModelCollectionInstances {
     #[br(count(count))]
     #[bw(assert(transforms.len() as u32 == count))]
-    pub transforms: Vec<[f32; 4 * 4]>,
+    pub transforms: Vec<Mat4<f32>>,
     #[br(count(count))]
     #[bw(assert(models.len() as u32 == count))]
     pub models: Vec<u16>,
@@ -159,7 +162,7 @@ pub struct ModelCollectionInstances {
     #[br(count(count))]
     #[bw(assert(bounds.len() as u32 == count))]
     #[brw(if(version.greater(ModelCollectionVersion::V6)))]
-    pub bounds: Vec<(Vec3<f32>, Vec3<f32>)>,
+    pub bounds: Vec<Aabb<f32>>,
 }
 
 bitflags! {
@@ -204,9 +207,9 @@ pub struct VegetationInstance {
 
 #[derive(Clone, Debug)]
 pub struct ModelInstance<'a> {
-    pub transform: &'a [f32; 4 * 4],
+    pub transform: &'a Mat4<f32>,
     pub model_index: u16,
     pub lod: Option<&'a (Vec3<f32>, f32)>,
     pub flags: Option<ModelInstanceFlags>,
-    pub bounds: Option<&'a (Vec3<f32>, Vec3<f32>)>,
+    pub bounds: Option<&'a Aabb<f32>>,
 }
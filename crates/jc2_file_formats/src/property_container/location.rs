@@ -0,0 +1,374 @@
+//! Typed schemas for the objects found in location files (`.blo`, `.bl` and `.epe`).
+//!
+//! Only keys with a well understood meaning are typed, and most are optional since their presence
+//! varies between files. Schemas borrow the container they were read from, so every other key,
+//! including nested child objects, stays within reach and converting back to a container keeps
+//! them in their original order.
+
+use crate::math::{Mat3x4, Vec3};
+
+use super::{
+    FromPropertyContainer, IntoPropertyContainer, PropertyContainer, PropertyContainerResult,
+};
+
+/// An object with a model, such as `CDamageableObject` or `CPlantedTree`.
+#[derive(Clone, Copy, Debug, PartialEq, FromPropertyContainer, IntoPropertyContainer)]
+pub struct ModelObject<'a> {
+    #[property(name = "_class")]
+    pub class: &'a str,
+    pub world: Option<Mat3x4<f32>>,
+    pub filename: Option<&'a str>,
+    #[property(source)]
+    pub source: &'a PropertyContainer,
+}
+
+impl ModelObject<'_> {
+    pub const CLASSES: [&'static str; 4] = [
+        "CDamageableObject",
+        "CLandmark",
+        "CPlantedTree",
+        "CSimpleRigidObject",
+    ];
+}
+
+/// A light source.
+#[derive(Clone, Copy, Debug, PartialEq, FromPropertyContainer, IntoPropertyContainer)]
+pub struct Light<'a> {
+    #[property(name = "_class")]
+    pub class: &'a str,
+    pub world: Option<Mat3x4<f32>>,
+    pub color: Option<Vec3<f32>>,
+    pub range: Option<f32>,
+    pub intensity: Option<f32>,
+    #[property(source)]
+    pub source: &'a PropertyContainer,
+}
+
+impl Light<'_> {
+    pub const CLASSES: [&'static str; 1] = ["CLight"];
+}
+
+/// A location characters or vehicles are spawned at.
+#[derive(Clone, Copy, Debug, PartialEq, FromPropertyContainer, IntoPropertyContainer)]
+pub struct SpawnPoint<'a> {
+    #[property(name = "_class")]
+    pub class: &'a str,
+    pub world: Option<Mat3x4<f32>>,
+    pub spawn_type: Option<&'a str>,
+    #[property(source)]
+    pub source: &'a PropertyContainer,
+}
+
+impl SpawnPoint<'_> {
+    pub const CLASSES: [&'static str; 1] = ["CSpawnPoint"];
+}
+
+/// A volume that fires events when entered.
+#[derive(Clone, Copy, Debug, PartialEq, FromPropertyContainer, IntoPropertyContainer)]
+pub struct Trigger<'a> {
+    #[property(name = "_class")]
+    pub class: &'a str,
+    pub world: Option<Mat3x4<f32>>,
+    pub size: Option<Vec3<f32>>,
+    #[property(source)]
+    pub source: &'a PropertyContainer,
+}
+
+impl Trigger<'_> {
+    pub const CLASSES: [&'static str; 1] = ["CTrigger"];
+}
+
+/// A placed vehicle, with `filename` pointing at its `.ee` archive.
+#[derive(Clone, Copy, Debug, PartialEq, FromPropertyContainer, IntoPropertyContainer)]
+pub struct Vehicle<'a> {
+    #[property(name = "_class")]
+    pub class: &'a str,
+    pub world: Option<Mat3x4<f32>>,
+    pub filename: Option<&'a str>,
+    #[property(source)]
+    pub source: &'a PropertyContainer,
+}
+
+impl Vehicle<'_> {
+    pub const CLASSES: [&'static str; 1] = ["CVehicle"];
+}
+
+/// A particle or sound effect placed in the world, with `filename` pointing at its effect.
+#[derive(Clone, Copy, Debug, PartialEq, FromPropertyContainer, IntoPropertyContainer)]
+pub struct EffectEmitter<'a> {
+    #[property(name = "_class")]
+    pub class: &'a str,
+    pub world: Option<Mat3x4<f32>>,
+    pub filename: Option<&'a str>,
+    #[property(source)]
+    pub source: &'a PropertyContainer,
+}
+
+impl EffectEmitter<'_> {
+    pub const CLASSES: [&'static str; 1] = ["CEffect"];
+}
+
+/// Any object in a location file, selected by its `_class`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LocationObject<'a> {
+    Model(ModelObject<'a>),
+    Light(Light<'a>),
+    SpawnPoint(SpawnPoint<'a>),
+    Trigger(Trigger<'a>),
+    Vehicle(Vehicle<'a>),
+    EffectEmitter(EffectEmitter<'a>),
+    /// A container with an unrecognised or missing `_class`.
+    Unknown(&'a PropertyContainer),
+}
+
+impl<'a> LocationObject<'a> {
+    pub const MODEL_CLASSES: [&'static str; 4] = ModelObject::CLASSES;
+
+    pub fn from_container(container: &'a PropertyContainer) -> PropertyContainerResult<Self> {
+        let Some(class) = container.get_value::<&str>("_class") else {
+            return Ok(Self::Unknown(container));
+        };
+
+        Ok(if ModelObject::CLASSES.contains(&class) {
+            Self::Model(ModelObject::from_property_container(container)?)
+        } else if Light::CLASSES.contains(&class) {
+            Self::Light(Light::from_property_container(container)?)
+        } else if SpawnPoint::CLASSES.contains(&class) {
+            Self::SpawnPoint(SpawnPoint::from_property_container(container)?)
+        } else if Trigger::CLASSES.contains(&class) {
+            Self::Trigger(Trigger::from_property_container(container)?)
+        } else if Vehicle::CLASSES.contains(&class) {
+            Self::Vehicle(Vehicle::from_property_container(container)?)
+        } else if EffectEmitter::CLASSES.contains(&class) {
+            Self::EffectEmitter(EffectEmitter::from_property_container(container)?)
+        } else {
+            Self::Unknown(container)
+        })
+    }
+
+    /// Returns the container the object was read from.
+    pub fn container(&self) -> &'a PropertyContainer {
+        match self {
+            Self::Model(object) => object.source,
+            Self::Light(object) => object.source,
+            Self::SpawnPoint(object) => object.source,
+            Self::Trigger(object) => object.source,
+            Self::Vehicle(object) => object.source,
+            Self::EffectEmitter(object) => object.source,
+            Self::Unknown(container) => container,
+        }
+    }
+
+    pub fn class(&self) -> Option<&'a str> {
+        match self {
+            Self::Model(object) => Some(object.class),
+            Self::Light(object) => Some(object.class),
+            Self::SpawnPoint(object) => Some(object.class),
+            Self::Trigger(object) => Some(object.class),
+            Self::Vehicle(object) => Some(object.class),
+            Self::EffectEmitter(object) => Some(object.class),
+            Self::Unknown(container) => container.get_value("_class"),
+        }
+    }
+
+    pub fn world(&self) -> Option<Mat3x4<f32>> {
+        match self {
+            Self::Model(object) => object.world,
+            Self::Light(object) => object.world,
+            Self::SpawnPoint(object) => object.world,
+            Self::Trigger(object) => object.world,
+            Self::Vehicle(object) => object.world,
+            Self::EffectEmitter(object) => object.world,
+            Self::Unknown(container) => container.get_value("world"),
+        }
+    }
+
+    /// Returns the resource the object loads, if it has one.
+    pub fn filename(&self) -> Option<&'a str> {
+        match self {
+            Self::Model(ModelObject { filename, .. })
+            | Self::Vehicle(Vehicle { filename, .. })
+            | Self::EffectEmitter(EffectEmitter { filename, .. }) => *filename,
+            Self::Light(_) | Self::SpawnPoint(_) | Self::Trigger(_) | Self::Unknown(_) => None,
+        }
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = &'a PropertyContainer> {
+        self.container().containers()
+    }
+}
+
+impl<'a> FromPropertyContainer<'a> for LocationObject<'a> {
+    fn from_property_container(container: &'a PropertyContainer) -> PropertyContainerResult<Self> {
+        Self::from_container(container)
+    }
+}
+
+impl IntoPropertyContainer for LocationObject<'_> {
+    fn into_property_container(self) -> PropertyContainer {
+        match self {
+            Self::Model(object) => object.into_property_container(),
+            Self::Light(object) => object.into_property_container(),
+            Self::SpawnPoint(object) => object.into_property_container(),
+            Self::Trigger(object) => object.into_property_container(),
+            Self::Vehicle(object) => object.into_property_container(),
+            Self::EffectEmitter(object) => object.into_property_container(),
+            Self::Unknown(container) => container.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property_container::PropertyValue;
+
+    /// Builds an object laid out as location files store them, with the class first and a
+    /// child object and an unhashed key after the typed ones.
+    fn object(class: &str, values: &[(&str, PropertyValue)]) -> PropertyContainer {
+        let mut child = PropertyContainer::new();
+        child.insert_value("_class", "CDamageableObject");
+
+        let mut container = PropertyContainer::new();
+        container.insert_value("_class", class);
+        container.insert_value("world", Mat3x4::<f32>::identity());
+        for (key, value) in values {
+            container.insert_value(*key, value.clone());
+        }
+        container.insert_value(0x1234_5678, 1);
+        container.insert("child", child);
+        container
+    }
+
+    fn keys(container: &PropertyContainer) -> Vec<jc2_hashing::HashString> {
+        container.iter().map(|(hash, _)| *hash).collect()
+    }
+
+    /// Checks that the object is read as the expected kind, and converts back unchanged.
+    fn round_trip(
+        container: &PropertyContainer,
+        matches: fn(&LocationObject<'_>) -> bool,
+    ) -> PropertyContainerResult<()> {
+        let object = LocationObject::from_container(container)?;
+        assert!(matches(&object), "{object:?}");
+        assert_eq!(object.world(), Some(Mat3x4::identity()));
+        assert_eq!(object.children().count(), 1);
+
+        let result = object.into_property_container();
+        assert_eq!(&result, container);
+        assert_eq!(keys(&result), keys(container));
+        Ok(())
+    }
+
+    #[test]
+    fn model_object() -> PropertyContainerResult<()> {
+        let container = object(
+            "CPlantedTree",
+            &[(
+                "filename",
+                "models/characters/maincharacters/rico/mc01-rico.lod".into(),
+            )],
+        );
+        round_trip(&container, |object| {
+            matches!(
+                object,
+                LocationObject::Model(ModelObject {
+                    filename: Some(_),
+                    ..
+                })
+            )
+        })
+    }
+
+    #[test]
+    fn light() -> PropertyContainerResult<()> {
+        let container = object(
+            "CLight",
+            &[
+                ("color", Vec3::new(1.0, 0.8, 0.6).into()),
+                ("range", 12.0f32.into()),
+                ("intensity", 2.5f32.into()),
+            ],
+        );
+        round_trip(&container, |object| {
+            matches!(
+                object,
+                LocationObject::Light(Light {
+                    range: Some(12.0),
+                    intensity: Some(2.5),
+                    ..
+                })
+            )
+        })
+    }
+
+    #[test]
+    fn spawn_point() -> PropertyContainerResult<()> {
+        let container = object("CSpawnPoint", &[("spawn_type", "vehicle".into())]);
+        round_trip(&container, |object| {
+            matches!(
+                object,
+                LocationObject::SpawnPoint(SpawnPoint {
+                    spawn_type: Some("vehicle"),
+                    ..
+                })
+            )
+        })
+    }
+
+    #[test]
+    fn trigger() -> PropertyContainerResult<()> {
+        let container = object("CTrigger", &[("size", Vec3::new(4.0, 2.0, 4.0).into())]);
+        round_trip(&container, |object| {
+            matches!(
+                object,
+                LocationObject::Trigger(Trigger { size: Some(_), .. })
+            )
+        })
+    }
+
+    #[test]
+    fn vehicle() -> PropertyContainerResult<()> {
+        let container = object(
+            "CVehicle",
+            &[(
+                "filename",
+                "exported/vehicles/arve/arve.v009_civil_helicopter.ee".into(),
+            )],
+        );
+        round_trip(&container, |object| {
+            matches!(
+                object,
+                LocationObject::Vehicle(Vehicle {
+                    filename: Some(_),
+                    ..
+                })
+            )
+        })
+    }
+
+    #[test]
+    fn effect_emitter() -> PropertyContainerResult<()> {
+        let container = object(
+            "CEffect",
+            &[("filename", "effects/env_casino_fire_light.bin".into())],
+        );
+        round_trip(&container, |object| {
+            matches!(
+                object,
+                LocationObject::EffectEmitter(EffectEmitter {
+                    filename: Some(_),
+                    ..
+                })
+            )
+        })
+    }
+
+    #[test]
+    fn unknown_object() -> PropertyContainerResult<()> {
+        let container = object("CStaticDecal", &[]);
+        round_trip(&container, |object| {
+            matches!(object, LocationObject::Unknown(_))
+        })
+    }
+}
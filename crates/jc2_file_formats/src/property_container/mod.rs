@@ -6,3 +6,136 @@ pub use property_block_file::*;
 
 pub mod property_file;
 pub use property_file::*;
+
+pub mod property_diff;
+pub use property_diff::*;
+
+pub mod property_path;
+pub use property_path::*;
+
+#[cfg(feature = "derive")]
+pub mod location;
+
+#[cfg(any(feature = "json", feature = "xml"))]
+pub mod text;
+
+#[cfg(feature = "derive")]
+pub use jc2_file_formats_derive::{FromPropertyContainer, IntoPropertyContainer};
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use jc2_hashing::HashString;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, FromPropertyContainer, IntoPropertyContainer)]
+    struct Child {
+        value: i32,
+    }
+
+    #[derive(Debug, PartialEq, FromPropertyContainer, IntoPropertyContainer)]
+    struct Typed {
+        #[property(name = "_class")]
+        class: String,
+        #[property(id = 0x1234_5678)]
+        hashed: f32,
+        #[property(default)]
+        count: i32,
+        #[property(container)]
+        child: Child,
+        #[property(skip)]
+        skipped: i32,
+        name: Option<String>,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, FromPropertyContainer, IntoPropertyContainer)]
+    struct Borrowed<'a> {
+        value: Option<i32>,
+        #[property(source)]
+        source: &'a PropertyContainer,
+    }
+
+    fn container() -> PropertyContainer {
+        let mut child = PropertyContainer::new();
+        child.insert_value("value", 2);
+
+        let mut container = PropertyContainer::new();
+        container.insert_value("_class", "CTest");
+        container.insert_value(0x1234_5678, 1.5f32);
+        container.insert("child", child);
+        container.insert_value("skipped", 3);
+        container
+    }
+
+    #[test]
+    fn read_attributes() -> PropertyContainerResult<()> {
+        let typed = Typed::from_property_container(&container())?;
+        assert_eq!(
+            typed,
+            Typed {
+                class: "CTest".into(),
+                hashed: 1.5,
+                count: 0,
+                child: Child { value: 2 },
+                skipped: 0,
+                name: None,
+            }
+        );
+
+        let mut container = container();
+        container.insert_value("name", 4);
+        assert_eq!(Typed::from_property_container(&container)?.name, None);
+        container.insert_value("count", "4");
+        assert!(matches!(
+            Typed::from_property_container(&container),
+            Err(PropertyContainerError::InvalidType("count"))
+        ));
+        container.insert_value("count", 4);
+        container.remove("child");
+        assert!(matches!(
+            Typed::from_property_container(&container),
+            Err(PropertyContainerError::MissingKey("child"))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn write_attributes() {
+        let typed = Typed {
+            class: "CTest".into(),
+            hashed: 1.5,
+            count: 5,
+            child: Child { value: 2 },
+            skipped: 3,
+            name: None,
+        };
+
+        let mut expected = container();
+        expected.remove("skipped");
+        expected.insert_value("count", 5);
+        assert_eq!(typed.into_property_container(), expected);
+    }
+
+    #[test]
+    fn write_over_source() -> PropertyContainerResult<()> {
+        let mut source = container();
+        source.insert_value("value", 1);
+
+        let mut borrowed = Borrowed::from_property_container(&source)?;
+        assert_eq!(borrowed.value, Some(1));
+
+        borrowed.value = Some(6);
+        let result = borrowed.into_property_container();
+        let keys = result.iter().map(|(hash, _)| *hash).collect::<Vec<_>>();
+        let expected = source.iter().map(|(hash, _)| *hash).collect::<Vec<_>>();
+        assert_eq!(keys, expected);
+        assert_eq!(result.get_value::<i32>("value"), Some(6));
+
+        borrowed.value = None;
+        let result = borrowed.into_property_container();
+        assert!(!result.contains("value"));
+        assert_eq!(result.len(), source.len() - 1);
+        assert!(result.contains(HashString::new(0x1234_5678)));
+        Ok(())
+    }
+}
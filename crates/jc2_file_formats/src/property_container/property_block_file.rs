@@ -6,10 +6,13 @@ use jc2_hashing::HashString;
 use crate::{
     BinReadWrite,
     common::{LengthVec, NullString},
-    math::{Vec2, Vec3, Vec4},
+    math::{Mat3, Mat3x4, Vec2, Vec3, Vec4},
 };
 
-use super::PropertyValue;
+use super::{
+    PropertyContainer, PropertyContainerError, PropertyContainerResult, PropertyEntry,
+    PropertyValue,
+};
 
 #[binrw]
 #[derive(Clone, Default, Debug)]
@@ -19,6 +22,18 @@ pub struct PropertyBlockFile(
     pub Vec<PropertyBlockContainer>,
 );
 
+impl TryFrom<Vec<PropertyContainer>> for PropertyBlockFile {
+    type Error = PropertyContainerError;
+
+    fn try_from(value: Vec<PropertyContainer>) -> PropertyContainerResult<Self> {
+        value
+            .into_iter()
+            .map(PropertyBlockContainer::try_from)
+            .collect::<PropertyContainerResult<_>>()
+            .map(Self)
+    }
+}
+
 impl PropertyBlockFile {
     const MAGIC: [u8; 4] = *b"PCBB";
     const DATA_OFFSET: u64 = 8;
@@ -42,7 +57,11 @@ impl PropertyBlockFile {
                 });
             }
 
+            // Read the container, then skip past it using the size, as nodes may be out of order
+            let size = u32::read_options(reader, endian, ())?;
+            let end = reader.stream_position()? + size as u64;
             result.push(PropertyBlockContainer::read_options(reader, endian, ())?);
+            reader.seek(std::io::SeekFrom::Start(end))?;
         }
 
         Ok(result)
@@ -161,6 +180,23 @@ impl AsMut<[PropertyBlockNode]> for PropertyBlockContainer {
     }
 }
 
+impl TryFrom<PropertyContainer> for PropertyBlockContainer {
+    type Error = PropertyContainerError;
+
+    fn try_from(value: PropertyContainer) -> PropertyContainerResult<Self> {
+        value
+            .into_iter()
+            .map(|(hash, entry)| {
+                Ok(PropertyBlockNode {
+                    hash,
+                    value: entry.try_into()?,
+                })
+            })
+            .collect::<PropertyContainerResult<_>>()
+            .map(Self)
+    }
+}
+
 impl From<PropertyBlockContainer> for Vec<PropertyBlockNode> {
     #[inline]
     fn from(value: PropertyBlockContainer) -> Self {
@@ -262,6 +298,19 @@ impl PropertyBlockNodeValue {
     }
 }
 
+impl TryFrom<PropertyEntry> for PropertyBlockNodeValue {
+    type Error = PropertyContainerError;
+
+    fn try_from(value: PropertyEntry) -> PropertyContainerResult<Self> {
+        Ok(match value {
+            PropertyEntry::Container(container) => {
+                PropertyBlockContainer::try_from(container)?.into()
+            }
+            PropertyEntry::Value(value) => PropertyBlockValue::try_from(value)?.into(),
+        })
+    }
+}
+
 impl From<PropertyBlockContainer> for PropertyBlockNodeValue {
     fn from(value: PropertyBlockContainer) -> Self {
         PropertyBlockNodeValue::Container(value.into())
@@ -293,19 +342,23 @@ pub enum PropertyBlockValue {
     #[brw(magic = 6u32)]
     Vec4(PropertyBlockPointer<Vec4<f32>>),
     #[brw(magic = 7u32)]
-    Mat3x3(PropertyBlockPointer<[f32; 9]>),
+    Mat3x3(PropertyBlockPointer<Mat3<f32>>),
     #[brw(magic = 8u32)]
-    Mat3x4(PropertyBlockPointer<[f32; 12]>),
+    Mat3x4(PropertyBlockPointer<Mat3x4<f32>>),
     #[brw(magic = 9u32)]
     VecI32(PropertyBlockPointer<LengthVec<i32, u32, true>>),
     #[brw(magic = 10u32)]
     VecF32(PropertyBlockPointer<LengthVec<f32, u32, true>>),
 }
 
-impl From<PropertyValue> for PropertyBlockValue {
-    fn from(value: PropertyValue) -> Self {
-        match value {
+impl TryFrom<PropertyValue> for PropertyBlockValue {
+    type Error = PropertyContainerError;
+
+    fn try_from(value: PropertyValue) -> PropertyContainerResult<Self> {
+        Ok(match value {
             PropertyValue::Empty => Self::Empty,
+            // Block files have no way to store raw payloads
+            PropertyValue::Raw(_) => return Err(PropertyContainerError::RawValue),
             PropertyValue::I32(value) => Self::I32(value),
             PropertyValue::F32(value) => Self::F32(value),
             PropertyValue::String(value) => Self::String(value.into()),
@@ -316,7 +369,7 @@ impl From<PropertyValue> for PropertyBlockValue {
             PropertyValue::Mat3x4(value) => Self::Mat3x4(value.into()),
             PropertyValue::VecI32(value) => Self::VecI32(value.into()),
             PropertyValue::VecF32(value) => Self::VecF32(value.into()),
-        }
+        })
     }
 }
 
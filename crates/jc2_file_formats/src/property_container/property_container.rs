@@ -1,9 +1,9 @@
-use std::collections::HashMap;
-
+use indexmap::IndexMap;
 use jc2_hashing::HashString;
+use thiserror::Error;
 
 use crate::{
-    math::{Vec2, Vec3, Vec4},
+    math::{Mat3, Mat3x4, Vec2, Vec3, Vec4},
     property_container::PropertyFileContainer,
 };
 
@@ -12,20 +12,67 @@ use super::{
     PropertyFile, PropertyFileSection, PropertyFileValue,
 };
 
+/// A tree of properties keyed by hash, preserving the order entries were inserted in.
 #[derive(Clone, Default, Debug, PartialEq)]
-pub struct PropertyContainer(HashMap<HashString, PropertyEntry>);
+pub struct PropertyContainer {
+    entries: IndexMap<HashString, PropertyEntry>,
+    raw: bool,
+}
 
 impl PropertyContainer {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Returns whether the container was decoded from a raw section of a [`PropertyFile`], and
+    /// is written back as one wherever it is stored.
+    pub fn is_raw(&self) -> bool {
+        self.raw
+    }
+
+    pub fn set_raw(&mut self, raw: bool) {
+        self.raw = raw;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> indexmap::map::Iter<'_, HashString, PropertyEntry> {
+        self.entries.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> indexmap::map::IterMut<'_, HashString, PropertyEntry> {
+        self.entries.iter_mut()
+    }
+
+    pub fn contains(&self, hash: impl Into<HashString>) -> bool {
+        self.entries.contains_key(&hash.into())
+    }
+
+    pub fn get_mut(&mut self, hash: impl Into<HashString>) -> Option<&mut PropertyEntry> {
+        self.entries.get_mut(&hash.into())
+    }
+
+    /// Removes an entry, shifting all following entries to preserve order.
+    pub fn remove(&mut self, hash: impl Into<HashString>) -> Option<PropertyEntry> {
+        self.entries.shift_remove(&hash.into())
+    }
+
+    pub fn retain(&mut self, f: impl FnMut(&HashString, &mut PropertyEntry) -> bool) {
+        self.entries.retain(f);
+    }
+
     pub fn insert(&mut self, hash: impl Into<HashString>, value: impl Into<PropertyEntry>) {
-        self.0.insert(hash.into(), value.into());
+        self.entries.insert(hash.into(), value.into());
     }
 
     pub fn get(&self, hash: impl Into<HashString>) -> Option<&PropertyEntry> {
-        self.0.get(&hash.into())
+        self.entries.get(&hash.into())
     }
 
     pub fn insert_container(
@@ -33,46 +80,50 @@ impl PropertyContainer {
         hash: impl Into<HashString>,
         value: impl Into<PropertyContainer>,
     ) {
-        self.0.insert(hash.into(), value.into().into());
+        self.entries.insert(hash.into(), value.into().into());
     }
 
     pub fn get_container(&self, hash: impl Into<HashString>) -> Option<&PropertyContainer> {
-        self.0.get(&hash.into()).and_then(|entry| match entry {
-            PropertyEntry::Container(container) => Some(container),
-            _ => None,
-        })
+        self.entries
+            .get(&hash.into())
+            .and_then(|entry| match entry {
+                PropertyEntry::Container(container) => Some(container),
+                _ => None,
+            })
     }
 
     pub fn containers<'a>(&'a self) -> FilterPropertyContainerValues<'a, &PropertyContainer> {
-        self.0.values().filter_map(|entry| match entry {
+        self.entries.values().filter_map(|entry| match entry {
             PropertyEntry::Container(container) => Some(container),
             _ => None,
         })
     }
 
     pub fn keyed_containers<'a>(&'a self) -> FilterPropertyContainerIter<'a, &PropertyContainer> {
-        self.0.iter().filter_map(|entry| match entry.1 {
+        self.entries.iter().filter_map(|entry| match entry.1 {
             PropertyEntry::Container(container) => Some((entry.0, container)),
             _ => None,
         })
     }
 
     pub fn insert_value(&mut self, hash: impl Into<HashString>, value: impl Into<PropertyValue>) {
-        self.0.insert(hash.into(), value.into().into());
+        self.entries.insert(hash.into(), value.into().into());
     }
 
     pub fn get_value<'a, T: FromPropertyValue<'a>>(
         &'a self,
         hash: impl Into<HashString>,
     ) -> Option<T> {
-        self.0.get(&hash.into()).and_then(|entry| match entry {
-            PropertyEntry::Value(value) => T::from_property_value(value),
-            _ => None,
-        })
+        self.entries
+            .get(&hash.into())
+            .and_then(|entry| match entry {
+                PropertyEntry::Value(value) => T::from_property_value(value),
+                _ => None,
+            })
     }
 
     pub fn values<'a>(&'a self) -> FilterPropertyContainerValues<'a, &PropertyValue> {
-        self.0.values().filter_map(|entry| match entry {
+        self.entries.values().filter_map(|entry| match entry {
             PropertyEntry::Value(value) => Some(value),
             _ => None,
         })
@@ -81,14 +132,14 @@ impl PropertyContainer {
     pub fn values_filtered<'a, T: FromPropertyValue<'a>>(
         &'a self,
     ) -> FilterPropertyContainerValues<'a, T> {
-        self.0.values().filter_map(|entry| match entry {
+        self.entries.values().filter_map(|entry| match entry {
             PropertyEntry::Value(value) => T::from_property_value(value),
             _ => None,
         })
     }
 
     pub fn keyed_values<'a>(&'a self) -> FilterPropertyContainerIter<'a, &PropertyValue> {
-        self.0.iter().filter_map(|entry| match entry.1 {
+        self.entries.iter().filter_map(|entry| match entry.1 {
             PropertyEntry::Value(value) => Some((entry.0, value)),
             _ => None,
         })
@@ -97,7 +148,7 @@ impl PropertyContainer {
     pub fn keyed_valued_filtered<'a, T: FromPropertyValue<'a>>(
         &'a self,
     ) -> FilterPropertyContainerIter<'a, T> {
-        self.0.iter().filter_map(|entry| match entry.1 {
+        self.entries.iter().filter_map(|entry| match entry.1 {
             PropertyEntry::Value(value) => T::from_property_value(value).map(|t| (entry.0, t)),
             _ => None,
         })
@@ -105,18 +156,46 @@ impl PropertyContainer {
 }
 
 type FilterMapValues<'a, K, V, R> =
-    std::iter::FilterMap<std::collections::hash_map::Values<'a, K, V>, fn(&'a V) -> Option<R>>;
+    std::iter::FilterMap<indexmap::map::Values<'a, K, V>, fn(&'a V) -> Option<R>>;
 type FilterPropertyContainerValues<'a, T> = FilterMapValues<'a, HashString, PropertyEntry, T>;
 
-type FilterMapIter<'a, K, V, R> = std::iter::FilterMap<
-    std::collections::hash_map::Iter<'a, K, V>,
-    fn((&'a K, &'a V)) -> Option<(&'a K, R)>,
->;
+type FilterMapIter<'a, K, V, R> =
+    std::iter::FilterMap<indexmap::map::Iter<'a, K, V>, fn((&'a K, &'a V)) -> Option<(&'a K, R)>>;
 type FilterPropertyContainerIter<'a, T> = FilterMapIter<'a, HashString, PropertyEntry, T>;
 
-impl<T: Sized + Into<HashMap<HashString, PropertyEntry>>> From<T> for PropertyContainer {
+impl<T: Sized + Into<IndexMap<HashString, PropertyEntry>>> From<T> for PropertyContainer {
     fn from(value: T) -> Self {
-        Self(value.into())
+        Self {
+            entries: value.into(),
+            raw: false,
+        }
+    }
+}
+
+impl IntoIterator for PropertyContainer {
+    type Item = (HashString, PropertyEntry);
+    type IntoIter = indexmap::map::IntoIter<HashString, PropertyEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PropertyContainer {
+    type Item = (&'a HashString, &'a PropertyEntry);
+    type IntoIter = indexmap::map::Iter<'a, HashString, PropertyEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut PropertyContainer {
+    type Item = (&'a HashString, &'a mut PropertyEntry);
+    type IntoIter = indexmap::map::IterMut<'a, HashString, PropertyEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
@@ -128,7 +207,7 @@ impl From<PropertyBlockFile> for Vec<PropertyContainer> {
 
 impl From<PropertyBlockContainer> for PropertyContainer {
     fn from(value: PropertyBlockContainer) -> Self {
-        let mut result = HashMap::<HashString, PropertyEntry>::new();
+        let mut result = IndexMap::<HashString, PropertyEntry>::new();
         for node in value.0.into_iter() {
             match node.value {
                 PropertyBlockNodeValue::Empty => {}
@@ -140,7 +219,7 @@ impl From<PropertyBlockContainer> for PropertyContainer {
                 }
             }
         }
-        Self(result)
+        result.into()
     }
 }
 
@@ -152,7 +231,8 @@ impl From<PropertyFile> for Vec<PropertyContainer> {
 
 impl From<PropertyFileContainer> for PropertyContainer {
     fn from(value: PropertyFileContainer) -> Self {
-        let mut result = HashMap::<HashString, PropertyEntry>::new();
+        let mut result = IndexMap::<HashString, PropertyEntry>::new();
+        let mut raw_index = 0;
         for section in value.0.into_iter() {
             match section {
                 PropertyFileSection::Container(containers) => {
@@ -175,10 +255,17 @@ impl From<PropertyFileContainer> for PropertyContainer {
                         result.insert(key, value.into());
                     }
                 }
-                _ => {}
+                PropertyFileSection::Raw(bytes) => {
+                    result.insert(
+                        PropertyFileSection::raw_key(raw_index),
+                        PropertyFileSection::decode_raw(bytes.value),
+                    );
+                    raw_index += 1;
+                }
+                PropertyFileSection::Empty(_) => {}
             }
         }
-        Self(result)
+        result.into()
     }
 }
 
@@ -209,10 +296,12 @@ pub enum PropertyValue {
     Vec2(Vec2<f32>),
     Vec3(Vec3<f32>),
     Vec4(Vec4<f32>),
-    Mat3x3([f32; 9]),
-    Mat3x4([f32; 12]),
+    Mat3x3(Mat3<f32>),
+    Mat3x4(Mat3x4<f32>),
     VecI32(Vec<i32>),
     VecF32(Vec<f32>),
+    /// The payload of a raw section in a [`PropertyFile`].
+    Raw(Vec<u8>),
 }
 
 impl From<PropertyBlockValue> for PropertyValue {
@@ -313,12 +402,24 @@ impl From<Vec4<f32>> for PropertyValue {
 
 impl From<[f32; 9]> for PropertyValue {
     fn from(value: [f32; 9]) -> Self {
-        PropertyValue::Mat3x3(value)
+        PropertyValue::Mat3x3(value.into())
     }
 }
 
 impl From<[f32; 12]> for PropertyValue {
     fn from(value: [f32; 12]) -> Self {
+        PropertyValue::Mat3x4(value.into())
+    }
+}
+
+impl From<Mat3<f32>> for PropertyValue {
+    fn from(value: Mat3<f32>) -> Self {
+        PropertyValue::Mat3x3(value)
+    }
+}
+
+impl From<Mat3x4<f32>> for PropertyValue {
+    fn from(value: Mat3x4<f32>) -> Self {
         PropertyValue::Mat3x4(value)
     }
 }
@@ -347,6 +448,18 @@ impl From<Vec<f32>> for PropertyValue {
     }
 }
 
+impl From<&[u8]> for PropertyValue {
+    fn from(value: &[u8]) -> Self {
+        PropertyValue::Raw(value.into())
+    }
+}
+
+impl From<Vec<u8>> for PropertyValue {
+    fn from(value: Vec<u8>) -> Self {
+        PropertyValue::Raw(value)
+    }
+}
+
 pub trait FromPropertyValue<'a>
 where
     Self: Sized,
@@ -354,6 +467,45 @@ where
     fn from_property_value(value: &'a PropertyValue) -> Option<Self>;
 }
 
+/// Converts a container into a typed struct, usually implemented with
+/// `#[derive(FromPropertyContainer)]`.
+pub trait FromPropertyContainer<'a>
+where
+    Self: Sized,
+{
+    fn from_property_container(container: &'a PropertyContainer) -> PropertyContainerResult<Self>;
+}
+
+/// Converts a typed struct into a container, usually implemented with
+/// `#[derive(IntoPropertyContainer)]`.
+pub trait IntoPropertyContainer {
+    fn into_property_container(self) -> PropertyContainer;
+}
+
+impl FromPropertyContainer<'_> for PropertyContainer {
+    fn from_property_container(container: &PropertyContainer) -> PropertyContainerResult<Self> {
+        Ok(container.clone())
+    }
+}
+
+impl IntoPropertyContainer for PropertyContainer {
+    fn into_property_container(self) -> PropertyContainer {
+        self
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PropertyContainerError {
+    #[error("missing key `{0}`")]
+    MissingKey(&'static str),
+    #[error("key `{0}` has an unexpected type")]
+    InvalidType(&'static str),
+    #[error("raw values can't be stored in property block files")]
+    RawValue,
+}
+
+pub type PropertyContainerResult<T> = Result<T, PropertyContainerError>;
+
 impl FromPropertyValue<'_> for PropertyValue {
     fn from_property_value(value: &'_ PropertyValue) -> Option<Self> {
         Some(value.clone())
@@ -450,7 +602,7 @@ impl FromPropertyValue<'_> for Vec4<f32> {
     }
 }
 
-impl FromPropertyValue<'_> for [f32; 9] {
+impl FromPropertyValue<'_> for Mat3<f32> {
     fn from_property_value(value: &PropertyValue) -> Option<Self> {
         match value {
             PropertyValue::Mat3x3(value) => Some(*value),
@@ -459,7 +611,7 @@ impl FromPropertyValue<'_> for [f32; 9] {
     }
 }
 
-impl FromPropertyValue<'_> for [f32; 12] {
+impl FromPropertyValue<'_> for Mat3x4<f32> {
     fn from_property_value(value: &PropertyValue) -> Option<Self> {
         match value {
             PropertyValue::Mat3x4(value) => Some(*value),
@@ -468,6 +620,18 @@ impl FromPropertyValue<'_> for [f32; 12] {
     }
 }
 
+impl FromPropertyValue<'_> for [f32; 9] {
+    fn from_property_value(value: &PropertyValue) -> Option<Self> {
+        Mat3::from_property_value(value).map(Into::into)
+    }
+}
+
+impl FromPropertyValue<'_> for [f32; 12] {
+    fn from_property_value(value: &PropertyValue) -> Option<Self> {
+        Mat3x4::from_property_value(value).map(Into::into)
+    }
+}
+
 impl<'a> FromPropertyValue<'a> for &'a [i32] {
     fn from_property_value(value: &'a PropertyValue) -> Option<Self> {
         match value {
@@ -503,3 +667,21 @@ impl FromPropertyValue<'_> for Vec<f32> {
         }
     }
 }
+
+impl<'a> FromPropertyValue<'a> for &'a [u8] {
+    fn from_property_value(value: &'a PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::Raw(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl FromPropertyValue<'_> for Vec<u8> {
+    fn from_property_value(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::Raw(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use std::fmt;
+
+use jc2_hashing::{HashList, HashString};
+
+use super::{PropertyContainer, PropertyEntry};
+
+/// The differences between two containers, ordered as the entries appear in them.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct PropertyDiff(pub Vec<PropertyDifference>);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyDifference {
+    /// The keys leading to the entry, starting from the compared containers.
+    pub path: Vec<HashString>,
+    pub change: PropertyChange,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyChange {
+    Added(PropertyEntry),
+    Removed(PropertyEntry),
+    Changed {
+        from: PropertyEntry,
+        to: PropertyEntry,
+    },
+}
+
+/// The result of a three-way merge.
+///
+/// Conflicting entries keep the value from `ours` in the merged container.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct PropertyMerge {
+    pub container: PropertyContainer,
+    pub conflicts: Vec<PropertyConflict>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyConflict {
+    pub path: Vec<HashString>,
+    pub base: Option<PropertyEntry>,
+    pub ours: Option<PropertyEntry>,
+    pub theirs: Option<PropertyEntry>,
+}
+
+impl PropertyContainer {
+    /// Compares two containers, descending into containers present in both.
+    pub fn diff(&self, other: &PropertyContainer) -> PropertyDiff {
+        let mut result = vec![];
+        diff(self, other, &mut vec![], &mut result);
+        PropertyDiff(result)
+    }
+
+    /// Merges the changes made by `ours` and `theirs` since `base`.
+    ///
+    /// Entries changed on only one side take that side's value, and containers changed on both
+    /// sides are merged recursively. Anything else changed on both sides is a conflict.
+    pub fn merge(
+        base: &PropertyContainer,
+        ours: &PropertyContainer,
+        theirs: &PropertyContainer,
+    ) -> PropertyMerge {
+        let mut result = PropertyMerge::default();
+        result.container = merge(base, ours, theirs, &mut vec![], &mut result.conflicts);
+        result
+    }
+}
+
+impl PropertyDiff {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, PropertyDifference> {
+        self.0.iter()
+    }
+
+    /// Displays one difference per line, resolving keys through `hashes` where possible.
+    pub fn display<'a>(&'a self, hashes: Option<&'a HashList>) -> PropertyDiffDisplay<'a> {
+        PropertyDiffDisplay { diff: self, hashes }
+    }
+}
+
+impl<'a> IntoIterator for &'a PropertyDiff {
+    type Item = &'a PropertyDifference;
+    type IntoIter = std::slice::Iter<'a, PropertyDifference>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+pub struct PropertyDiffDisplay<'a> {
+    diff: &'a PropertyDiff,
+    hashes: Option<&'a HashList>,
+}
+
+impl fmt::Display for PropertyDiffDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for difference in &self.diff.0 {
+            let path = PropertyPathDisplay(&difference.path, self.hashes);
+            match &difference.change {
+                PropertyChange::Added(entry) => {
+                    writeln!(f, "+ {path}: {}", EntryDisplay(entry))?;
+                }
+                PropertyChange::Removed(entry) => {
+                    writeln!(f, "- {path}: {}", EntryDisplay(entry))?;
+                }
+                PropertyChange::Changed { from, to } => {
+                    writeln!(
+                        f,
+                        "~ {path}: {} -> {}",
+                        EntryDisplay(from),
+                        EntryDisplay(to)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Displays keys separated by `/`, resolving them through a hash list where possible.
+pub struct PropertyPathDisplay<'a>(pub &'a [HashString], pub Option<&'a HashList>);
+
+impl fmt::Display for PropertyPathDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, hash) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str("/")?;
+            }
+            match self.1.and_then(|hashes| hashes.find_string(*hash)) {
+                Some(name) => f.write_str(name)?,
+                None => write!(f, "0x{:08X}", hash.hash())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct EntryDisplay<'a>(&'a PropertyEntry);
+
+impl fmt::Display for EntryDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            PropertyEntry::Container(container) => write!(f, "{{{} entries}}", container.len()),
+            PropertyEntry::Value(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+fn diff(
+    from: &PropertyContainer,
+    to: &PropertyContainer,
+    path: &mut Vec<HashString>,
+    result: &mut Vec<PropertyDifference>,
+) {
+    let difference = |path: &[HashString], hash: HashString, change: PropertyChange| {
+        let mut path = path.to_vec();
+        path.push(hash);
+        PropertyDifference { path, change }
+    };
+
+    for (hash, entry) in from {
+        match (entry, to.get(*hash)) {
+            (PropertyEntry::Container(from), Some(PropertyEntry::Container(to))) => {
+                path.push(*hash);
+                diff(from, to, path, result);
+                path.pop();
+            }
+            (from, Some(to)) if from != to => result.push(difference(
+                path,
+                *hash,
+                PropertyChange::Changed {
+                    from: from.clone(),
+                    to: to.clone(),
+                },
+            )),
+            (_, Some(_)) => {}
+            (from, None) => {
+                result.push(difference(
+                    path,
+                    *hash,
+                    PropertyChange::Removed(from.clone()),
+                ));
+            }
+        }
+    }
+
+    for (hash, entry) in to {
+        if !from.contains(*hash) {
+            result.push(difference(
+                path,
+                *hash,
+                PropertyChange::Added(entry.clone()),
+            ));
+        }
+    }
+}
+
+fn merge(
+    base: &PropertyContainer,
+    ours: &PropertyContainer,
+    theirs: &PropertyContainer,
+    path: &mut Vec<HashString>,
+    conflicts: &mut Vec<PropertyConflict>,
+) -> PropertyContainer {
+    let empty = PropertyContainer::new();
+    let mut result = PropertyContainer::new();
+
+    // Keep the order of `ours`, with entries only added by `theirs` appended
+    let keys = ours
+        .iter()
+        .chain(theirs.iter().filter(|(hash, _)| !ours.contains(**hash)))
+        .map(|(hash, _)| *hash)
+        .collect::<Vec<_>>();
+
+    for hash in keys {
+        let (b, o, t) = (base.get(hash), ours.get(hash), theirs.get(hash));
+
+        let entry = if o == t || t == b {
+            o.cloned()
+        } else if o == b {
+            t.cloned()
+        } else if let (
+            None | Some(PropertyEntry::Container(_)),
+            Some(PropertyEntry::Container(o)),
+            Some(PropertyEntry::Container(t)),
+        ) = (b, o, t)
+        {
+            let b = match b {
+                Some(PropertyEntry::Container(b)) => b,
+                _ => &empty,
+            };
+            path.push(hash);
+            let merged = merge(b, o, t, path, conflicts);
+            path.pop();
+            Some(merged.into())
+        } else {
+            let mut path = path.clone();
+            path.push(hash);
+            conflicts.push(PropertyConflict {
+                path,
+                base: b.cloned(),
+                ours: o.cloned(),
+                theirs: t.cloned(),
+            });
+            o.cloned()
+        };
+
+        if let Some(entry) = entry {
+            result.insert(hash, entry);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property_container::PropertyValue;
+
+    fn container(values: &[(&str, i32)]) -> PropertyContainer {
+        let mut container = PropertyContainer::new();
+        for (key, value) in values {
+            container.insert_value(*key, *value);
+        }
+        container
+    }
+
+    fn path(keys: &[&str]) -> Vec<HashString> {
+        keys.iter().map(|key| HashString::from_str(key)).collect()
+    }
+
+    #[test]
+    fn diff_values() {
+        let from = container(&[("kept", 0), ("removed", 1), ("changed", 2)]);
+        let to = container(&[("kept", 0), ("changed", 3), ("added", 4)]);
+
+        let diff = from.diff(&to);
+        assert_eq!(
+            diff.0,
+            [
+                PropertyDifference {
+                    path: path(&["removed"]),
+                    change: PropertyChange::Removed(PropertyValue::I32(1).into()),
+                },
+                PropertyDifference {
+                    path: path(&["changed"]),
+                    change: PropertyChange::Changed {
+                        from: PropertyValue::I32(2).into(),
+                        to: PropertyValue::I32(3).into(),
+                    },
+                },
+                PropertyDifference {
+                    path: path(&["added"]),
+                    change: PropertyChange::Added(PropertyValue::I32(4).into()),
+                },
+            ]
+        );
+        assert!(to.diff(&to).is_empty());
+    }
+
+    #[test]
+    fn diff_nested_containers() {
+        let mut from = PropertyContainer::new();
+        from.insert("child", container(&[("foo", 0)]));
+        let mut to = PropertyContainer::new();
+        to.insert("child", container(&[("foo", 1)]));
+
+        let diff = from.diff(&to);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff.0[0].path, path(&["child", "foo"]));
+
+        // A container replaced by a value is a change of the whole entry
+        to.insert_value("child", 1);
+        let diff = from.diff(&to);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff.0[0].path, path(&["child"]));
+    }
+
+    #[test]
+    fn merge_one_sided_changes() {
+        let base = container(&[("a", 0), ("b", 0), ("c", 0)]);
+        let ours = container(&[("a", 1), ("b", 0), ("c", 0), ("d", 1)]);
+        let theirs = container(&[("a", 0), ("b", 2), ("e", 2)]);
+
+        let merge = PropertyContainer::merge(&base, &ours, &theirs);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(
+            merge.container,
+            container(&[("a", 1), ("b", 2), ("d", 1), ("e", 2)])
+        );
+        let keys = merge
+            .container
+            .iter()
+            .map(|(hash, _)| *hash)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, path(&["a", "b", "d", "e"]));
+    }
+
+    #[test]
+    fn merge_conflicts() {
+        let base = container(&[("a", 0), ("b", 0)]);
+        let ours = container(&[("a", 1)]);
+        let theirs = container(&[("a", 2), ("b", 2)]);
+
+        let merge = PropertyContainer::merge(&base, &ours, &theirs);
+        assert_eq!(merge.container, container(&[("a", 1)]));
+        assert_eq!(
+            merge.conflicts,
+            [
+                PropertyConflict {
+                    path: path(&["a"]),
+                    base: Some(PropertyValue::I32(0).into()),
+                    ours: Some(PropertyValue::I32(1).into()),
+                    theirs: Some(PropertyValue::I32(2).into()),
+                },
+                PropertyConflict {
+                    path: path(&["b"]),
+                    base: Some(PropertyValue::I32(0).into()),
+                    ours: None,
+                    theirs: Some(PropertyValue::I32(2).into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_nested_containers() {
+        let mut base = PropertyContainer::new();
+        base.insert("child", container(&[("a", 0), ("b", 0)]));
+        let mut ours = PropertyContainer::new();
+        ours.insert("child", container(&[("a", 1), ("b", 0)]));
+        let mut theirs = PropertyContainer::new();
+        theirs.insert("child", container(&[("a", 0), ("b", 2)]));
+
+        let merge = PropertyContainer::merge(&base, &ours, &theirs);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(
+            merge.container.get_container("child"),
+            Some(&container(&[("a", 1), ("b", 2)]))
+        );
+
+        // Containers added on both sides are merged as if they were empty before
+        let merge = PropertyContainer::merge(&PropertyContainer::new(), &ours, &theirs);
+        assert_eq!(merge.conflicts.len(), 2);
+        assert_eq!(merge.conflicts[0].path, path(&["child", "a"]));
+        assert_eq!(merge.conflicts[1].path, path(&["child", "b"]));
+    }
+}
@@ -1,12 +1,35 @@
+use std::io::Cursor;
+
 use binrw::{BinRead, BinWrite, binrw};
-use jc2_hashing::HashString;
+use jc2_hashing::{HashList, HashString};
 
 use crate::{
     common::{LengthString, LengthVec},
-    math::{Vec2, Vec3, Vec4},
+    math::{Mat3, Mat3x4, Vec2, Vec3, Vec4},
 };
 
-use super::PropertyValue;
+use super::{PropertyContainer, PropertyEntry, PropertyValue};
+
+/// Controls which sections keys are written to when converting from a [`PropertyContainer`].
+#[derive(Clone, Copy, Debug, Default)]
+pub enum PropertyFileKeys<'a> {
+    /// Every key is written to `HashedContainer` and `HashedValue` sections.
+    #[default]
+    Hashed,
+    /// Keys resolved by the list are written to `Container` and `Value` sections, with any
+    /// unresolved keys falling back to hashed sections.
+    Named(&'a HashList),
+}
+
+impl PropertyFileKeys<'_> {
+    #[inline]
+    fn resolve(&self, hash: HashString) -> Option<&str> {
+        match self {
+            Self::Hashed => None,
+            Self::Named(list) => list.find_string(hash).map(String::as_str),
+        }
+    }
+}
 
 #[binrw]
 #[derive(Clone, Default, Debug)]
@@ -17,6 +40,22 @@ pub struct PropertyFile(
 );
 
 impl PropertyFile {
+    pub fn from_containers(value: Vec<PropertyContainer>, keys: PropertyFileKeys<'_>) -> Self {
+        Self(
+            value
+                .into_iter()
+                .map(|container| PropertyFileContainer::from_container(container, keys))
+                .collect(),
+        )
+    }
+
+    /// Collects the names of every key stored in a named section.
+    pub fn collect_names(&self, names: &mut HashList) {
+        for container in &self.0 {
+            container.collect_names(names);
+        }
+    }
+
     #[inline]
     #[binrw::parser(reader, endian)]
     fn parse() -> binrw::BinResult<Vec<PropertyFileContainer>> {
@@ -47,6 +86,96 @@ impl PropertyFile {
 #[derive(Clone, Debug)]
 pub struct PropertyFileContainer(pub LengthVec<PropertyFileSection, u8>);
 
+impl From<Vec<PropertyContainer>> for PropertyFile {
+    fn from(value: Vec<PropertyContainer>) -> Self {
+        Self::from_containers(value, PropertyFileKeys::Hashed)
+    }
+}
+
+impl PropertyFileContainer {
+    /// Converts a container, grouping consecutive entries of the same kind into sections so
+    /// that entry order is preserved. Containers alternating between kinds more often than a
+    /// file can hold sections fail to write, rather than having their entries reordered.
+    pub fn from_container(value: PropertyContainer, keys: PropertyFileKeys<'_>) -> Self {
+        let mut sections: Vec<PropertyFileSection> = vec![];
+
+        for (hash, entry) in value {
+            if let Some(bytes) = PropertyFileSection::encode_raw(&entry) {
+                sections.push(PropertyFileSection::Raw(bytes.into()));
+                continue;
+            }
+
+            match (keys.resolve(hash), entry, sections.last_mut()) {
+                (Some(name), PropertyEntry::Container(container), last) => {
+                    let entry = (name.into(), Self::from_container(container, keys));
+                    match last {
+                        Some(PropertyFileSection::Container(section)) => section.push(entry),
+                        _ => sections.push(PropertyFileSection::Container(vec![entry].into())),
+                    }
+                }
+                (Some(name), PropertyEntry::Value(value), last) => {
+                    let entry = (name.into(), value.into());
+                    match last {
+                        Some(PropertyFileSection::Value(section)) => section.push(entry),
+                        _ => sections.push(PropertyFileSection::Value(vec![entry].into())),
+                    }
+                }
+                (None, PropertyEntry::Container(container), last) => {
+                    let entry = (hash, Self::from_container(container, keys));
+                    match last {
+                        Some(PropertyFileSection::HashedContainer(section)) => section.push(entry),
+                        _ => {
+                            sections.push(PropertyFileSection::HashedContainer(vec![entry].into()));
+                        }
+                    }
+                }
+                (None, PropertyEntry::Value(value), last) => {
+                    let entry = (hash, value.into());
+                    match last {
+                        Some(PropertyFileSection::HashedValue(section)) => section.push(entry),
+                        _ => sections.push(PropertyFileSection::HashedValue(vec![entry].into())),
+                    }
+                }
+            }
+        }
+
+        Self(sections.into())
+    }
+
+    /// Collects the names of every key stored in a named section.
+    pub fn collect_names(&self, names: &mut HashList) {
+        for section in self.0.iter() {
+            match section {
+                PropertyFileSection::Container(containers) => {
+                    for (name, container) in containers.iter() {
+                        names.insert_string(name.as_str());
+                        container.collect_names(names);
+                    }
+                }
+                PropertyFileSection::Value(values) => {
+                    for (name, _) in values.iter() {
+                        names.insert_string(name.as_str());
+                    }
+                }
+                PropertyFileSection::HashedContainer(containers) => {
+                    for (_, container) in containers.iter() {
+                        container.collect_names(names);
+                    }
+                }
+                PropertyFileSection::Empty(_)
+                | PropertyFileSection::Raw(_)
+                | PropertyFileSection::HashedValue(_) => {}
+            }
+        }
+    }
+}
+
+impl From<PropertyContainer> for PropertyFileContainer {
+    fn from(value: PropertyContainer) -> Self {
+        Self::from_container(value, PropertyFileKeys::Hashed)
+    }
+}
+
 #[binrw]
 #[derive(Clone, Debug)]
 pub enum PropertyFileSection {
@@ -64,6 +193,56 @@ pub enum PropertyFileSection {
     HashedValue(LengthVec<(HashString, PropertyFileValue), u16>),
 }
 
+impl PropertyFileSection {
+    /// Returns the key the raw section at `index` is stored under when converted to a
+    /// [`PropertyContainer`], as raw sections have no key of their own. The key only names the
+    /// entry, so raw entries can be renamed, reordered or removed freely.
+    pub fn raw_key(index: usize) -> HashString {
+        format!("_raw{index}").into()
+    }
+
+    /// Decodes the payload of a raw section.
+    ///
+    /// Payloads holding a nested container are exposed as one marked with
+    /// [`PropertyContainer::is_raw`], provided converting it back reproduces the payload
+    /// exactly. Anything else is kept as a [`PropertyValue::Raw`].
+    pub fn decode_raw(bytes: Vec<u8>) -> PropertyEntry {
+        let mut cursor = Cursor::new(bytes.as_slice());
+        let container = PropertyFileContainer::read_le(&mut cursor)
+            .ok()
+            .filter(|_| cursor.position() == bytes.len() as u64)
+            .map(PropertyContainer::from)
+            .filter(|container| Self::encode_container(container.clone()).as_ref() == Some(&bytes));
+
+        match container {
+            Some(mut container) => {
+                container.set_raw(true);
+                container.into()
+            }
+            None => PropertyValue::Raw(bytes).into(),
+        }
+    }
+
+    /// Returns the payload of a raw section if the entry was decoded from one.
+    fn encode_raw(entry: &PropertyEntry) -> Option<Vec<u8>> {
+        match entry {
+            PropertyEntry::Value(PropertyValue::Raw(bytes)) => Some(bytes.clone()),
+            PropertyEntry::Container(container) if container.is_raw() => {
+                Self::encode_container(container.clone())
+            }
+            PropertyEntry::Container(_) | PropertyEntry::Value(_) => None,
+        }
+    }
+
+    fn encode_container(container: PropertyContainer) -> Option<Vec<u8>> {
+        let mut cursor = Cursor::new(vec![]);
+        PropertyFileContainer::from(container)
+            .write_le(&mut cursor)
+            .ok()
+            .map(|_| cursor.into_inner())
+    }
+}
+
 impl Default for PropertyFileSection {
     fn default() -> Self {
         Self::Empty(0)
@@ -89,9 +268,9 @@ pub enum PropertyFileValue {
     #[brw(magic = 6u8)]
     Vec4(Vec4<f32>),
     #[brw(magic = 7u8)]
-    Mat3x3([f32; 9]),
+    Mat3x3(Mat3<f32>),
     #[brw(magic = 8u8)]
-    Mat3x4([f32; 12]),
+    Mat3x4(Mat3x4<f32>),
     #[brw(magic = 9u8)]
     VecI32(LengthVec<i32, u32>),
     #[brw(magic = 10u8)]
@@ -101,7 +280,8 @@ pub enum PropertyFileValue {
 impl From<PropertyValue> for PropertyFileValue {
     fn from(value: PropertyValue) -> Self {
         match value {
-            PropertyValue::Empty => Self::Empty,
+            // Raw values have no value encoding, and are written as raw sections instead
+            PropertyValue::Empty | PropertyValue::Raw(_) => Self::Empty,
             PropertyValue::I32(value) => Self::I32(value),
             PropertyValue::F32(value) => Self::F32(value),
             PropertyValue::String(value) => Self::String(value.into()),
@@ -115,3 +295,99 @@ impl From<PropertyValue> for PropertyFileValue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alternating(count: i32) -> PropertyContainer {
+        let mut container = PropertyContainer::new();
+        for i in 0..count {
+            if i % 2 == 0 {
+                container.insert_value(format!("value{i}").as_str(), i);
+            } else {
+                container.insert(format!("container{i}").as_str(), PropertyContainer::new());
+            }
+        }
+        container
+    }
+
+    #[test]
+    fn keep_alternating_sections_in_order() -> binrw::BinResult<()> {
+        let container = alternating(200);
+        let file = PropertyFileContainer::from(container.clone());
+        assert_eq!(file.0.len(), 200);
+
+        let mut writer = Cursor::new(vec![]);
+        PropertyFile(vec![file]).write_le(&mut writer)?;
+        writer.set_position(0);
+        let result: Vec<PropertyContainer> = PropertyFile::read_le(&mut writer)?.into();
+        assert_eq!(result, std::slice::from_ref(&container));
+        // Equality ignores order, so the keys are compared in order separately
+        let keys = |container: &PropertyContainer| {
+            container.iter().map(|(hash, _)| *hash).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&result[0]), keys(&container));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_too_many_sections() {
+        let file = PropertyFileContainer::from(alternating(300));
+        assert_eq!(file.0.len(), 300);
+
+        let mut writer = Cursor::new(vec![]);
+        assert!(PropertyFile(vec![file]).write_le(&mut writer).is_err());
+    }
+
+    fn raw_section(container: PropertyContainer) -> Option<PropertyFileSection> {
+        PropertyFileSection::encode_container(container)
+            .map(|bytes| PropertyFileSection::Raw(bytes.into()))
+    }
+
+    fn raw_payloads(file: &PropertyFileContainer) -> Vec<&[u8]> {
+        file.0
+            .iter()
+            .filter_map(|section| match section {
+                PropertyFileSection::Raw(bytes) => Some(bytes.value.as_slice()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn write_raw_sections_regardless_of_key() {
+        let mut first = PropertyContainer::new();
+        first.insert_value("first", 1);
+        let mut second = PropertyContainer::new();
+        second.insert_value("second", 2);
+        let sections = [raw_section(first), raw_section(second.clone())];
+        let file = PropertyFileContainer(sections.into_iter().flatten().collect::<Vec<_>>().into());
+
+        let mut container = PropertyContainer::from(file);
+        let raw = container.get_container(PropertyFileSection::raw_key(1));
+        assert!(raw.is_some_and(PropertyContainer::is_raw));
+
+        // Removing the first raw entry shifts the second into its place
+        container.remove(PropertyFileSection::raw_key(0));
+        let file = PropertyFileContainer::from(container);
+        let expected = PropertyFileSection::encode_container(second);
+        assert_eq!(
+            raw_payloads(&file),
+            [expected.as_deref().unwrap_or_default()]
+        );
+    }
+
+    #[test]
+    fn write_keys_named_like_raw_sections_as_containers() {
+        let mut container = PropertyContainer::new();
+        container.insert(PropertyFileSection::raw_key(0), PropertyContainer::new());
+
+        let file = PropertyFileContainer::from(container);
+        assert!(raw_payloads(&file).is_empty());
+        assert!(matches!(
+            file.0.first(),
+            Some(PropertyFileSection::HashedContainer(_))
+        ));
+    }
+}
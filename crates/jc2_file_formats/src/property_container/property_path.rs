@@ -0,0 +1,428 @@
+use std::str::FromStr;
+
+use jc2_hashing::HashString;
+use thiserror::Error;
+
+use super::{FromPropertyValue, PropertyContainer, PropertyEntry, PropertyValue};
+
+/// A path to entries within nested containers, separated by `/`.
+///
+/// Each segment is one of:
+/// - a key, either a name such as `world` or a raw hash such as `0x1A2B3C4D`
+/// - `*`, matching every entry of a container
+/// - `**`, matching zero or more nested containers
+///
+/// Segments may be followed by predicates such as `[_class=CLight]` or `[filename]`, which only
+/// match containers holding a value equal to the one given, or any value for the key.
+///
+/// For example `**/*[_class=CPlantedTree]/world` finds the transform of every planted tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyPath(Vec<PropertyPathSegment>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyPathSegment {
+    pub key: PropertyPathKey,
+    pub predicates: Vec<PropertyPathPredicate>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PropertyPathKey {
+    Hash(HashString),
+    Any,
+    Descendants,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyPathPredicate {
+    pub key: HashString,
+    pub value: Option<String>,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PropertyPathError {
+    #[error("path is empty")]
+    Empty,
+    #[error("segment `{0}` is empty")]
+    EmptySegment(String),
+    #[error("segment `{0}` has an unterminated predicate")]
+    UnterminatedPredicate(String),
+    #[error("segment `{0}` may not have predicates")]
+    UnexpectedPredicate(String),
+}
+
+impl PropertyPath {
+    pub fn new(path: &str) -> Result<Self, PropertyPathError> {
+        path.parse()
+    }
+
+    #[inline]
+    pub fn segments(&self) -> &[PropertyPathSegment] {
+        &self.0
+    }
+}
+
+impl FromStr for PropertyPath {
+    type Err = PropertyPathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        if path.trim().is_empty() {
+            return Err(PropertyPathError::Empty);
+        }
+
+        // Split on `/`, ignoring any within predicates as values may contain paths
+        let mut segments = vec![];
+        let mut depth = 0usize;
+        let mut start = 0usize;
+        for (index, char) in path.char_indices() {
+            match char {
+                '[' => depth += 1,
+                ']' => depth = depth.saturating_sub(1),
+                '/' if depth == 0 => {
+                    segments.push(path[start..index].parse()?);
+                    start = index + 1;
+                }
+                _ => {}
+            }
+        }
+        segments.push(path[start..].parse()?);
+
+        Ok(Self(segments))
+    }
+}
+
+impl TryFrom<&str> for PropertyPath {
+    type Error = PropertyPathError;
+
+    #[inline]
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl FromStr for PropertyPathSegment {
+    type Err = PropertyPathError;
+
+    fn from_str(segment: &str) -> Result<Self, Self::Err> {
+        let (key, mut rest) = segment
+            .find('[')
+            .map_or((segment, ""), |index| segment.split_at(index));
+
+        let key = match key.trim() {
+            "" => return Err(PropertyPathError::EmptySegment(segment.into())),
+            "*" => PropertyPathKey::Any,
+            "**" => PropertyPathKey::Descendants,
+            key => PropertyPathKey::Hash(parse_key(key)),
+        };
+
+        let mut predicates = vec![];
+        while let Some(predicate) = rest.strip_prefix('[') {
+            let Some(end) = predicate.find(']') else {
+                return Err(PropertyPathError::UnterminatedPredicate(segment.into()));
+            };
+
+            predicates.push(match predicate[..end].split_once('=') {
+                Some((key, value)) => PropertyPathPredicate {
+                    key: parse_key(key.trim()),
+                    value: Some(value.into()),
+                },
+                None => PropertyPathPredicate {
+                    key: parse_key(predicate[..end].trim()),
+                    value: None,
+                },
+            });
+            rest = predicate[end + 1..].trim_start();
+        }
+
+        if !rest.trim().is_empty() {
+            return Err(PropertyPathError::UnterminatedPredicate(segment.into()));
+        }
+
+        if key == PropertyPathKey::Descendants && !predicates.is_empty() {
+            return Err(PropertyPathError::UnexpectedPredicate(segment.into()));
+        }
+
+        Ok(Self { key, predicates })
+    }
+}
+
+fn parse_key(key: &str) -> HashString {
+    key.strip_prefix("0x")
+        .or_else(|| key.strip_prefix("0X"))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .map_or_else(|| HashString::from_str(key), HashString::new)
+}
+
+impl PropertyPathPredicate {
+    pub fn matches(&self, container: &PropertyContainer) -> bool {
+        match (container.get(self.key), &self.value) {
+            (Some(_), None) => true,
+            (Some(PropertyEntry::Value(value)), Some(expected)) => match value {
+                PropertyValue::String(value) => value == expected,
+                PropertyValue::I32(value) => expected.parse() == Ok(*value),
+                PropertyValue::F32(value) => expected.parse() == Ok(*value),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl PropertyPathSegment {
+    #[inline]
+    fn matches(&self, entry: &PropertyEntry) -> bool {
+        self.predicates.is_empty()
+            || matches!(entry, PropertyEntry::Container(container)
+                if self.predicates.iter().all(|predicate| predicate.matches(container)))
+    }
+}
+
+impl PropertyContainer {
+    /// Returns the first entry matching the path.
+    pub fn find(&self, path: &PropertyPath) -> Option<&PropertyEntry> {
+        self.find_all(path).into_iter().next()
+    }
+
+    /// Returns every entry matching the path, in order.
+    pub fn find_all(&self, path: &PropertyPath) -> Vec<&PropertyEntry> {
+        let mut result = vec![];
+        collect(self, &path.0, &mut result);
+        result
+    }
+
+    /// Returns every container matching the path, in order.
+    pub fn find_containers(&self, path: &PropertyPath) -> Vec<&PropertyContainer> {
+        self.find_all(path)
+            .into_iter()
+            .filter_map(|entry| match entry {
+                PropertyEntry::Container(container) => Some(container),
+                PropertyEntry::Value(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns every value matching the path which can be converted to `T`, in order.
+    pub fn find_values<'a, T: FromPropertyValue<'a>>(&'a self, path: &PropertyPath) -> Vec<T> {
+        self.find_all(path)
+            .into_iter()
+            .filter_map(|entry| match entry {
+                PropertyEntry::Value(value) => T::from_property_value(value),
+                PropertyEntry::Container(_) => None,
+            })
+            .collect()
+    }
+
+    /// Calls `f` with every entry matching the path, returning the number of matches.
+    pub fn find_mut(
+        &mut self,
+        path: &PropertyPath,
+        mut f: impl FnMut(&mut PropertyEntry),
+    ) -> usize {
+        visit_mut(self, &path.0, &mut f)
+    }
+
+    /// Replaces every entry matching the path, returning the number of replaced entries.
+    ///
+    /// Only existing entries are replaced, no containers or keys are created.
+    pub fn set_all(&mut self, path: &PropertyPath, value: impl Into<PropertyEntry>) -> usize {
+        let value = value.into();
+        self.find_mut(path, |entry| *entry = value.clone())
+    }
+
+    /// Removes every entry matching the path, returning the number of removed entries.
+    pub fn remove_all(&mut self, path: &PropertyPath) -> usize {
+        remove(self, &path.0)
+    }
+
+    /// Returns every container nested within this one, depth first and in order.
+    pub fn descendants(&self) -> Vec<&PropertyContainer> {
+        let mut result = vec![];
+        let mut stack: Vec<&PropertyContainer> = self.containers().rev().collect();
+        while let Some(container) = stack.pop() {
+            result.push(container);
+            stack.extend(container.containers().rev());
+        }
+        result
+    }
+
+    /// Returns every container nested within this one for which `predicate` returns true.
+    pub fn descendants_where(
+        &self,
+        mut predicate: impl FnMut(&PropertyContainer) -> bool,
+    ) -> Vec<&PropertyContainer> {
+        self.descendants()
+            .into_iter()
+            .filter(|container| predicate(container))
+            .collect()
+    }
+}
+
+#[inline]
+fn matches_key(segment: &PropertyPathSegment, hash: HashString, entry: &PropertyEntry) -> bool {
+    match segment.key {
+        PropertyPathKey::Hash(key) => key == hash && segment.matches(entry),
+        PropertyPathKey::Any => segment.matches(entry),
+        PropertyPathKey::Descendants => false,
+    }
+}
+
+fn collect<'a>(
+    container: &'a PropertyContainer,
+    segments: &[PropertyPathSegment],
+    result: &mut Vec<&'a PropertyEntry>,
+) {
+    let Some((segment, rest)) = segments.split_first() else {
+        return;
+    };
+
+    if segment.key == PropertyPathKey::Descendants {
+        collect(container, rest, result);
+        for child in container.containers() {
+            collect(child, segments, result);
+        }
+        return;
+    }
+
+    for (hash, entry) in container {
+        if !matches_key(segment, *hash, entry) {
+            continue;
+        }
+
+        match entry {
+            _ if rest.is_empty() => result.push(entry),
+            PropertyEntry::Container(child) => collect(child, rest, result),
+            PropertyEntry::Value(_) => {}
+        }
+    }
+}
+
+fn visit_mut(
+    container: &mut PropertyContainer,
+    segments: &[PropertyPathSegment],
+    f: &mut dyn FnMut(&mut PropertyEntry),
+) -> usize {
+    let Some((segment, rest)) = segments.split_first() else {
+        return 0;
+    };
+
+    if segment.key == PropertyPathKey::Descendants {
+        // Children are visited before this level, so entries replaced by `f` are never
+        // descended into, which would recurse forever if they contain the key again
+        let mut count = 0;
+        for (_, entry) in container.iter_mut() {
+            if let PropertyEntry::Container(child) = entry {
+                count += visit_mut(child, segments, f);
+            }
+        }
+        return count + visit_mut(container, rest, f);
+    }
+
+    let mut count = 0;
+    for (hash, entry) in container.iter_mut() {
+        if !matches_key(segment, *hash, entry) {
+            continue;
+        }
+
+        if rest.is_empty() {
+            f(entry);
+            count += 1;
+        } else if let PropertyEntry::Container(child) = entry {
+            count += visit_mut(child, rest, f);
+        }
+    }
+    count
+}
+
+fn remove(container: &mut PropertyContainer, segments: &[PropertyPathSegment]) -> usize {
+    match segments {
+        [] => 0,
+        [segment] => {
+            let count = container.len();
+            container.retain(|hash, entry| !matches_key(segment, *hash, entry));
+            count - container.len()
+        }
+        [segment, rest @ ..] if segment.key == PropertyPathKey::Descendants => {
+            let mut count = remove(container, rest);
+            for (_, entry) in container.iter_mut() {
+                if let PropertyEntry::Container(child) = entry {
+                    count += remove(child, segments);
+                }
+            }
+            count
+        }
+        [segment, rest @ ..] => {
+            let mut count = 0;
+            for (hash, entry) in container.iter_mut() {
+                if !matches_key(segment, *hash, entry) {
+                    continue;
+                }
+
+                if let PropertyEntry::Container(child) = entry {
+                    count += remove(child, rest);
+                }
+            }
+            count
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nested() -> PropertyContainer {
+        let mut leaf = PropertyContainer::new();
+        leaf.insert_value("foo", 2);
+
+        let mut child = PropertyContainer::new();
+        child.insert_value("foo", 1);
+        child.insert("leaf", leaf);
+
+        let mut root = PropertyContainer::new();
+        root.insert("child", child);
+        root.insert_value("foo", 0);
+        root
+    }
+
+    #[test]
+    fn set_all_descendants() -> Result<(), PropertyPathError> {
+        let mut container = nested();
+        let path = PropertyPath::new("**/foo")?;
+
+        assert_eq!(container.set_all(&path, 3), 3);
+        assert_eq!(container.find_values::<i32>(&path), [3, 3, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn set_all_descendants_with_container() -> Result<(), PropertyPathError> {
+        let mut value = PropertyContainer::new();
+        value.insert_value("foo", 4);
+
+        let mut container = nested();
+        let count = container.set_all(&PropertyPath::new("**/foo")?, value.clone());
+        assert_eq!(count, 3);
+        assert_eq!(container.get_container("foo"), Some(&value));
+        assert_eq!(
+            container.find_values::<i32>(&PropertyPath::new("**/foo/foo")?),
+            [4, 4, 4]
+        );
+
+        let mut container = nested();
+        let count = container.set_all(&PropertyPath::new("**/*")?, value.clone());
+        assert_eq!(count, 5);
+        assert_eq!(container.get_container("child"), Some(&value));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_hash_keys() -> Result<(), PropertyPathError> {
+        let container = nested();
+        let hash = HashString::from_str("foo").hash();
+
+        for path in [format!("0x{hash:08x}"), format!("0X{hash:08X}")] {
+            let path = PropertyPath::new(&path)?;
+            assert_eq!(container.find_values::<i32>(&path), [0]);
+        }
+        Ok(())
+    }
+}
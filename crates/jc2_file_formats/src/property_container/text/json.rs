@@ -0,0 +1,278 @@
+use jc2_hashing::{HashList, HashString};
+use serde_json::{Map, Number, Value};
+
+use super::{
+    CONTAINER_TYPE, PropertyFileFormat, PropertyTextError, PropertyTextKey, PropertyTextResult,
+    PropertyTextValue, value_type,
+};
+use crate::property_container::{PropertyContainer, PropertyEntry, PropertyValue};
+
+/// Serialises containers to JSON. Keys found in `names` are written by name, as stored in named
+/// sections, while other keys are written by id along with any name `hashes` resolves them to.
+///
+/// ```json
+/// {
+///   "containers": [
+///     [
+///       { "name": "_class", "type": "string", "value": "CLight" },
+///       { "id": "0x1A2B3C4D", "type": "vec3", "value": [0, 1.5, 0] },
+///       { "name": "children", "type": "object", "value": [] }
+///     ]
+///   ],
+///   "format": "file"
+/// }
+/// ```
+pub fn to_string(
+    containers: &[PropertyContainer],
+    format: PropertyFileFormat,
+    names: Option<&HashList>,
+    hashes: Option<&HashList>,
+) -> PropertyTextResult<String> {
+    let mut result = Map::new();
+    result.insert("format".into(), format.as_str().into());
+    result.insert(
+        "containers".into(),
+        containers
+            .iter()
+            .map(|container| container_to_value(container, names, hashes))
+            .collect(),
+    );
+    Ok(serde_json::to_string_pretty(&Value::Object(result))?)
+}
+
+/// Parses containers from JSON along with the format they were read from, recording every key
+/// stored by name into `names` if provided.
+pub fn from_str(
+    text: &str,
+    mut names: Option<&mut HashList>,
+) -> PropertyTextResult<(Vec<PropertyContainer>, PropertyFileFormat)> {
+    let value: Value = serde_json::from_str(text)?;
+    let (containers, format) = match &value {
+        Value::Object(object) => (
+            object.get("containers").unwrap_or(&Value::Null),
+            match object.get("format") {
+                Some(Value::String(format)) => PropertyFileFormat::parse(format)?,
+                Some(format) => return Err(invalid("format", format)),
+                None => PropertyFileFormat::default(),
+            },
+        ),
+        value => (value, PropertyFileFormat::default()),
+    };
+
+    match containers {
+        Value::Array(containers) => Ok((
+            containers
+                .iter()
+                .map(|container| container_from_value(container, &mut names))
+                .collect::<PropertyTextResult<_>>()?,
+            format,
+        )),
+        value => Err(invalid("containers", value)),
+    }
+}
+
+fn container_to_value(
+    container: &PropertyContainer,
+    names: Option<&HashList>,
+    hashes: Option<&HashList>,
+) -> Value {
+    Value::Array(
+        container
+            .iter()
+            .map(|(hash, entry)| {
+                let mut result = Map::new();
+                let key = PropertyTextKey::new(*hash, names, hashes);
+                if let Some(name) = key.name() {
+                    result.insert("name".into(), name.into());
+                }
+                if let Some(id) = key.id() {
+                    result.insert("id".into(), id.into());
+                }
+                match entry {
+                    PropertyEntry::Container(container) => {
+                        result.insert("type".into(), CONTAINER_TYPE.into());
+                        if container.is_raw() {
+                            result.insert("raw".into(), true.into());
+                        }
+                        result.insert("value".into(), container_to_value(container, names, hashes));
+                    }
+                    PropertyEntry::Value(value) => {
+                        result.insert("type".into(), value_type(value).into());
+                        let scalar = matches!(value, PropertyValue::I32(_) | PropertyValue::F32(_));
+                        result.insert("value".into(), text_value_to_value(value.into(), scalar));
+                    }
+                }
+                Value::Object(result)
+            })
+            .collect(),
+    )
+}
+
+fn text_value_to_value(value: PropertyTextValue<'_>, scalar: bool) -> Value {
+    // Floats are written using their shortest representation, with non-finite values as strings
+    fn float(value: f32) -> Value {
+        let text = format!("{value:?}");
+        text.parse::<Number>()
+            .map_or_else(|_| Value::String(text), Value::Number)
+    }
+
+    match value {
+        PropertyTextValue::Empty => Value::Null,
+        PropertyTextValue::String(value) => value.into(),
+        PropertyTextValue::I32(value) => match value.as_slice() {
+            [value] if scalar => (*value).into(),
+            values => values.iter().copied().map(Value::from).collect(),
+        },
+        PropertyTextValue::F32(value) => match value.as_slice() {
+            [value] if scalar => float(*value),
+            values => values.iter().copied().map(float).collect(),
+        },
+    }
+}
+
+fn container_from_value(
+    value: &Value,
+    names: &mut Option<&mut HashList>,
+) -> PropertyTextResult<PropertyContainer> {
+    let Value::Array(entries) = value else {
+        return Err(invalid(CONTAINER_TYPE, value));
+    };
+
+    let mut result = PropertyContainer::new();
+    for entry in entries {
+        let Value::Object(entry) = entry else {
+            return Err(invalid("entry", entry));
+        };
+
+        let string = |key: &str| entry.get(key).and_then(Value::as_str).map(String::from);
+        let hash: HashString = PropertyTextKey::parse(string("name"), string("id"), names)?;
+        let kind = string("type").ok_or(PropertyTextError::MissingType)?;
+        let value = entry.get("value").unwrap_or(&Value::Null);
+
+        if kind == CONTAINER_TYPE {
+            let mut container = container_from_value(value, names)?;
+            container.set_raw(entry.get("raw").and_then(Value::as_bool).unwrap_or(false));
+            result.insert_container(hash, container);
+        } else {
+            result.insert_value(hash, value_to_text_value(&kind, value)?.into_value(&kind)?);
+        }
+    }
+    Ok(result)
+}
+
+fn value_to_text_value<'a>(
+    kind: &str,
+    value: &'a Value,
+) -> PropertyTextResult<PropertyTextValue<'a>> {
+    let int = |value: &Value| {
+        value
+            .as_i64()
+            .and_then(|value| i32::try_from(value).ok())
+            .ok_or_else(|| invalid(kind, value))
+    };
+    let float = |value: &Value| match value {
+        Value::Number(number) => number
+            .as_f64()
+            .map(|value| value as f32)
+            .ok_or_else(|| invalid(kind, value)),
+        Value::String(string) => string.parse().ok().ok_or_else(|| invalid(kind, value)),
+        _ => Err(invalid(kind, value)),
+    };
+
+    Ok(match (kind, value) {
+        (_, Value::Null) => PropertyTextValue::Empty,
+        ("string", Value::String(value)) => PropertyTextValue::String(value),
+        ("int" | "vec_int" | "raw", Value::Array(values)) => {
+            PropertyTextValue::I32(values.iter().map(int).collect::<Result<_, _>>()?)
+        }
+        ("int" | "vec_int" | "raw", value) => PropertyTextValue::I32(vec![int(value)?]),
+        (_, Value::Array(values)) => {
+            PropertyTextValue::F32(values.iter().map(float).collect::<Result<_, _>>()?)
+        }
+        (_, value) => PropertyTextValue::F32(vec![float(value)?]),
+    })
+}
+
+fn invalid(kind: &str, value: &Value) -> PropertyTextError {
+    PropertyTextError::InvalidValue {
+        kind: kind.into(),
+        value: value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property_container::text::tests::containers;
+
+    #[test]
+    fn round_trip() -> PropertyTextResult<()> {
+        let (containers, names, hashes) = containers();
+        let text = to_string(
+            &containers,
+            PropertyFileFormat::BlockFile,
+            Some(&names),
+            Some(&hashes),
+        )?;
+        assert!(text.contains(r#""name": "world""#));
+        assert!(text.contains(r#""raw": true"#));
+
+        let mut result_names = HashList::new();
+        let (result, format) = from_str(&text, Some(&mut result_names))?;
+        assert_eq!(result, containers);
+        assert_eq!(format, PropertyFileFormat::BlockFile);
+        assert!(
+            result[0]
+                .get_container("data")
+                .is_some_and(PropertyContainer::is_raw)
+        );
+
+        // Only keys written by name are recorded
+        assert_eq!(result_names.len(), names.len());
+        assert!(names.keys().all(|hash| result_names.contains(*hash)));
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_ids() -> PropertyTextResult<()> {
+        let (containers, ..) = containers();
+        let text = to_string(&containers, PropertyFileFormat::PropertyFile, None, None)?;
+        assert!(!text.contains(r#""name""#));
+
+        let mut names = HashList::new();
+        let (result, format) = from_str(&text, Some(&mut names))?;
+        assert_eq!(result, containers);
+        assert_eq!(format, PropertyFileFormat::PropertyFile);
+        assert!(names.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn read_bare_containers() -> PropertyTextResult<()> {
+        let text = r#"[[
+            { "name": "int", "type": "int", "value": 3 },
+            { "id": "0x12345678", "type": "vec_float", "value": [1, "inf"] }
+        ]]"#;
+        let (result, format) = from_str(text, None)?;
+        assert_eq!(format, PropertyFileFormat::PropertyFile);
+        assert_eq!(result[0].get_value::<i32>("int"), Some(3));
+        assert_eq!(
+            result[0].get_value::<&[f32]>(0x1234_5678),
+            Some([1.0, f32::INFINITY].as_slice())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reject_invalid_entries() {
+        for text in [
+            r#"[[{ "name": "a", "id": "0x12345678", "type": "int", "value": 1 }]]"#,
+            r#"[[{ "name": "a", "value": 1 }]]"#,
+            r#"[[{ "name": "a", "type": "int", "value": "1" }]]"#,
+            r#"[[{ "name": "a", "type": "quat", "value": [0, 0, 0, 1] }]]"#,
+            r#"{ "format": "text", "containers": [] }"#,
+        ] {
+            assert!(from_str(text, None).is_err(), "{text}");
+        }
+    }
+}
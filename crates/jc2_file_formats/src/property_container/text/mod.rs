@@ -0,0 +1,420 @@
+use jc2_hashing::{HashList, HashString};
+use thiserror::Error;
+
+use super::PropertyValue;
+
+#[cfg(feature = "json")]
+pub mod json;
+
+#[cfg(feature = "xml")]
+pub mod xml;
+
+#[derive(Error, Debug)]
+pub enum PropertyTextError {
+    #[cfg(feature = "json")]
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "xml")]
+    #[error(transparent)]
+    Xml(#[from] quick_xml::Error),
+    #[cfg(feature = "xml")]
+    #[error(transparent)]
+    XmlAttribute(#[from] quick_xml::events::attributes::AttrError),
+    #[cfg(feature = "xml")]
+    #[error(transparent)]
+    XmlEscape(#[from] quick_xml::escape::EscapeError),
+    #[error("unexpected element `{0}`")]
+    UnexpectedElement(String),
+    #[error("unexpected end of document")]
+    UnexpectedEnd,
+    #[error("entry is missing a `name` or `id`")]
+    MissingKey,
+    #[error("invalid id `{0}`")]
+    InvalidId(String),
+    #[error("name `{name}` does not match id `{id}`")]
+    MismatchedKey { name: String, id: String },
+    #[error("unknown file format `{0}`")]
+    UnknownFormat(String),
+    #[error("entry is missing a `type`")]
+    MissingType,
+    #[error("unknown value type `{0}`")]
+    UnknownType(String),
+    #[error("invalid {kind} value `{value}`")]
+    InvalidValue { kind: String, value: String },
+}
+
+pub type PropertyTextResult<T> = Result<T, PropertyTextError>;
+
+/// The binary format containers were read from, recorded in text so they are written back the
+/// same way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PropertyFileFormat {
+    /// A [`PropertyFile`](super::PropertyFile), keeping named keys apart from hashed ones.
+    #[default]
+    PropertyFile,
+    /// A [`PropertyBlockFile`](super::PropertyBlockFile), where every key is hashed.
+    BlockFile,
+}
+
+impl PropertyFileFormat {
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            Self::PropertyFile => "file",
+            Self::BlockFile => "block",
+        }
+    }
+
+    pub(crate) fn parse(format: &str) -> PropertyTextResult<Self> {
+        match format {
+            "file" => Ok(Self::PropertyFile),
+            "block" => Ok(Self::BlockFile),
+            _ => Err(PropertyTextError::UnknownFormat(format.into())),
+        }
+    }
+}
+
+/// A key as it appears in text. Keys stored by name are written as a name alone, while hashed
+/// keys are written as an id, along with their name if it is known.
+pub(crate) enum PropertyTextKey<'a> {
+    Name(&'a str),
+    Id(HashString),
+    ResolvedId(&'a str, HashString),
+}
+
+impl<'a> PropertyTextKey<'a> {
+    /// Writes keys found in `names` by name, and resolves the rest through `hashes`.
+    pub(crate) fn new(
+        hash: HashString,
+        names: Option<&'a HashList>,
+        hashes: Option<&'a HashList>,
+    ) -> Self {
+        if let Some(name) = names.and_then(|names| names.find_string(hash)) {
+            return Self::Name(name);
+        }
+        match hashes.and_then(|hashes| hashes.find_string(hash)) {
+            Some(name) => Self::ResolvedId(name, hash),
+            None => Self::Id(hash),
+        }
+    }
+
+    pub(crate) fn name(&self) -> Option<&'a str> {
+        match self {
+            Self::Name(name) | Self::ResolvedId(name, _) => Some(name),
+            Self::Id(_) => None,
+        }
+    }
+
+    pub(crate) fn id(&self) -> Option<String> {
+        match self {
+            Self::Id(hash) | Self::ResolvedId(_, hash) => Some(format_id(*hash)),
+            Self::Name(_) => None,
+        }
+    }
+
+    /// Parses a key, recording it into `names` if it is stored by name.
+    pub(crate) fn parse(
+        name: Option<String>,
+        id: Option<String>,
+        names: &mut Option<&mut HashList>,
+    ) -> PropertyTextResult<HashString> {
+        match (name, id) {
+            (Some(name), None) => {
+                let hash = HashString::from_str(&name);
+                if let Some(names) = names {
+                    names.insert_string(name);
+                }
+                Ok(hash)
+            }
+            (name, Some(id)) => {
+                let hash = parse_id(&id)?;
+                match name {
+                    Some(name) if HashString::from_str(&name) != hash => {
+                        Err(PropertyTextError::MismatchedKey { name, id })
+                    }
+                    _ => Ok(hash),
+                }
+            }
+            (None, None) => Err(PropertyTextError::MissingKey),
+        }
+    }
+}
+
+fn format_id(hash: HashString) -> String {
+    format!("0x{:08X}", hash.hash())
+}
+
+fn parse_id(id: &str) -> PropertyTextResult<HashString> {
+    id.strip_prefix("0x")
+        .or_else(|| id.strip_prefix("0X"))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .map(HashString::new)
+        .ok_or_else(|| PropertyTextError::InvalidId(id.into()))
+}
+
+pub(crate) const CONTAINER_TYPE: &str = "object";
+
+pub(crate) fn value_type(value: &PropertyValue) -> &'static str {
+    match value {
+        PropertyValue::Empty => "empty",
+        PropertyValue::I32(_) => "int",
+        PropertyValue::F32(_) => "float",
+        PropertyValue::String(_) => "string",
+        PropertyValue::Vec2(_) => "vec2",
+        PropertyValue::Vec3(_) => "vec3",
+        PropertyValue::Vec4(_) => "vec4",
+        PropertyValue::Mat3x3(_) => "mat3x3",
+        PropertyValue::Mat3x4(_) => "mat3x4",
+        PropertyValue::VecI32(_) => "vec_int",
+        PropertyValue::VecF32(_) => "vec_float",
+        PropertyValue::Raw(_) => "raw",
+    }
+}
+
+/// The components of a value, used to share formatting between text formats.
+pub(crate) enum PropertyTextValue<'a> {
+    Empty,
+    String(&'a str),
+    I32(Vec<i32>),
+    F32(Vec<f32>),
+}
+
+impl<'a> From<&'a PropertyValue> for PropertyTextValue<'a> {
+    fn from(value: &'a PropertyValue) -> Self {
+        match value {
+            PropertyValue::Empty => Self::Empty,
+            PropertyValue::I32(value) => Self::I32(vec![*value]),
+            PropertyValue::F32(value) => Self::F32(vec![*value]),
+            PropertyValue::String(value) => Self::String(value),
+            PropertyValue::Vec2(value) => Self::F32(<[f32; 2]>::from(*value).into()),
+            PropertyValue::Vec3(value) => Self::F32(<[f32; 3]>::from(*value).into()),
+            PropertyValue::Vec4(value) => Self::F32(<[f32; 4]>::from(*value).into()),
+            PropertyValue::Mat3x3(value) => Self::F32(<[f32; 9]>::from(*value).into()),
+            PropertyValue::Mat3x4(value) => Self::F32(<[f32; 12]>::from(*value).into()),
+            PropertyValue::VecI32(value) => Self::I32(value.clone()),
+            PropertyValue::VecF32(value) => Self::F32(value.clone()),
+            PropertyValue::Raw(value) => Self::I32(value.iter().map(|&byte| byte.into()).collect()),
+        }
+    }
+}
+
+impl PropertyTextValue<'_> {
+    /// Builds a value of the given type from its parsed components.
+    pub(crate) fn into_value(self, kind: &str) -> PropertyTextResult<PropertyValue> {
+        fn array<const N: usize>(value: Vec<f32>, kind: &str) -> PropertyTextResult<[f32; N]> {
+            value
+                .try_into()
+                .map_err(|value| PropertyTextError::InvalidValue {
+                    kind: kind.into(),
+                    value: format!("{value:?}"),
+                })
+        }
+
+        Ok(match (kind, self) {
+            ("empty", Self::Empty) => PropertyValue::Empty,
+            ("int", Self::I32(value)) if value.len() == 1 => PropertyValue::I32(value[0]),
+            ("float", Self::F32(value)) if value.len() == 1 => PropertyValue::F32(value[0]),
+            ("string", Self::String(value)) => PropertyValue::String(value.into()),
+            ("string", Self::Empty) => PropertyValue::String(String::new()),
+            ("vec2", Self::F32(value)) => PropertyValue::Vec2(array::<2>(value, kind)?.into()),
+            ("vec3", Self::F32(value)) => PropertyValue::Vec3(array::<3>(value, kind)?.into()),
+            ("vec4", Self::F32(value)) => PropertyValue::Vec4(array::<4>(value, kind)?.into()),
+            ("mat3x3", Self::F32(value)) => PropertyValue::Mat3x3(array::<9>(value, kind)?.into()),
+            ("mat3x4", Self::F32(value)) => PropertyValue::Mat3x4(array::<12>(value, kind)?.into()),
+            ("vec_int", Self::I32(value)) => PropertyValue::VecI32(value),
+            ("vec_int", Self::Empty) => PropertyValue::VecI32(vec![]),
+            ("vec_float", Self::F32(value)) => PropertyValue::VecF32(value),
+            ("vec_float", Self::Empty) => PropertyValue::VecF32(vec![]),
+            ("raw", Self::I32(value)) => PropertyValue::Raw(
+                value
+                    .iter()
+                    .map(|&byte| u8::try_from(byte))
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| PropertyTextError::InvalidValue {
+                        kind: kind.into(),
+                        value: format!("{value:?}"),
+                    })?,
+            ),
+            ("raw", Self::Empty) => PropertyValue::Raw(vec![]),
+            (
+                "empty" | "int" | "float" | "string" | "vec2" | "vec3" | "vec4" | "mat3x3"
+                | "mat3x4" | "vec_int" | "vec_float" | "raw",
+                value,
+            ) => {
+                return Err(PropertyTextError::InvalidValue {
+                    kind: kind.into(),
+                    value: value.to_string(),
+                });
+            }
+            _ => return Err(PropertyTextError::UnknownType(kind.into())),
+        })
+    }
+
+    /// Parses the comma separated components of a value of the given type.
+    #[cfg(feature = "xml")]
+    pub(crate) fn parse<'a>(
+        kind: &str,
+        text: &'a str,
+    ) -> PropertyTextResult<PropertyTextValue<'a>> {
+        fn parse<T: std::str::FromStr>(kind: &str, text: &str) -> PropertyTextResult<Vec<T>> {
+            text.split(',')
+                .map(|component| {
+                    component
+                        .trim()
+                        .parse()
+                        .ok()
+                        .ok_or_else(|| PropertyTextError::InvalidValue {
+                            kind: kind.into(),
+                            value: text.into(),
+                        })
+                })
+                .collect()
+        }
+
+        Ok(match kind {
+            "string" => PropertyTextValue::String(text),
+            _ if text.trim().is_empty() => PropertyTextValue::Empty,
+            "int" | "vec_int" | "raw" => PropertyTextValue::I32(parse(kind, text)?),
+            _ => PropertyTextValue::F32(parse(kind, text)?),
+        })
+    }
+}
+
+impl std::fmt::Display for PropertyTextValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // `Debug` is used as it produces the shortest round-trip representation of floats,
+        // while avoiding the long expansions `Display` produces for very large or small values
+        fn join<T: std::fmt::Debug>(
+            f: &mut std::fmt::Formatter<'_>,
+            values: &[T],
+        ) -> std::fmt::Result {
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(",")?;
+                }
+                write!(f, "{value:?}")?;
+            }
+            Ok(())
+        }
+
+        match self {
+            Self::Empty => Ok(()),
+            Self::String(value) => f.write_str(value),
+            Self::I32(values) => join(f, values),
+            Self::F32(values) => join(f, values),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::property_container::PropertyContainer;
+
+    /// Builds a container holding every type of value, along with the keys stored by name and
+    /// the names of hashed keys. Keys are named, hashed with a known name, or hashed alone.
+    #[cfg(any(feature = "json", feature = "xml"))]
+    pub(crate) fn containers() -> (Vec<PropertyContainer>, HashList, HashList) {
+        use crate::math::{Mat3, Mat3x4, Vec2, Vec3, Vec4};
+
+        let values = [
+            ("_class", PropertyValue::String("CLight".into())),
+            ("label", PropertyValue::String("<a & \"b\">, c".into())),
+            ("blank", PropertyValue::String(String::new())),
+            ("empty", PropertyValue::Empty),
+            ("int", PropertyValue::I32(-5)),
+            ("float", PropertyValue::F32(0.1)),
+            ("vec2", PropertyValue::Vec2(Vec2::new(1.0, -2.5))),
+            ("vec3", PropertyValue::Vec3(Vec3::new(0.0, 1.5, 1e-20))),
+            ("vec4", PropertyValue::Vec4(Vec4::new(1.0, 2.0, 3.0, 4.0))),
+            ("mat3x3", PropertyValue::Mat3x3(Mat3::identity())),
+            (
+                "world",
+                PropertyValue::Mat3x4(Mat3x4::from_translation(Vec3::new(1.0, 2.0, 3.0))),
+            ),
+            ("vec_int", PropertyValue::VecI32(vec![1, -2, i32::MAX])),
+            ("no_ints", PropertyValue::VecI32(vec![])),
+            (
+                "vec_float",
+                PropertyValue::VecF32(vec![0.5, 3e30, f32::INFINITY]),
+            ),
+            ("no_floats", PropertyValue::VecF32(vec![])),
+            ("raw", PropertyValue::Raw(vec![0, 127, 255])),
+        ];
+
+        let mut raw = PropertyContainer::new();
+        raw.insert_value(0x1234_5678, 7);
+        raw.set_raw(true);
+
+        let mut container = PropertyContainer::new();
+        for (key, value) in &values {
+            container.insert_value(*key, value.clone());
+        }
+        container.insert_value(0x8765_4321, 1.0f32);
+        container.insert_container("children", PropertyContainer::new());
+        container.insert_container("data", raw);
+
+        // `world` and `data` are hashed keys whose names are known
+        let mut names = HashList::new();
+        let mut hashes = HashList::new();
+        for (key, _) in &values {
+            names.insert_string(*key);
+        }
+        names.insert_string("children");
+        for key in ["world", "data"] {
+            names.remove(HashString::from_str(key));
+            hashes.insert_string(key);
+        }
+
+        (vec![container, PropertyContainer::new()], names, hashes)
+    }
+
+    #[test]
+    fn parse_keys() -> PropertyTextResult<()> {
+        let mut names = HashList::new();
+        let hash = PropertyTextKey::parse(Some("world".into()), None, &mut Some(&mut names))?;
+        assert_eq!(hash, HashString::from_str("world"));
+        assert!(names.contains(hash));
+
+        let id = format_id(hash);
+        assert_eq!(
+            PropertyTextKey::parse(None, Some(id.clone()), &mut None)?,
+            hash
+        );
+        assert_eq!(
+            PropertyTextKey::parse(Some("world".into()), Some(id), &mut None)?,
+            hash
+        );
+
+        assert!(matches!(
+            PropertyTextKey::parse(Some("color".into()), Some(format_id(hash)), &mut None),
+            Err(PropertyTextError::MismatchedKey { .. })
+        ));
+        assert!(matches!(
+            PropertyTextKey::parse(None, Some("12".into()), &mut None),
+            Err(PropertyTextError::InvalidId(_))
+        ));
+        assert!(matches!(
+            PropertyTextKey::parse(None, None, &mut None),
+            Err(PropertyTextError::MissingKey)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "xml")]
+    fn parse_values() {
+        let value = PropertyTextValue::parse("vec2", " 1, 2.5 ").and_then(|v| v.into_value("vec2"));
+        assert!(matches!(value, Ok(PropertyValue::Vec2(_))));
+
+        for (kind, text) in [("vec3", "1,2"), ("int", "1.5"), ("raw", "256")] {
+            let value = PropertyTextValue::parse(kind, text).and_then(|v| v.into_value(kind));
+            assert!(
+                matches!(value, Err(PropertyTextError::InvalidValue { .. })),
+                "{kind}: {value:?}"
+            );
+        }
+
+        let value = PropertyTextValue::parse("quat", "1").and_then(|v| v.into_value("quat"));
+        assert!(matches!(value, Err(PropertyTextError::UnknownType(_))));
+    }
+}
@@ -0,0 +1,300 @@
+use std::fmt::Write;
+
+use jc2_hashing::{HashList, HashString};
+use quick_xml::{
+    Reader,
+    escape::escape,
+    events::{BytesStart, Event},
+};
+
+use super::{
+    CONTAINER_TYPE, PropertyFileFormat, PropertyTextError, PropertyTextKey, PropertyTextResult,
+    PropertyTextValue, value_type,
+};
+use crate::property_container::{PropertyContainer, PropertyEntry};
+
+const ROOT: &str = "container";
+const VALUE: &str = "value";
+
+/// Serialises containers to XML. Keys found in `names` are written by name, as stored in named
+/// sections, while other keys are written by id along with any name `hashes` resolves them to.
+///
+/// ```xml
+/// <container format="file">
+///   <object>
+///     <value name="_class" type="string">CLight</value>
+///     <value id="0x1A2B3C4D" type="vec3">0,1.5,0</value>
+///     <object name="children" />
+///   </object>
+/// </container>
+/// ```
+pub fn to_string(
+    containers: &[PropertyContainer],
+    format: PropertyFileFormat,
+    names: Option<&HashList>,
+    hashes: Option<&HashList>,
+) -> String {
+    let mut result = String::new();
+    let _ = writeln!(result, "<{ROOT} format=\"{}\">", format.as_str());
+    for container in containers {
+        write_container(&mut result, None, container, names, hashes, 1);
+    }
+    let _ = writeln!(result, "</{ROOT}>");
+    result
+}
+
+/// Parses containers from XML along with the format they were read from, recording every key
+/// stored by name into `names` if provided.
+pub fn from_str(
+    text: &str,
+    mut names: Option<&mut HashList>,
+) -> PropertyTextResult<(Vec<PropertyContainer>, PropertyFileFormat)> {
+    let mut reader = Reader::from_str(text);
+    let mut result = vec![];
+
+    let format = loop {
+        match reader.read_event()? {
+            Event::Start(start) if start.name().as_ref() == ROOT.as_bytes() => {
+                break read_format(&start)?;
+            }
+            Event::Empty(start) if start.name().as_ref() == ROOT.as_bytes() => {
+                return Ok((result, read_format(&start)?));
+            }
+            Event::Start(start) | Event::Empty(start) => return Err(unexpected(&start)),
+            Event::Eof => return Err(PropertyTextError::UnexpectedEnd),
+            _ => {}
+        }
+    };
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) if start.name().as_ref() == CONTAINER_TYPE.as_bytes() => {
+                result.push(read_container(&mut reader, &mut names)?);
+            }
+            Event::Empty(start) if start.name().as_ref() == CONTAINER_TYPE.as_bytes() => {
+                result.push(PropertyContainer::new());
+            }
+            Event::Start(start) | Event::Empty(start) => return Err(unexpected(&start)),
+            Event::End(_) => return Ok((result, format)),
+            Event::Eof => return Err(PropertyTextError::UnexpectedEnd),
+            _ => {}
+        }
+    }
+}
+
+fn write_container(
+    result: &mut String,
+    hash: Option<HashString>,
+    container: &PropertyContainer,
+    names: Option<&HashList>,
+    hashes: Option<&HashList>,
+    depth: usize,
+) {
+    let indent = "  ".repeat(depth);
+    let mut key = hash
+        .map(|hash| key_attribute(hash, names, hashes))
+        .unwrap_or_default();
+    if container.is_raw() {
+        key.push_str(" raw=\"true\"");
+    }
+    if container.is_empty() {
+        let _ = writeln!(result, "{indent}<{CONTAINER_TYPE}{key} />");
+        return;
+    }
+
+    let _ = writeln!(result, "{indent}<{CONTAINER_TYPE}{key}>");
+    for (hash, entry) in container {
+        match entry {
+            PropertyEntry::Container(container) => {
+                write_container(result, Some(*hash), container, names, hashes, depth + 1);
+            }
+            PropertyEntry::Value(value) => {
+                let key = key_attribute(*hash, names, hashes);
+                let kind = value_type(value);
+                let text = PropertyTextValue::from(value).to_string();
+                let _ = if text.is_empty() {
+                    writeln!(result, "{indent}  <{VALUE}{key} type=\"{kind}\" />")
+                } else {
+                    writeln!(
+                        result,
+                        "{indent}  <{VALUE}{key} type=\"{kind}\">{}</{VALUE}>",
+                        escape(&text)
+                    )
+                };
+            }
+        }
+    }
+    let _ = writeln!(result, "{indent}</{CONTAINER_TYPE}>");
+}
+
+fn key_attribute(hash: HashString, names: Option<&HashList>, hashes: Option<&HashList>) -> String {
+    let key = PropertyTextKey::new(hash, names, hashes);
+    let mut result = String::new();
+    if let Some(name) = key.name() {
+        let _ = write!(result, " name=\"{}\"", escape(name));
+    }
+    if let Some(id) = key.id() {
+        let _ = write!(result, " id=\"{id}\"");
+    }
+    result
+}
+
+fn read_container(
+    reader: &mut Reader<&[u8]>,
+    names: &mut Option<&mut HashList>,
+) -> PropertyTextResult<PropertyContainer> {
+    let mut result = PropertyContainer::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => {
+                let (hash, kind) = read_attributes(&start, names)?;
+                match start.name().as_ref() {
+                    name if name == CONTAINER_TYPE.as_bytes() => {
+                        let mut container = read_container(reader, names)?;
+                        container.set_raw(read_raw(&start)?);
+                        result.insert_container(hash, container);
+                    }
+                    name if name == VALUE.as_bytes() => {
+                        let kind = kind.ok_or(PropertyTextError::MissingType)?;
+                        let text = reader.read_text(start.name())?;
+                        let text = quick_xml::escape::unescape(&text)?;
+                        let value = PropertyTextValue::parse(&kind, &text)?.into_value(&kind)?;
+                        result.insert_value(hash, value);
+                    }
+                    _ => return Err(unexpected(&start)),
+                }
+            }
+            Event::Empty(start) => {
+                let (hash, kind) = read_attributes(&start, names)?;
+                match start.name().as_ref() {
+                    name if name == CONTAINER_TYPE.as_bytes() => {
+                        let mut container = PropertyContainer::new();
+                        container.set_raw(read_raw(&start)?);
+                        result.insert_container(hash, container);
+                    }
+                    name if name == VALUE.as_bytes() => {
+                        let kind = kind.ok_or(PropertyTextError::MissingType)?;
+                        let value = PropertyTextValue::Empty.into_value(&kind)?;
+                        result.insert_value(hash, value);
+                    }
+                    _ => return Err(unexpected(&start)),
+                }
+            }
+            Event::End(_) => return Ok(result),
+            Event::Eof => return Err(PropertyTextError::UnexpectedEnd),
+            _ => {}
+        }
+    }
+}
+
+fn read_attributes(
+    start: &BytesStart<'_>,
+    names: &mut Option<&mut HashList>,
+) -> PropertyTextResult<(HashString, Option<String>)> {
+    let mut name = None;
+    let mut id = None;
+    let mut kind = None;
+    for attribute in start.attributes() {
+        let attribute = attribute?;
+        let value = attribute.unescape_value()?.into_owned();
+        match attribute.key.as_ref() {
+            b"name" => name = Some(value),
+            b"id" => id = Some(value),
+            b"type" => kind = Some(value),
+            _ => {}
+        }
+    }
+    Ok((PropertyTextKey::parse(name, id, names)?, kind))
+}
+
+/// Reads whether a container element was decoded from a raw section.
+fn read_raw(start: &BytesStart<'_>) -> PropertyTextResult<bool> {
+    match start.try_get_attribute("raw")? {
+        Some(raw) => Ok(raw.unescape_value()? == "true"),
+        None => Ok(false),
+    }
+}
+
+/// Reads the format recorded on the root element, defaulting to a property file.
+fn read_format(start: &BytesStart<'_>) -> PropertyTextResult<PropertyFileFormat> {
+    match start.try_get_attribute("format")? {
+        Some(format) => PropertyFileFormat::parse(&format.unescape_value()?),
+        None => Ok(PropertyFileFormat::default()),
+    }
+}
+
+fn unexpected(start: &BytesStart<'_>) -> PropertyTextError {
+    PropertyTextError::UnexpectedElement(String::from_utf8_lossy(start.name().as_ref()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property_container::text::tests::containers;
+
+    #[test]
+    fn round_trip() -> PropertyTextResult<()> {
+        let (containers, names, hashes) = containers();
+        let text = to_string(
+            &containers,
+            PropertyFileFormat::BlockFile,
+            Some(&names),
+            Some(&hashes),
+        );
+        assert!(text.contains(r#"name="world""#));
+        assert!(text.contains(r#"raw="true""#));
+
+        let mut result_names = HashList::new();
+        let (result, format) = from_str(&text, Some(&mut result_names))?;
+        assert_eq!(result, containers);
+        assert_eq!(format, PropertyFileFormat::BlockFile);
+        assert!(
+            result[0]
+                .get_container("data")
+                .is_some_and(PropertyContainer::is_raw)
+        );
+
+        // Only keys written by name are recorded
+        assert_eq!(result_names.len(), names.len());
+        assert!(names.keys().all(|hash| result_names.contains(*hash)));
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_ids() -> PropertyTextResult<()> {
+        let (containers, ..) = containers();
+        let text = to_string(&containers, PropertyFileFormat::PropertyFile, None, None);
+        assert!(!text.contains("name="));
+
+        let mut names = HashList::new();
+        let (result, format) = from_str(&text, Some(&mut names))?;
+        assert_eq!(result, containers);
+        assert_eq!(format, PropertyFileFormat::PropertyFile);
+        assert!(names.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn read_default_format() -> PropertyTextResult<()> {
+        let (result, format) = from_str("<container />", None)?;
+        assert!(result.is_empty());
+        assert_eq!(format, PropertyFileFormat::PropertyFile);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_invalid_elements() {
+        for text in [
+            r#"<container><object><value name="a" id="0x12345678" type="int">1</value></object></container>"#,
+            r#"<container><object><value name="a">1</value></object></container>"#,
+            r#"<container><object><value name="a" type="int">a</value></object></container>"#,
+            r#"<container><object><value name="a" type="quat">0,0,0,1</value></object></container>"#,
+            r#"<container><object><entry name="a" /></object></container>"#,
+            r#"<container format="text" />"#,
+            "<container><object>",
+        ] {
+            assert!(from_str(text, None).is_err(), "{text}");
+        }
+    }
+}
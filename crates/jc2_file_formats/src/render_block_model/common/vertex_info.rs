@@ -1,6 +1,9 @@
 use binrw::{BinRead, BinWrite, binrw};
 
-use crate::math::{Vec2, Vec4};
+use crate::{
+    math::{Vec2, Vec4},
+    render_block_model::GeneralVertex,
+};
 
 #[binrw]
 #[brw(repr = u32)]
@@ -34,6 +37,45 @@ impl Default for VertexInfo {
     }
 }
 
+impl VertexInfo {
+    /// Creates the vertex info for `format`, scaling the vertices into the `[-1, 1]` range the
+    /// quantised `I16` format can hold. Vertices are left as they are for `F32`.
+    pub fn quantise(format: VertexFormat, vertices: &mut [GeneralVertex]) -> Self {
+        let mut result = Self {
+            format,
+            ..Default::default()
+        };
+
+        if format == VertexFormat::F32 {
+            return result;
+        }
+
+        // Extents are uniform, as older versions only store one per set of UVs
+        let (mut scale, mut uv0_extent, mut uv1_extent) = (0f32, 0f32, 0f32);
+        for vertex in vertices.iter() {
+            let (position, uv0, uv1) = (vertex.position, vertex.uv0, vertex.uv1);
+            scale = scale.max(position.x.abs().max(position.y.abs()).max(position.z.abs()));
+            uv0_extent = uv0_extent.max(uv0.x.abs().max(uv0.y.abs()));
+            uv1_extent = uv1_extent.max(uv1.x.abs().max(uv1.y.abs()));
+        }
+
+        let non_zero = |extent: f32| if extent > 0.0 { extent } else { 1.0 };
+        let (scale, uv0_extent, uv1_extent) =
+            (non_zero(scale), non_zero(uv0_extent), non_zero(uv1_extent));
+
+        for vertex in vertices.iter_mut() {
+            vertex.position = vertex.position / scale;
+            vertex.uv0 = Vec2::new(vertex.uv0.x / uv0_extent, vertex.uv0.y / uv0_extent);
+            vertex.uv1 = Vec2::new(vertex.uv1.x / uv1_extent, vertex.uv1.y / uv1_extent);
+        }
+
+        result.scale = scale;
+        result.uv0_extent = Vec2::splat(uv0_extent);
+        result.uv1_extent = Vec2::splat(uv1_extent);
+        result
+    }
+}
+
 impl BinRead for VertexInfo {
     type Args<'a> = (bool,);
 
@@ -74,7 +116,7 @@ impl BinWrite for VertexInfo {
         self.format.write_options(writer, endian, ())?;
         self.scale.write_options(writer, endian, ())?;
         if args.0 {
-            self.uv1_extent.write_options(writer, endian, ())?;
+            self.uv0_extent.write_options(writer, endian, ())?;
             self.uv1_extent.write_options(writer, endian, ())?;
         } else {
             self.uv0_extent.x.write_options(writer, endian, ())?;
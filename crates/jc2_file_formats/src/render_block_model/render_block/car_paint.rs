@@ -117,6 +117,7 @@ impl BinWrite for CarPaintRenderBlock {
         args: Self::Args<'_>,
     ) -> binrw::prelude::BinResult<()> {
         self.version.write_options(writer, endian, ())?;
+        self.attributes.write_options(writer, endian, ())?;
         if self.version != CarPaintVersion::V3 {
             self.deform_table.write_options(writer, endian, args)?;
         }
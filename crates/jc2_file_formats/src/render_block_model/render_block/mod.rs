@@ -1,6 +1,7 @@
 use std::ops::{Deref, DerefMut};
 
 use binrw::{BinRead, BinWrite, binrw};
+use jc2_hashing_derive::hash_magic;
 
 use super::RenderBlockError;
 
@@ -40,79 +41,100 @@ pub use vegetation_foliage::*;
 mod window;
 pub use window::*;
 
+#[hash_magic]
 #[binrw]
 #[rustfmt::skip]
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug)]
 pub enum RenderBlock {
-    /// HashString::from_str("BillboardFoliage")
-    #[brw(magic(2907872880u32))]
+    #[hash_magic("BillboardFoliage")]
     BillboardFoliage(BillboardFoliageRenderBlock),
 
-    // /// HashString::from_str("Box")
-    // #[brw(magic(1097613365u32))]
+    // #[hash_magic("Box")]
     // Box(BoxRenderBlock),
 
-    /// HashString::from_str("CarPaint")
-    #[brw(magic(3448970869u32))]
+    #[hash_magic("CarPaint")]
     CarPaint(CarPaintRenderBlock),
 
-    /// HashString::from_str("CarPaintSimple")
-    #[brw(magic(2173928592u32))]
+    #[hash_magic("CarPaintSimple")]
     CarPaintSimple(CarPaintSimpleRenderBlock),
 
-    /// HashString::from_str("DeformableWindow")
-    #[brw(magic(112326146u32))]
+    #[hash_magic("DeformableWindow")]
     DeformableWindow(DeformableWindowRenderBlock),
 
-    /// HashString::from_str("Facade")
-    #[brw(magic(3459897279u32))]
+    #[hash_magic("Facade")]
     Facade(FacadeRenderBlock),
 
-    /// HashString::from_str("General")
-    #[brw(magic(2807577387u32))]
+    #[hash_magic("General")]
     General(GeneralRenderBlock),
 
-    /// HashString::from_str("Halo")
-    #[brw(magic(1708766642u32))]
+    #[hash_magic("Halo")]
     Halo(HaloRenderBlock),
 
-    /// HashString::from_str("Lambert")
-    #[brw(magic(3587672800u32))]
+    #[hash_magic("Lambert")]
     Lambert(LambertRenderBlock),
 
-    // /// HashString::from_str("Merged")
-    // #[brw(magic(2441454787u32))]
+    // #[hash_magic("Merged")]
     // Merged(MergedRenderBlock),
 
-    // /// HashString::from_str("Occluder")
-    // #[brw(magic(709121340u32))]
+    // #[hash_magic("Occluder")]
     // Occluder(OccluderRenderBlock),
 
-    // /// HashString::from_str("Road")
-    // #[brw(magic(1183865387u32))]
+    // #[hash_magic("Road")]
     // Road(RoadRenderBlock),
 
-    /// HashString::from_str("SkinnedGeneral")
-    #[brw(magic(1583709984u32))]
+    #[hash_magic("SkinnedGeneral")]
     SkinnedGeneral(SkinnedGeneralRenderBlock),
 
-    /// HashString::from_str("VegetationBark")
-    #[brw(magic(2985890621u32))]
+    #[hash_magic("VegetationBark")]
     VegetationBark(VegetationBarkRenderBlock),
 
-    /// HashString::from_str("VegetationFoliage")
-    #[brw(magic(3617096902u32))]
+    #[hash_magic("VegetationFoliage")]
     VegetationFoliage(VegetationFoliageRenderBlock),
 
-    /// HashString::from_str("Window")
-    #[brw(magic(1528824822u32))]
+    #[hash_magic("Window")]
     Window(WindowRenderBlock),
 }
 
+impl RenderBlock {
+    /// Returns the paths of every texture the block's material references.
+    pub fn texture_paths(&self) -> impl Iterator<Item = &str> {
+        let material = match self {
+            RenderBlock::BillboardFoliage(block) => &block.material,
+            RenderBlock::CarPaint(block) => &block.material,
+            RenderBlock::CarPaintSimple(block) => &block.material,
+            RenderBlock::DeformableWindow(block) => &block.material,
+            RenderBlock::Facade(block) => &block.material,
+            RenderBlock::General(block) => &block.material,
+            RenderBlock::Halo(block) => &block.material,
+            RenderBlock::Lambert(block) => &block.material,
+            RenderBlock::SkinnedGeneral(block) => &block.material,
+            RenderBlock::VegetationBark(block) => &block.material,
+            RenderBlock::VegetationFoliage(block) => &block.material,
+            RenderBlock::Window(block) => &block.material,
+        };
+        material
+            .textures
+            .iter()
+            .map(|texture| texture.as_str())
+            .filter(|texture| !texture.is_empty())
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct RenderBlocks(Vec<RenderBlock>);
 
+impl RenderBlocks {
+    const BLOCK_FOOTER: u32 = 2309737967u32;
+}
+
+impl From<Vec<RenderBlock>> for RenderBlocks {
+    #[inline]
+    fn from(value: Vec<RenderBlock>) -> Self {
+        Self(value)
+    }
+}
+
 impl Deref for RenderBlocks {
     type Target = Vec<RenderBlock>;
 
@@ -145,8 +167,7 @@ impl BinRead for RenderBlocks {
         for _ in 0..length {
             blocks.push(RenderBlock::read_options(reader, endian, ())?);
 
-            const BLOCK_FOOTER: u32 = 2309737967u32;
-            if u32::read_options(reader, endian, ())? != BLOCK_FOOTER {
+            if u32::read_options(reader, endian, ())? != RenderBlocks::BLOCK_FOOTER {
                 return Err(BinError::Custom {
                     pos: reader.stream_position()?,
                     err: Box::new(RenderBlockError::InvalidBlockFooter),
@@ -169,8 +190,9 @@ impl BinWrite for RenderBlocks {
     ) -> binrw::prelude::BinResult<()> {
         if let Ok(length) = u32::try_from(self.len()) {
             length.write_options(writer, endian, ())?;
-            for index in self.iter() {
-                index.write_options(writer, endian, ())?;
+            for block in self.iter() {
+                block.write_options(writer, endian, ())?;
+                RenderBlocks::BLOCK_FOOTER.write_options(writer, endian, ())?;
             }
             Ok(())
         } else {
@@ -31,6 +31,13 @@ impl<T: Vertex> DerefMut for VertexBuffer<T> {
     }
 }
 
+impl<T: Vertex> From<Vec<T>> for VertexBuffer<T> {
+    #[inline]
+    fn from(value: Vec<T>) -> Self {
+        Self(value)
+    }
+}
+
 impl<T: Vertex> BinRead for VertexBuffer<T>
 where
     T: for<'a> BinRead<Args<'a> = T::VertexArgs> + for<'b> BinWrite<Args<'b> = T::VertexArgs>,
@@ -111,6 +118,13 @@ impl<T: Index> DerefMut for IndexBuffer<T> {
     }
 }
 
+impl<T: Index> From<Vec<T>> for IndexBuffer<T> {
+    #[inline]
+    fn from(value: Vec<T>) -> Self {
+        Self(value)
+    }
+}
+
 type BinError = binrw::Error;
 
 impl<T: Index + AsPrimitive<usize>> BinRead for IndexBuffer<T> {
@@ -297,10 +297,10 @@ impl BinWrite for VertexBuffer<LitDeformableVertex> {
                     morph_tangent: vertex.morph_tangent,
                 });
             }
-            positions.write_options(writer, endian, ())?;
-            datas.write_options(writer, endian, ())?;
+            DeformablePositions::from(positions).write_options(writer, endian, ())?;
+            LitDeformableData::from(datas).write_options(writer, endian, ())?;
         } else {
-            vertices.write_options(writer, endian, ())?;
+            PackedLitDeformableVertices::from(vertices).write_options(writer, endian, ())?;
         }
         Ok(())
     }
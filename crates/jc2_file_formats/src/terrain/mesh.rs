@@ -0,0 +1,257 @@
+use bitvec::vec::BitVec;
+use thiserror::Error;
+
+use super::TerrainMesh;
+
+/// The number of grid cells along each side of a terrain mesh.
+pub const TERRAIN_MESH_DIVISIONS: u8 = 64;
+
+const POINTS: usize = TERRAIN_MESH_DIVISIONS as usize + 1;
+const HALF: u8 = TERRAIN_MESH_DIVISIONS / 2;
+
+#[derive(Error, Debug)]
+pub enum TerrainError {
+    #[error("unexpected end of triangle tree")]
+    UnexpectedEnd,
+}
+
+/// The triangles of a terrain mesh, on a grid of [`TERRAIN_MESH_DIVISIONS`] cells along each
+/// side.
+#[derive(Clone, Debug, Default)]
+pub struct TerrainTriangles {
+    /// The grid position of each vertex, the four corners coming first.
+    pub vertices: Vec<[u8; 2]>,
+    /// The triangles in each quadrant, ordered by x then z. Triangles crossing the middle of
+    /// the mesh are kept in the lowest quadrant they touch.
+    pub quadrants: [Vec<[u16; 3]>; 4],
+}
+
+impl TerrainTriangles {
+    fn vertex(&mut self, grid: &mut [u16], position: [u8; 2]) -> u16 {
+        let index = grid_index(position);
+        if grid[index] == u16::MAX {
+            grid[index] = self.vertices.len() as u16;
+            self.vertices.push(position);
+        }
+        grid[index]
+    }
+
+    fn quadrant(&self, triangle: [u16; 3]) -> usize {
+        quadrant(triangle.map(|vertex| self.vertices[usize::from(vertex)]))
+    }
+
+    /// Returns the triangles of every quadrant in turn.
+    pub fn triangles(&self) -> impl Iterator<Item = &[u16; 3]> {
+        self.quadrants.iter().flatten()
+    }
+}
+
+fn quadrant(positions: [[u8; 2]; 3]) -> usize {
+    let x = positions.iter().all(|position| position[0] >= HALF);
+    let z = positions.iter().all(|position| position[1] >= HALF);
+    usize::from(x) + usize::from(z) * 2
+}
+
+/// Returns the middle of an edge, if it falls on the grid.
+fn midpoint(b: [u8; 2], c: [u8; 2]) -> Option<[u8; 2]> {
+    let sum = [b[0] + c[0], b[1] + c[1]];
+    (sum[0] % 2 == 0 && sum[1] % 2 == 0).then(|| sum.map(|axis| axis / 2))
+}
+
+const fn roots(flipped: bool) -> [[[u8; 2]; 3]; 2] {
+    const MAX: u8 = TERRAIN_MESH_DIVISIONS;
+    if flipped {
+        [
+            [[0, 0], [0, MAX], [MAX, 0]],
+            [[MAX, MAX], [MAX, 0], [0, MAX]],
+        ]
+    } else {
+        [
+            [[MAX, 0], [0, 0], [MAX, MAX]],
+            [[0, MAX], [MAX, MAX], [0, 0]],
+        ]
+    }
+}
+
+const fn grid_index(position: [u8; 2]) -> usize {
+    position[0] as usize + position[1] as usize * POINTS
+}
+
+impl TerrainMesh {
+    /// Splits the mesh into triangles. Each bit of the tree says whether a triangle is split in
+    /// two across its longest edge, starting from the two halves of the square. Neighbouring
+    /// meshes split the square along opposite diagonals, so `flipped` should be set when the
+    /// sum of the mesh's grid coordinates is odd.
+    pub fn triangulate(&self, flipped: bool) -> Result<TerrainTriangles, TerrainError> {
+        let mut result = TerrainTriangles::default();
+        let mut grid = vec![u16::MAX; POINTS * POINTS];
+
+        let max = TERRAIN_MESH_DIVISIONS;
+        for corner in [[0, 0], [max, 0], [0, max], [max, max]] {
+            result.vertex(&mut grid, corner);
+        }
+
+        let mut bits = self.triangles.value.iter().by_vals();
+        for root in roots(flipped) {
+            let root = root.map(|position| result.vertex(&mut grid, position));
+            let mut stack = vec![root];
+            while let Some(triangle @ [a, b, c]) = stack.pop() {
+                if bits.next().ok_or(TerrainError::UnexpectedEnd)? {
+                    let [b, c] = [b, c].map(|vertex| result.vertices[usize::from(vertex)]);
+                    let middle = result.vertex(&mut grid, [(b[0] + c[0]) / 2, (b[1] + c[1]) / 2]);
+                    stack.push([middle, triangle[2], a]);
+                    stack.push([middle, a, triangle[1]]);
+                } else {
+                    let quadrant = result.quadrant(triangle);
+                    result.quadrants[quadrant].push(triangle);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Builds a mesh from the height of each point on its grid, splitting triangles until every
+    /// point is within `tolerance` of the surface. The edges are always split as far as they
+    /// go, so that meshes built separately meet without cracks.
+    pub fn from_heights(height: impl Fn([u8; 2]) -> f32, flipped: bool, tolerance: f32) -> Self {
+        let mut heights = vec![0.0; POINTS * POINTS];
+        for z in 0..=TERRAIN_MESH_DIVISIONS {
+            for x in 0..=TERRAIN_MESH_DIVISIONS {
+                heights[grid_index([x, z])] = height([x, z]);
+            }
+        }
+
+        // Every triangle of the fully split tree, one level at a time
+        let mut levels = vec![roots(flipped).to_vec()];
+        while let Some(level) = levels
+            .last()
+            .filter(|level| midpoint(level[0][1], level[0][2]).is_some())
+        {
+            let next = level
+                .iter()
+                .filter_map(|&[a, b, c]| Some([midpoint(b, c)?, a, b, c]))
+                .flat_map(|[m, a, b, c]| [[m, c, a], [m, a, b]])
+                .collect();
+            levels.push(next);
+        }
+
+        // A point must be added if it or any point splitting the triangles it creates is too
+        // far from the surface, which keeps neighbouring triangles split alike
+        let mut errors = vec![0.0f32; POINTS * POINTS];
+        for level in levels.iter().rev() {
+            for &[a, b, c] in level {
+                let Some(m) = midpoint(b, c) else {
+                    continue;
+                };
+                let error = if m.contains(&0) || m.contains(&TERRAIN_MESH_DIVISIONS) {
+                    f32::INFINITY
+                } else {
+                    let [m, b, c] = [m, b, c].map(|position| heights[grid_index(position)]);
+                    (m - (b + c) / 2.0).abs()
+                };
+                let error = [midpoint(c, a), midpoint(a, b)]
+                    .into_iter()
+                    .flatten()
+                    .map(|child| errors[grid_index(child)])
+                    .fold(error, f32::max);
+                errors[grid_index(m)] = errors[grid_index(m)].max(error);
+            }
+        }
+
+        let mut triangles = BitVec::new();
+        let mut index_counts = [0; 4];
+        for root in roots(flipped) {
+            let mut stack = vec![root];
+            while let Some(triangle @ [a, b, c]) = stack.pop() {
+                let split = midpoint(b, c).filter(|&m| errors[grid_index(m)] > tolerance);
+                triangles.push(split.is_some());
+                if let Some(m) = split {
+                    stack.push([m, c, a]);
+                    stack.push([m, a, b]);
+                } else {
+                    index_counts[quadrant(triangle)] += 3;
+                }
+            }
+        }
+
+        Self {
+            triangles: triangles.into(),
+            index_counts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: u8 = TERRAIN_MESH_DIVISIONS;
+
+    fn hills([x, z]: [u8; 2]) -> f32 {
+        (f32::from(x) * 0.3).sin() * 20.0 + (f32::from(z) * 0.2).cos() * 10.0
+    }
+
+    /// Checks the mesh triangulates into the triangles its index counts describe.
+    fn check(mesh: &TerrainMesh, flipped: bool) -> Result<TerrainTriangles, TerrainError> {
+        let triangles = mesh.triangulate(flipped)?;
+        let counts = triangles
+            .quadrants
+            .each_ref()
+            .map(|quadrant| quadrant.len() as u16 * 3);
+        assert_eq!(counts, mesh.index_counts);
+
+        // Every split adds a triangle, starting from the two halves of the square
+        let count = triangles.triangles().count();
+        assert_eq!(mesh.triangles.value.len(), count * 2 - 2);
+
+        // Every point along the edges is used, so that neighbouring meshes meet without cracks
+        for i in 0..=MAX {
+            for point in [[i, 0], [i, MAX], [0, i], [MAX, i]] {
+                assert!(triangles.vertices.contains(&point), "{point:?}");
+            }
+        }
+        Ok(triangles)
+    }
+
+    #[test]
+    fn flat_mesh() -> Result<(), TerrainError> {
+        for flipped in [false, true] {
+            let mesh = TerrainMesh::from_heights(|_| 100.0, flipped, 0.5);
+            let triangles = check(&mesh, flipped)?;
+            // Nothing needs splitting away from the edges, which are split all the way
+            assert!(triangles.vertices.len() < POINTS * POINTS / 2);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn uneven_mesh() -> Result<(), TerrainError> {
+        for flipped in [false, true] {
+            let coarse = TerrainMesh::from_heights(hills, flipped, 4.0);
+            let fine = TerrainMesh::from_heights(hills, flipped, 0.5);
+            let (coarse, fine) = (check(&coarse, flipped)?, check(&fine, flipped)?);
+            assert!(coarse.vertices.len() < fine.vertices.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fully_split_mesh() -> Result<(), TerrainError> {
+        let mesh = TerrainMesh::from_heights(hills, false, -1.0);
+        let triangles = check(&mesh, false)?;
+        assert_eq!(triangles.vertices.len(), POINTS * POINTS);
+        assert_eq!(triangles.triangles().count(), usize::from(MAX).pow(2) * 2);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_truncated_tree() {
+        let mut mesh = TerrainMesh::from_heights(hills, false, 0.5);
+        mesh.triangles.value.pop();
+        assert!(matches!(
+            mesh.triangulate(false),
+            Err(TerrainError::UnexpectedEnd)
+        ));
+    }
+}
@@ -2,6 +2,9 @@ use binrw::binrw;
 
 use crate::common::{LengthBitVec, LengthVec};
 
+mod mesh;
+pub use mesh::*;
+
 #[binrw]
 #[brw(magic = 12u32)]
 #[derive(Clone, Debug)]
@@ -0,0 +1,93 @@
+use binrw::binrw;
+use bitflags::bitflags;
+
+#[binrw]
+#[brw(little, magic = b"DDS ")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DdsHeader {
+    #[brw(magic = 124u32)]
+    pub flags: DdsFlags,
+    pub height: u32,
+    pub width: u32,
+    pub pitch_or_linear_size: u32,
+    pub depth: u32,
+    pub mip_map_count: u32,
+    pub reserved: [u32; 11],
+    pub pixel_format: DdsPixelFormat,
+    pub caps: DdsCaps,
+    pub caps2: DdsCaps2,
+    pub caps3: u32,
+    pub caps4: u32,
+    pub reserved2: u32,
+}
+
+#[binrw]
+#[brw(little, magic = 32u32)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DdsPixelFormat {
+    pub flags: DdsPixelFormatFlags,
+    pub four_cc: [u8; 4],
+    pub rgb_bit_count: u32,
+    pub masks: [u32; 4],
+}
+
+bitflags! {
+    #[binrw]
+    #[br(map = Self::from_bits_retain)]
+    #[bw(map = |&x: &Self| x.bits())]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct DdsFlags: u32 {
+        const CAPS = 0x1;
+        const HEIGHT = 0x2;
+        const WIDTH = 0x4;
+        const PITCH = 0x8;
+        const PIXEL_FORMAT = 0x1000;
+        const MIP_MAP_COUNT = 0x20000;
+        const LINEAR_SIZE = 0x80000;
+        const DEPTH = 0x800000;
+    }
+}
+
+bitflags! {
+    #[binrw]
+    #[br(map = Self::from_bits_retain)]
+    #[bw(map = |&x: &Self| x.bits())]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct DdsPixelFormatFlags: u32 {
+        const ALPHA_PIXELS = 0x1;
+        const ALPHA = 0x2;
+        const FOUR_CC = 0x4;
+        const RGB = 0x40;
+        const LUMINANCE = 0x20000;
+    }
+}
+
+bitflags! {
+    #[binrw]
+    #[br(map = Self::from_bits_retain)]
+    #[bw(map = |&x: &Self| x.bits())]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct DdsCaps: u32 {
+        const COMPLEX = 0x8;
+        const TEXTURE = 0x1000;
+        const MIP_MAP = 0x400000;
+    }
+}
+
+bitflags! {
+    #[binrw]
+    #[br(map = Self::from_bits_retain)]
+    #[bw(map = |&x: &Self| x.bits())]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct DdsCaps2: u32 {
+        const CUBEMAP = 0x200;
+        const CUBEMAP_POSITIVE_X = 0x400;
+        const CUBEMAP_NEGATIVE_X = 0x800;
+        const CUBEMAP_POSITIVE_Y = 0x1000;
+        const CUBEMAP_NEGATIVE_Y = 0x2000;
+        const CUBEMAP_POSITIVE_Z = 0x4000;
+        const CUBEMAP_NEGATIVE_Z = 0x8000;
+        const CUBEMAP_ALL_FACES = 0xFC00;
+        const VOLUME = 0x200000;
+    }
+}
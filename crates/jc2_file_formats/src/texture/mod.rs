@@ -0,0 +1,629 @@
+use std::io::{Read, Seek, Write};
+
+use binrw::{BinRead, BinWrite};
+use image::{RgbaImage, imageops::FilterType};
+use thiserror::Error;
+
+mod header;
+pub use header::*;
+
+#[derive(Error, Debug)]
+pub enum TextureError {
+    #[error("unsupported dds format ({0})")]
+    UnsupportedFormat(String),
+    #[error("invalid texture size ({width}x{height})")]
+    InvalidSize { width: u32, height: u32 },
+    #[error("invalid pixel data length (expected {expected}, found {found})")]
+    InvalidLength { expected: usize, found: usize },
+    #[error("invalid surface (face {face}, mip {mip})")]
+    InvalidSurface { face: usize, mip: usize },
+    #[error("cubemaps need exactly 6 faces of the same size")]
+    InvalidCubemap,
+    #[error(transparent)]
+    Binary(#[from] binrw::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// How the pixels of a texture are stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DdsFormat {
+    /// `DXT1`, with 1-bit alpha.
+    Bc1,
+    /// `DXT3`, with explicit 4-bit alpha.
+    Bc2,
+    /// `DXT5`, with interpolated alpha.
+    Bc3,
+    /// `ATI1`, a single channel.
+    Bc4,
+    /// `ATI2`, two channels, used for normal maps.
+    Bc5,
+    /// Pixels stored as is, with a mask for each channel. Luminance formats only use the first
+    /// and last masks.
+    Uncompressed {
+        bit_count: u32,
+        masks: [u32; 4],
+        luminance: bool,
+    },
+}
+
+impl DdsFormat {
+    pub const BGRA8: Self = Self::Uncompressed {
+        bit_count: 32,
+        masks: [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000],
+        luminance: false,
+    };
+
+    pub const L8: Self = Self::Uncompressed {
+        bit_count: 8,
+        masks: [0xFF, 0, 0, 0],
+        luminance: true,
+    };
+
+    pub fn from_pixel_format(pixel_format: &DdsPixelFormat) -> Result<Self, TextureError> {
+        let flags = pixel_format.flags;
+
+        if flags.contains(DdsPixelFormatFlags::FOUR_CC) {
+            return match &pixel_format.four_cc {
+                b"DXT1" => Ok(Self::Bc1),
+                b"DXT2" | b"DXT3" => Ok(Self::Bc2),
+                b"DXT4" | b"DXT5" => Ok(Self::Bc3),
+                b"ATI1" | b"BC4U" => Ok(Self::Bc4),
+                b"ATI2" | b"BC5U" => Ok(Self::Bc5),
+                four_cc => Err(TextureError::UnsupportedFormat(
+                    String::from_utf8_lossy(four_cc).into_owned(),
+                )),
+            };
+        }
+
+        if !flags.intersects(DdsPixelFormatFlags::RGB | DdsPixelFormatFlags::LUMINANCE) {
+            return Err(TextureError::UnsupportedFormat(format!(
+                "flags 0x{:X}",
+                flags.bits()
+            )));
+        }
+
+        let bit_count = pixel_format.rgb_bit_count;
+        if !matches!(bit_count, 8 | 16 | 24 | 32) {
+            return Err(TextureError::UnsupportedFormat(format!("{bit_count} bit")));
+        }
+
+        let mut masks = pixel_format.masks;
+        if !flags.contains(DdsPixelFormatFlags::ALPHA_PIXELS) {
+            masks[3] = 0;
+        }
+
+        Ok(Self::Uncompressed {
+            bit_count,
+            masks,
+            luminance: flags.contains(DdsPixelFormatFlags::LUMINANCE),
+        })
+    }
+
+    pub fn pixel_format(&self) -> DdsPixelFormat {
+        let four_cc = |four_cc: &[u8; 4]| DdsPixelFormat {
+            flags: DdsPixelFormatFlags::FOUR_CC,
+            four_cc: *four_cc,
+            ..Default::default()
+        };
+
+        match *self {
+            Self::Bc1 => four_cc(b"DXT1"),
+            Self::Bc2 => four_cc(b"DXT3"),
+            Self::Bc3 => four_cc(b"DXT5"),
+            Self::Bc4 => four_cc(b"ATI1"),
+            Self::Bc5 => four_cc(b"ATI2"),
+            Self::Uncompressed {
+                bit_count,
+                masks,
+                luminance,
+            } => {
+                let mut flags = if luminance {
+                    DdsPixelFormatFlags::LUMINANCE
+                } else {
+                    DdsPixelFormatFlags::RGB
+                };
+                flags.set(DdsPixelFormatFlags::ALPHA_PIXELS, masks[3] != 0);
+                DdsPixelFormat {
+                    flags,
+                    four_cc: [0; 4],
+                    rgb_bit_count: bit_count,
+                    masks,
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub const fn is_compressed(&self) -> bool {
+        !matches!(self, Self::Uncompressed { .. })
+    }
+
+    #[inline]
+    const fn block_format(&self) -> Option<texpresso::Format> {
+        match self {
+            Self::Bc1 => Some(texpresso::Format::Bc1),
+            Self::Bc2 => Some(texpresso::Format::Bc2),
+            Self::Bc3 => Some(texpresso::Format::Bc3),
+            Self::Bc4 => Some(texpresso::Format::Bc4),
+            Self::Bc5 => Some(texpresso::Format::Bc5),
+            Self::Uncompressed { .. } => None,
+        }
+    }
+
+    /// Returns the number of bytes a single surface of the given size takes up.
+    pub fn surface_size(&self, width: u32, height: u32) -> usize {
+        let (width, height) = (width as usize, height as usize);
+        if let Self::Uncompressed { bit_count, .. } = *self {
+            return width * height * bit_count as usize / 8;
+        }
+        self.block_format()
+            .map_or(0, |format| format.compressed_size(width, height))
+    }
+
+    fn decode(&self, data: &[u8], width: u32, height: u32) -> RgbaImage {
+        let mut image = RgbaImage::new(width, height);
+
+        match *self {
+            Self::Uncompressed {
+                bit_count,
+                masks,
+                luminance,
+            } => {
+                let bytes_per_pixel = bit_count as usize / 8;
+                for (pixel, bytes) in image.pixels_mut().zip(data.chunks_exact(bytes_per_pixel)) {
+                    let mut value = [0u8; 4];
+                    value[..bytes_per_pixel].copy_from_slice(bytes);
+                    let value = u32::from_le_bytes(value);
+
+                    let r = unpack_channel(value, masks[0], 0);
+                    let (g, b) = if luminance {
+                        (r, r)
+                    } else {
+                        (
+                            unpack_channel(value, masks[1], 0),
+                            unpack_channel(value, masks[2], 0),
+                        )
+                    };
+                    pixel.0 = [r, g, b, unpack_channel(value, masks[3], u8::MAX)];
+                }
+            }
+            _ => {
+                if let Some(format) = self.block_format() {
+                    format.decompress(data, width as usize, height as usize, &mut image);
+                }
+            }
+        }
+
+        image
+    }
+
+    fn encode(&self, image: &RgbaImage) -> Vec<u8> {
+        let mut data = vec![0; self.surface_size(image.width(), image.height())];
+
+        match *self {
+            Self::Uncompressed {
+                bit_count,
+                masks,
+                luminance,
+            } => {
+                let bytes_per_pixel = bit_count as usize / 8;
+                for (pixel, bytes) in image.pixels().zip(data.chunks_exact_mut(bytes_per_pixel)) {
+                    let [r, g, b, a] = pixel.0;
+                    let value = if luminance {
+                        // Rec. 709 weights, scaled to sum to 256
+                        let l = (u32::from(r) * 54 + u32::from(g) * 183 + u32::from(b) * 19) >> 8;
+                        pack_channel(l as u8, masks[0]) | pack_channel(a, masks[3])
+                    } else {
+                        pack_channel(r, masks[0])
+                            | pack_channel(g, masks[1])
+                            | pack_channel(b, masks[2])
+                            | pack_channel(a, masks[3])
+                    };
+                    bytes.copy_from_slice(&value.to_le_bytes()[..bytes_per_pixel]);
+                }
+            }
+            _ => {
+                if let Some(format) = self.block_format() {
+                    let params = texpresso::Params {
+                        weights: texpresso::COLOUR_WEIGHTS_PERCEPTUAL,
+                        weigh_colour_by_alpha: matches!(self, Self::Bc2 | Self::Bc3),
+                        ..Default::default()
+                    };
+                    format.compress(
+                        image.as_raw(),
+                        image.width() as usize,
+                        image.height() as usize,
+                        params,
+                        &mut data,
+                    );
+                }
+            }
+        }
+
+        data
+    }
+}
+
+#[inline]
+fn unpack_channel(value: u32, mask: u32, default: u8) -> u8 {
+    if mask == 0 {
+        return default;
+    }
+    let max = mask >> mask.trailing_zeros();
+    let value = (value & mask) >> mask.trailing_zeros();
+    (u64::from(value) * 255 / u64::from(max)) as u8
+}
+
+#[inline]
+fn pack_channel(value: u8, mask: u32) -> u32 {
+    if mask == 0 {
+        return 0;
+    }
+    let max = mask >> mask.trailing_zeros();
+    let value = (u64::from(value) * u64::from(max) + 127) / 255;
+    (value as u32) << mask.trailing_zeros()
+}
+
+#[inline]
+const fn mip_size(size: u32, mip: usize) -> u32 {
+    let size = size >> mip;
+    if size == 0 { 1 } else { size }
+}
+
+/// A DDS texture, holding the surfaces of every face and mip level as they are stored in the
+/// file.
+#[derive(Clone, Debug)]
+pub struct DdsTexture {
+    pub width: u32,
+    pub height: u32,
+    pub format: DdsFormat,
+    pub mip_count: usize,
+    pub cubemap: bool,
+    /// Each face in turn, with its mips from largest to smallest. Cubemap faces are ordered
+    /// +X, -X, +Y, -Y, +Z, -Z.
+    pub data: Vec<u8>,
+}
+
+impl DdsTexture {
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, TextureError> {
+        let header = DdsHeader::read(reader)?;
+        let format = DdsFormat::from_pixel_format(&header.pixel_format)?;
+        if header.width == 0 || header.height == 0 {
+            return Err(TextureError::InvalidSize {
+                width: header.width,
+                height: header.height,
+            });
+        }
+
+        // Plenty of tools leave the mip count flag unset when there is only one level, and a
+        // corrupt count can't go past the 1x1 level
+        let mip_count = if header.flags.contains(DdsFlags::MIP_MAP_COUNT) {
+            let max_mip_count = 32 - header.width.max(header.height).leading_zeros();
+            header.mip_map_count.clamp(1, max_mip_count) as usize
+        } else {
+            1
+        };
+
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        let texture = Self {
+            width: header.width,
+            height: header.height,
+            format,
+            mip_count,
+            cubemap: header.caps2.contains(DdsCaps2::CUBEMAP),
+            data,
+        };
+
+        let expected = texture.face_size() * texture.face_count();
+        if texture.data.len() < expected {
+            return Err(TextureError::InvalidLength {
+                expected,
+                found: texture.data.len(),
+            });
+        }
+
+        Ok(texture)
+    }
+
+    pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<(), TextureError> {
+        let mut flags = DdsFlags::CAPS
+            | DdsFlags::HEIGHT
+            | DdsFlags::WIDTH
+            | DdsFlags::PIXEL_FORMAT
+            | DdsFlags::MIP_MAP_COUNT;
+        let pitch_or_linear_size = if let DdsFormat::Uncompressed { bit_count, .. } = self.format {
+            flags |= DdsFlags::PITCH;
+            (self.width * bit_count).div_ceil(8)
+        } else {
+            flags |= DdsFlags::LINEAR_SIZE;
+            self.format.surface_size(self.width, self.height) as u32
+        };
+
+        let mut caps = DdsCaps::TEXTURE;
+        let mut caps2 = DdsCaps2::empty();
+        if self.mip_count > 1 {
+            caps |= DdsCaps::COMPLEX | DdsCaps::MIP_MAP;
+        }
+        if self.cubemap {
+            caps |= DdsCaps::COMPLEX;
+            caps2 |= DdsCaps2::CUBEMAP | DdsCaps2::CUBEMAP_ALL_FACES;
+        }
+
+        DdsHeader {
+            flags,
+            height: self.height,
+            width: self.width,
+            pitch_or_linear_size,
+            mip_map_count: self.mip_count as u32,
+            pixel_format: self.format.pixel_format(),
+            caps,
+            caps2,
+            ..Default::default()
+        }
+        .write(writer)?;
+        writer.write_all(&self.data)?;
+
+        Ok(())
+    }
+
+    /// Encodes images into a texture, one for each face, generating mips down to 1x1 if asked
+    /// to. Six images make a cubemap.
+    pub fn encode(
+        faces: &[RgbaImage],
+        format: DdsFormat,
+        mipmaps: bool,
+    ) -> Result<Self, TextureError> {
+        let Some(first) = faces.first() else {
+            return Err(TextureError::InvalidSize {
+                width: 0,
+                height: 0,
+            });
+        };
+        let (width, height) = first.dimensions();
+        if width == 0 || height == 0 {
+            return Err(TextureError::InvalidSize { width, height });
+        }
+
+        let cubemap = faces.len() > 1;
+        if cubemap
+            && (faces.len() != 6
+                || faces
+                    .iter()
+                    .any(|face| face.dimensions() != (width, height)))
+        {
+            return Err(TextureError::InvalidCubemap);
+        }
+
+        let mip_count = if mipmaps {
+            (32 - width.max(height).leading_zeros()) as usize
+        } else {
+            1
+        };
+
+        let mut data = vec![];
+        for face in faces {
+            data.extend(format.encode(face));
+            for mip in 1..mip_count {
+                let mip = image::imageops::resize(
+                    face,
+                    mip_size(width, mip),
+                    mip_size(height, mip),
+                    FilterType::Triangle,
+                );
+                data.extend(format.encode(&mip));
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            format,
+            mip_count,
+            cubemap,
+            data,
+        })
+    }
+
+    #[inline]
+    pub const fn face_count(&self) -> usize {
+        if self.cubemap { 6 } else { 1 }
+    }
+
+    /// Returns the size of a mip level.
+    #[inline]
+    pub const fn mip_dimensions(&self, mip: usize) -> (u32, u32) {
+        (mip_size(self.width, mip), mip_size(self.height, mip))
+    }
+
+    fn face_size(&self) -> usize {
+        (0..self.mip_count)
+            .map(|mip| {
+                let (width, height) = self.mip_dimensions(mip);
+                self.format.surface_size(width, height)
+            })
+            .sum()
+    }
+
+    /// Returns the stored data of a single face and mip level.
+    pub fn surface(&self, face: usize, mip: usize) -> Result<&[u8], TextureError> {
+        if face >= self.face_count() || mip >= self.mip_count {
+            return Err(TextureError::InvalidSurface { face, mip });
+        }
+
+        let offset = face * self.face_size()
+            + (0..mip)
+                .map(|mip| {
+                    let (width, height) = self.mip_dimensions(mip);
+                    self.format.surface_size(width, height)
+                })
+                .sum::<usize>();
+        let (width, height) = self.mip_dimensions(mip);
+        let size = self.format.surface_size(width, height);
+
+        self.data
+            .get(offset..offset + size)
+            .ok_or(TextureError::InvalidLength {
+                expected: offset + size,
+                found: self.data.len(),
+            })
+    }
+
+    /// Decodes a single face and mip level to RGBA.
+    pub fn decode(&self, face: usize, mip: usize) -> Result<RgbaImage, TextureError> {
+        let (width, height) = self.mip_dimensions(mip);
+        Ok(self.format.decode(self.surface(face, mip)?, width, height))
+    }
+
+    /// Decodes every face and mip level, for when the compressed format cannot be used directly.
+    /// Faces are returned in turn, each with its mips from largest to smallest.
+    pub fn decode_all(&self) -> Result<Vec<RgbaImage>, TextureError> {
+        let mut images = Vec::with_capacity(self.face_count() * self.mip_count);
+        for face in 0..self.face_count() {
+            for mip in 0..self.mip_count {
+                images.push(self.decode(face, mip)?);
+            }
+        }
+        Ok(images)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::Rgba;
+
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([(x * 40) as u8, (y * 60) as u8, (x * y) as u8, 255 - x as u8])
+        })
+    }
+
+    fn round_trip(texture: &DdsTexture) -> Result<DdsTexture, TextureError> {
+        let mut writer = Cursor::new(vec![]);
+        texture.write(&mut writer)?;
+        writer.set_position(0);
+        DdsTexture::read(&mut writer)
+    }
+
+    /// Checks every channel is within `tolerance` of the original, as block compression is lossy.
+    fn similar(a: &RgbaImage, b: &RgbaImage, tolerance: u8) -> bool {
+        a.dimensions() == b.dimensions()
+            && a.pixels()
+                .zip(b.pixels())
+                .all(|(a, b)| a.0.iter().zip(b.0).all(|(a, b)| a.abs_diff(b) <= tolerance))
+    }
+
+    #[test]
+    fn uncompressed_round_trip() -> Result<(), TextureError> {
+        let image = gradient(5, 3);
+        let texture = DdsTexture::encode(std::slice::from_ref(&image), DdsFormat::BGRA8, false)?;
+        let pixel = image.get_pixel(0, 0).0;
+        assert_eq!(texture.data[..4], [pixel[2], pixel[1], pixel[0], pixel[3]]);
+
+        let result = round_trip(&texture)?;
+        assert_eq!(result.format, DdsFormat::BGRA8);
+        assert_eq!(result.decode(0, 0)?, image);
+        Ok(())
+    }
+
+    #[test]
+    fn luminance_round_trip() -> Result<(), TextureError> {
+        let image = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 7]));
+        let texture = DdsTexture::encode(&[image], DdsFormat::L8, false)?;
+        assert_eq!(texture.data, [100; 16]);
+
+        let result = round_trip(&texture)?;
+        assert_eq!(result.format, DdsFormat::L8);
+        // L8 has no alpha, so it decodes as opaque
+        assert!(
+            result
+                .decode(0, 0)?
+                .pixels()
+                .all(|pixel| pixel.0 == [100, 100, 100, 255])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compressed_round_trip() -> Result<(), TextureError> {
+        let image = RgbaImage::from_pixel(8, 8, Rgba([255, 0, 0, 128]));
+        for (format, tolerance) in [(DdsFormat::Bc2, 17), (DdsFormat::Bc3, 4)] {
+            let texture = DdsTexture::encode(std::slice::from_ref(&image), format, false)?;
+            assert_eq!(texture.data.len(), 4 * 16);
+
+            let result = round_trip(&texture)?;
+            assert_eq!(result.format, format);
+            assert!(similar(&result.decode(0, 0)?, &image, tolerance));
+        }
+
+        let opaque = RgbaImage::from_pixel(8, 8, Rgba([0, 255, 0, 255]));
+        let texture = DdsTexture::encode(std::slice::from_ref(&opaque), DdsFormat::Bc1, false)?;
+        assert_eq!(texture.data.len(), 4 * 8);
+        assert!(similar(&round_trip(&texture)?.decode(0, 0)?, &opaque, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn mip_chain() -> Result<(), TextureError> {
+        let texture = DdsTexture::encode(&[gradient(8, 2)], DdsFormat::BGRA8, true)?;
+        assert_eq!(texture.mip_count, 4);
+        assert_eq!(texture.data.len(), (16 + 4 + 2 + 1) * 4);
+
+        let result = round_trip(&texture)?;
+        assert_eq!(result.mip_count, 4);
+        assert_eq!(result.mip_dimensions(3), (1, 1));
+        let sizes = result
+            .decode_all()?
+            .iter()
+            .map(RgbaImage::dimensions)
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [(8, 2), (4, 1), (2, 1), (1, 1)]);
+        assert!(matches!(
+            result.surface(0, 4),
+            Err(TextureError::InvalidSurface { face: 0, mip: 4 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn cubemap_round_trip() -> Result<(), TextureError> {
+        let faces = (0..6)
+            .map(|face| RgbaImage::from_pixel(4, 4, Rgba([face * 40, 0, 0, 255])))
+            .collect::<Vec<_>>();
+        let texture = DdsTexture::encode(&faces, DdsFormat::BGRA8, false)?;
+
+        let result = round_trip(&texture)?;
+        assert!(result.cubemap);
+        assert_eq!(result.decode_all()?, faces);
+
+        assert!(matches!(
+            DdsTexture::encode(&faces[..5], DdsFormat::BGRA8, false),
+            Err(TextureError::InvalidCubemap)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_truncated_data() -> Result<(), TextureError> {
+        let texture = DdsTexture::encode(&[gradient(4, 4)], DdsFormat::BGRA8, false)?;
+        let mut writer = Cursor::new(vec![]);
+        texture.write(&mut writer)?;
+
+        let mut data = writer.into_inner();
+        data.pop();
+        assert!(matches!(
+            DdsTexture::read(&mut Cursor::new(data)),
+            Err(TextureError::InvalidLength {
+                expected: 64,
+                found: 63
+            })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Field, Fields, GenericArgument, Lifetime, LifetimeParam, LitInt, LitStr,
+    PathArguments, Type, parse_macro_input, parse_quote, spanned::Spanned,
+};
+
+/// Implements `FromPropertyContainer`, reading each field from the key matching its name.
+///
+/// Fields accept `#[property(...)]` attributes:
+/// - `name = "_class"` or `id = 0x1A2B3C4D` to read from another key
+/// - `default` to fall back to `Default::default()` when the key is missing
+/// - `container` to read a nested container implementing `FromPropertyContainer`
+/// - `skip` to ignore the key entirely
+/// - `source` to borrow the `&PropertyContainer` the struct was read from, keeping every key
+///   other fields don't type within reach without copying them
+///
+/// Fields of type `Option<T>` are `None` when the key is missing or holds another type, as with
+/// `PropertyContainer::get_value`.
+#[proc_macro_derive(FromPropertyContainer, attributes(property))]
+pub fn derive_from_property_container(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_property_container(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `IntoPropertyContainer`, writing each field to the key matching its name.
+///
+/// Accepts the same `#[property(...)]` attributes as `FromPropertyContainer`, with `None` fields
+/// left out of the container. Structs with a `source` field start from a copy of it instead, so
+/// keys keep their original order and those without a field are kept. A `None` field removes
+/// its key from the copy, including a key of another type that was read as `None`.
+#[proc_macro_derive(IntoPropertyContainer, attributes(property))]
+pub fn derive_into_property_container(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_property_container(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct PropertyField<'a> {
+    field: &'a Field,
+    key: TokenStream2,
+    label: String,
+    optional: Option<&'a Type>,
+    default: bool,
+    container: bool,
+    skip: bool,
+    source: bool,
+}
+
+impl<'a> PropertyField<'a> {
+    fn parse(field: &'a Field) -> syn::Result<Self> {
+        let Some(ident) = &field.ident else {
+            return Err(syn::Error::new(field.span(), "expected a named field"));
+        };
+
+        let name = ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name);
+        let mut result = Self {
+            field,
+            key: quote!(#name),
+            label: name.into(),
+            optional: option_type(&field.ty),
+            default: false,
+            container: false,
+            skip: false,
+            source: false,
+        };
+
+        for attribute in &field.attrs {
+            if !attribute.path().is_ident("property") {
+                continue;
+            }
+
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    result.label = name.value();
+                    result.key = quote!(#name);
+                } else if meta.path.is_ident("id") {
+                    let id: LitInt = meta.value()?.parse()?;
+                    result.label = format!("0x{:08X}", id.base10_parse::<u32>()?);
+                    result.key = quote!({
+                        let id: u32 = #id;
+                        id
+                    });
+                } else if meta.path.is_ident("default") {
+                    result.default = true;
+                } else if meta.path.is_ident("container") {
+                    result.container = true;
+                } else if meta.path.is_ident("skip") {
+                    result.skip = true;
+                } else if meta.path.is_ident("source") {
+                    result.source = true;
+                } else {
+                    return Err(meta.error(
+                        "expected `name`, `id`, `default`, `container`, `skip` or `source`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(result)
+    }
+}
+
+fn fields(input: &DeriveInput) -> syn::Result<Vec<PropertyField<'_>>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().map(PropertyField::parse).collect(),
+            Fields::Unit => Ok(vec![]),
+            Fields::Unnamed(_) => Err(syn::Error::new(
+                input.span(),
+                "only structs with named fields are supported",
+            )),
+        },
+        Data::Enum(_) | Data::Union(_) => {
+            Err(syn::Error::new(input.span(), "only structs are supported"))
+        }
+    }
+}
+
+/// Returns `T` if the type is `Option<T>`.
+fn option_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+
+    let segment = path.path.segments.last()?;
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+
+    if segment.ident != "Option" || arguments.args.len() != 1 {
+        return None;
+    }
+
+    let Some(GenericArgument::Type(ty)) = arguments.args.first() else {
+        return None;
+    };
+    Some(ty)
+}
+
+/// Reads an `Option<T>` field, which is `None` unless the key holds a convertible entry.
+fn read_optional(
+    field: &PropertyField<'_>,
+    ty: &Type,
+    module: &TokenStream2,
+    lifetime: &Lifetime,
+) -> TokenStream2 {
+    let key = &field.key;
+    let (variant, convert) = if field.container {
+        (
+            quote!(Container),
+            quote!(
+                <#ty as #module::FromPropertyContainer<#lifetime>>::from_property_container(value)
+                    .ok()
+            ),
+        )
+    } else {
+        (
+            quote!(Value),
+            quote!(<#ty as #module::FromPropertyValue<#lifetime>>::from_property_value(value)),
+        )
+    };
+
+    quote! {
+        match container.get(#key) {
+            ::core::option::Option::Some(#module::PropertyEntry::#variant(value)) => #convert,
+            _ => ::core::option::Option::None,
+        }
+    }
+}
+
+fn from_property_container(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let module = quote!(::jc2_file_formats::property_container);
+    let ident = &input.ident;
+
+    // Borrow from the container using the struct's lifetime, or a new one if it has none
+    let mut generics = input.generics.clone();
+    let lifetime = if let Some(param) = input.generics.lifetimes().next() {
+        param.lifetime.clone()
+    } else {
+        let lifetime: Lifetime = parse_quote!('__property);
+        generics
+            .params
+            .insert(0, LifetimeParam::new(lifetime.clone()).into());
+        lifetime
+    };
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = fields(input)?;
+    let fields = fields.iter().map(|field| {
+        let name = &field.field.ident;
+        if field.skip {
+            return quote!(#name: ::core::default::Default::default());
+        }
+
+        if field.source {
+            return quote!(#name: container);
+        }
+
+        let PropertyField { key, label, .. } = field;
+        let variant = if field.container {
+            quote!(Container)
+        } else {
+            quote!(Value)
+        };
+
+        if let Some(ty) = field.optional {
+            let read = read_optional(field, ty, &module, &lifetime);
+            return quote!(#name: #read);
+        }
+
+        let ty = &field.field.ty;
+        let convert = if field.container {
+            quote!(
+                <#ty as #module::FromPropertyContainer<#lifetime>>::from_property_container(
+                    value,
+                )?
+            )
+        } else {
+            quote!(
+                <#ty as #module::FromPropertyValue<#lifetime>>::from_property_value(value)
+                    .ok_or(#module::PropertyContainerError::InvalidType(#label))?
+            )
+        };
+        let missing = if field.default {
+            quote!(::core::default::Default::default())
+        } else {
+            quote!(return ::core::result::Result::Err(
+                #module::PropertyContainerError::MissingKey(#label)
+            ))
+        };
+
+        quote! {
+            #name: match container.get(#key) {
+                ::core::option::Option::Some(#module::PropertyEntry::#variant(value)) => #convert,
+                ::core::option::Option::Some(_) => {
+                    return ::core::result::Result::Err(
+                        #module::PropertyContainerError::InvalidType(#label),
+                    );
+                }
+                ::core::option::Option::None => #missing,
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #module::FromPropertyContainer<#lifetime>
+            for #ident #ty_generics #where_clause
+        {
+            fn from_property_container(
+                container: &#lifetime #module::PropertyContainer,
+            ) -> #module::PropertyContainerResult<Self> {
+                ::core::result::Result::Ok(Self {
+                    #(#fields,)*
+                })
+            }
+        }
+    })
+}
+
+fn into_property_container(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let module = quote!(::jc2_file_formats::property_container);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = fields(input)?;
+    let source = fields.iter().find(|field| field.source);
+    let container = if let Some(field) = source {
+        let name = &field.field.ident;
+        quote!(::core::clone::Clone::clone(self.#name))
+    } else {
+        quote!(#module::PropertyContainer::new())
+    };
+
+    let fields = fields
+        .iter()
+        .filter(|field| !field.skip && !field.source)
+        .map(|field| {
+            let name = &field.field.ident;
+            let key = &field.key;
+            let insert = if field.container {
+                quote!(container.insert_container(
+                    #key,
+                    #module::IntoPropertyContainer::into_property_container(value),
+                ))
+            } else {
+                quote!(container.insert_value(#key, value))
+            };
+
+            if field.optional.is_some() {
+                // Only a copied source can already hold the key
+                let remove = source.map(|_| {
+                    quote!(else {
+                        container.remove(#key);
+                    })
+                });
+                quote! {
+                    if let ::core::option::Option::Some(value) = self.#name {
+                        #insert;
+                    } #remove
+                }
+            } else {
+                quote! {
+                    let value = self.#name;
+                    #insert;
+                }
+            }
+        });
+
+    Ok(quote! {
+        impl #impl_generics #module::IntoPropertyContainer for #ident #ty_generics #where_clause {
+            fn into_property_container(self) -> #module::PropertyContainer {
+                let mut container = #container;
+                #(#fields)*
+                container
+            }
+        }
+    })
+}
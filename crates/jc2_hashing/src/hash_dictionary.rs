@@ -0,0 +1,343 @@
+use std::{
+    io::{BufRead, Read, Seek, Write},
+    path::PathBuf,
+};
+
+use binrw::{BinRead, BinWrite, binrw, helpers};
+
+use crate::{HashEntry, HashList, HashString};
+
+/// How lines of a plain text dictionary are hashed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashEntryKind {
+    /// Lines are hashed as-is, such as property names.
+    String,
+    /// Lines are hashed by their lowercased file name, such as the paths in a `.filelist`.
+    #[default]
+    Path,
+}
+
+/// Two different entries that share a hash. Paths are hashed by file name alone, so the same file
+/// name in different directories is not a collision.
+#[derive(Clone, Debug)]
+pub struct HashCollision {
+    pub hash: HashString,
+    /// The entry that was kept.
+    pub existing: HashEntry,
+    /// The entry that was discarded.
+    pub rejected: HashEntry,
+}
+
+impl std::fmt::Display for HashCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "0x{:08X}: kept {}, rejected {}",
+            self.hash.hash(),
+            self.existing,
+            self.rejected
+        )
+    }
+}
+
+impl HashList {
+    /// Inserts an entry unless another already has its hash, in which case the existing entry is
+    /// kept. The collision is returned unless both entries are paths to the same file name.
+    pub fn insert_unique(&mut self, entry: HashEntry) -> Result<(), HashCollision> {
+        let Some(hash) = entry.hash() else {
+            return Ok(());
+        };
+
+        match self.find(hash) {
+            Some(existing) if !same_name(existing, &entry) => Err(HashCollision {
+                hash,
+                existing: existing.clone(),
+                rejected: entry,
+            }),
+            Some(_) => Ok(()),
+            None => {
+                self.insert(entry);
+                Ok(())
+            }
+        }
+    }
+
+    /// Merges another dictionary into this one, keeping existing entries on collision.
+    pub fn merge(&mut self, other: HashList) -> Vec<HashCollision> {
+        let mut entries = other.into_values().collect::<Vec<_>>();
+        entries.sort_by_cached_key(HashEntry::to_string);
+        entries
+            .into_iter()
+            .filter_map(|entry| self.insert_unique(entry).err())
+            .collect()
+    }
+
+    /// Reads a plain text dictionary with one entry per line, such as a `.filelist`.
+    ///
+    /// Blank lines are skipped, and backslashes in paths are treated as separators.
+    pub fn read_text(
+        reader: impl BufRead,
+        kind: HashEntryKind,
+    ) -> std::io::Result<(Self, Vec<HashCollision>)> {
+        let mut result = Self::new();
+        let mut collisions = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let entry = match kind {
+                HashEntryKind::String => HashEntry::String(line.into()),
+                HashEntryKind::Path => HashEntry::Path(line.replace('\\', "/").into()),
+            };
+            if let Err(collision) = result.insert_unique(entry) {
+                collisions.push(collision);
+            }
+        }
+        Ok((result, collisions))
+    }
+
+    /// Writes a plain text dictionary with one entry per line, sorted alphabetically.
+    pub fn write_text(&self, mut writer: impl Write) -> std::io::Result<()> {
+        let mut lines = self.values().map(HashEntry::to_string).collect::<Vec<_>>();
+        lines.sort_unstable();
+        for line in lines {
+            writeln!(writer, "{line}")?;
+        }
+        Ok(())
+    }
+
+    /// Reads a dictionary written by [`HashList::write_binary`].
+    pub fn read_binary(mut reader: impl Read + Seek) -> binrw::BinResult<Self> {
+        let file = HashDictionaryFile::read(&mut reader)?;
+        Ok(file
+            .entries
+            .into_iter()
+            .map(|entry| (entry.hash, entry.value.into()))
+            .collect())
+    }
+
+    /// Writes a compact binary dictionary, with entries sorted by hash.
+    pub fn write_binary(&self, mut writer: impl Write + Seek) -> binrw::BinResult<()> {
+        let mut entries = self
+            .iter()
+            .map(|(hash, entry)| HashDictionaryEntry {
+                hash: *hash,
+                value: entry.clone().into(),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|entry| entry.hash);
+        HashDictionaryFile { entries }.write(&mut writer)
+    }
+
+    /// Returns a dictionary of every file name known to ship with the game.
+    #[cfg(feature = "dictionary")]
+    pub fn jc2() -> Self {
+        let mut result = Self::new();
+        for (_, filelist) in JC2_FILELISTS {
+            result.merge(Self::read_filelist(filelist));
+        }
+        result
+    }
+
+    /// Returns the bundled `.filelist` of a game archive, matched by the directory and name of
+    /// its `.tab` file such as `archives_win32/pc0.tab` or `DLC/pc_00.tab`.
+    #[cfg(feature = "dictionary")]
+    pub fn jc2_archive(path: &std::path::Path) -> Option<Self> {
+        let directory = path.parent()?.file_name()?.to_str()?;
+        let name = format!("{directory}/{}", path.file_stem()?.to_str()?);
+        JC2_FILELISTS
+            .iter()
+            .find(|(archive, _)| archive.eq_ignore_ascii_case(&name))
+            .map(|(_, filelist)| Self::read_filelist(filelist))
+    }
+
+    #[cfg(feature = "dictionary")]
+    fn read_filelist(filelist: &str) -> Self {
+        let (list, _) = Self::read_text(filelist.as_bytes(), HashEntryKind::Path)
+            .expect("reading from memory cannot fail");
+        list
+    }
+}
+
+/// Returns whether two entries name the same thing, comparing paths by file name as they are
+/// hashed.
+fn same_name(a: &HashEntry, b: &HashEntry) -> bool {
+    match (a, b) {
+        (HashEntry::Path(a), HashEntry::Path(b)) => {
+            let name = |path: &PathBuf| path.file_name().map(|name| name.to_ascii_lowercase());
+            name(a) == name(b)
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(feature = "dictionary")]
+macro_rules! filelist {
+    ($name:literal) => {
+        (
+            $name,
+            include_str!(concat!("../../../assets/", $name, ".filelist")),
+        )
+    };
+}
+
+/// The bundled `.filelist` of every game archive, by directory and name.
+#[cfg(feature = "dictionary")]
+const JC2_FILELISTS: &[(&str, &str)] = &[
+    filelist!("archives_win32/pc0"),
+    filelist!("archives_win32/pc1"),
+    filelist!("archives_win32/pc2"),
+    filelist!("archives_win32/pc3"),
+    filelist!("archives_win32/pc4"),
+    filelist!("dlc/pc_00"),
+    filelist!("dlc/pc_10"),
+    filelist!("dlc/pc_20"),
+    filelist!("dlc/pc_30"),
+    filelist!("dlc/pc_40"),
+    filelist!("dlc/pc_50"),
+    filelist!("dlc/pc_60"),
+    filelist!("dlc/pc_70"),
+    filelist!("dlc/pc_80"),
+    filelist!("dlc/pc_90"),
+    filelist!("dlc/pc_100"),
+    filelist!("dlc/pc_110"),
+    filelist!("dlc/pc_120"),
+    filelist!("dlc/pc_130"),
+    filelist!("dlc/pc_140"),
+    filelist!("dlc/pc_150"),
+    filelist!("dlc/pc_160"),
+    filelist!("dlc/pc_170"),
+    filelist!("dlc/pc_180"),
+    filelist!("dlc/pc_190"),
+];
+
+#[binrw]
+#[brw(little, magic = b"HDIC")]
+struct HashDictionaryFile {
+    #[bw(calc = entries.len() as u32)]
+    count: u32,
+    #[br(parse_with = helpers::count(count as usize))]
+    entries: Vec<HashDictionaryEntry>,
+}
+
+#[binrw]
+struct HashDictionaryEntry {
+    hash: HashString,
+    value: HashDictionaryValue,
+}
+
+#[binrw]
+enum HashDictionaryValue {
+    #[brw(magic = 0u8)]
+    String(HashDictionaryString),
+    #[brw(magic = 1u8)]
+    Path(HashDictionaryString),
+}
+
+#[binrw]
+struct HashDictionaryString {
+    #[bw(calc = value.len() as u16)]
+    length: u16,
+    #[br(
+        parse_with = helpers::count(usize::from(length)),
+        map = |bytes: Vec<u8>| String::from_utf8_lossy(&bytes).into_owned(),
+    )]
+    #[bw(map = |value: &String| value.as_bytes().to_vec())]
+    value: String,
+}
+
+impl From<HashEntry> for HashDictionaryValue {
+    fn from(value: HashEntry) -> Self {
+        match value {
+            HashEntry::String(value) => Self::String(HashDictionaryString { value }),
+            HashEntry::Path(value) => Self::Path(HashDictionaryString {
+                value: value.to_string_lossy().into_owned(),
+            }),
+        }
+    }
+}
+
+impl From<HashDictionaryValue> for HashEntry {
+    fn from(value: HashDictionaryValue) -> Self {
+        match value {
+            HashDictionaryValue::String(value) => HashEntry::String(value.value),
+            HashDictionaryValue::Path(value) => HashEntry::Path(PathBuf::from(value.value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn sorted(list: &HashList) -> Vec<(HashString, HashEntry)> {
+        let mut entries = list
+            .iter()
+            .map(|(hash, entry)| (*hash, entry.clone()))
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(hash, _)| *hash);
+        entries
+    }
+
+    #[test]
+    fn text_round_trip() -> std::io::Result<()> {
+        let text = "models\\jc_design_tools\\a.lod\r\n\nsettings/b.bin\nother/B.bin\n";
+        let (list, collisions) = HashList::read_text(text.as_bytes(), HashEntryKind::Path)?;
+        // The same file name in another directory shares the hash without colliding
+        assert!(collisions.is_empty());
+        assert_eq!(list.len(), 2);
+        assert!(list.contains("a.lod".into()));
+
+        let mut result = vec![];
+        list.write_text(&mut result)?;
+        assert_eq!(result, b"models/jc_design_tools/a.lod\nsettings/b.bin\n");
+        Ok(())
+    }
+
+    #[test]
+    fn binary_round_trip() -> binrw::BinResult<()> {
+        let mut list = HashList::new();
+        list.insert_string("_class");
+        list.insert_string("world");
+        list.insert_path("models/jc_design_tools/a.lod");
+
+        let mut writer = Cursor::new(vec![]);
+        list.write_binary(&mut writer)?;
+        writer.set_position(0);
+        let result = HashList::read_binary(&mut writer)?;
+        assert_eq!(sorted(&result), sorted(&list));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_missing_magic() {
+        assert!(HashList::read_binary(Cursor::new(b"HDIX\0\0\0\0")).is_err());
+    }
+
+    #[cfg(feature = "dictionary")]
+    #[test]
+    fn bundled_dictionary() {
+        let list = HashList::jc2();
+        let entry = list.find("accomplishments.bin".into());
+        assert_eq!(
+            entry.and_then(HashEntry::as_path),
+            Some(std::path::Path::new("accomplishments/accomplishments.bin"))
+        );
+    }
+
+    #[cfg(feature = "dictionary")]
+    #[test]
+    fn bundled_archive_dictionary() {
+        use std::path::Path;
+
+        let list = HashList::jc2_archive(Path::new("Just Cause 2/archives_win32/pc0.tab"));
+        assert!(list.is_some_and(|list| list.contains("accomplishments.bin".into())));
+        assert!(HashList::jc2_archive(Path::new("DLC/PC_00.tab")).is_some());
+        assert!(HashList::jc2_archive(Path::new("pc0.tab")).is_none());
+    }
+}
@@ -13,7 +13,7 @@ type IntoKeys = std::collections::hash_map::IntoKeys<HashString, HashEntry>;
 type IntoValues = std::collections::hash_map::IntoValues<HashString, HashEntry>;
 type IntoIter = std::collections::hash_map::IntoIter<HashString, HashEntry>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HashEntry {
     String(String),
     Path(PathBuf),
@@ -51,6 +51,24 @@ impl HashEntry {
             HashEntry::String(_) => None,
         }
     }
+
+    /// Returns the hash this entry is stored under, if it has one.
+    #[inline]
+    pub fn hash(&self) -> Option<HashString> {
+        match self {
+            HashEntry::String(string) => Some(HashString::from_str(string)),
+            HashEntry::Path(path) => HashString::from_path(path),
+        }
+    }
+}
+
+impl std::fmt::Display for HashEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashEntry::String(string) => f.write_str(string),
+            HashEntry::Path(path) => write!(f, "{}", path.display()),
+        }
+    }
 }
 
 impl From<HashEntry> for Option<String> {
@@ -95,6 +113,16 @@ impl HashList {
         Self(HashMap::with_capacity(capacity))
     }
 
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     #[inline]
     pub fn extend<T: IntoIterator<Item = (HashString, HashEntry)>>(&mut self, iter: T) {
         self.0.extend(iter);
@@ -3,8 +3,11 @@ extern crate static_assertions;
 
 pub use paste::paste;
 
+mod hash_dictionary;
+pub use hash_dictionary::{HashCollision, HashEntryKind};
+
 mod hash_list;
-pub use hash_list::HashList;
+pub use hash_list::{HashEntry, HashList};
 
 mod hash_string_macros;
 
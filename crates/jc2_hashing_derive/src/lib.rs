@@ -0,0 +1,85 @@
+use jc2_hashing::HashString;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, LitInt, LitStr, parse_macro_input, spanned::Spanned};
+
+/// Tags enum variants with the hash of a name, for use with `binrw`.
+///
+/// Placed above `#[binrw]`, each variant's `#[hash_magic("Name")]` is replaced with
+/// `#[brw(magic(hash_u32))]` where the hash is `HashString::from_str("Name")`:
+///
+/// ```
+/// use binrw::{BinRead, binrw, io::Cursor};
+/// use jc2_hashing::HashString;
+/// use jc2_hashing_derive::hash_magic;
+///
+/// #[hash_magic]
+/// #[binrw]
+/// #[brw(little)]
+/// pub enum RenderBlock {
+///     #[hash_magic("General")]
+///     General(u32),
+/// }
+///
+/// let mut data = HashString::from_str("General").hash().to_le_bytes().to_vec();
+/// data.extend(1u32.to_le_bytes());
+/// let block = RenderBlock::read(&mut Cursor::new(data))?;
+/// assert!(matches!(block, RenderBlock::General(1)));
+/// # Ok::<(), binrw::Error>(())
+/// ```
+#[proc_macro_attribute]
+pub fn hash_magic(attribute: TokenStream, item: TokenStream) -> TokenStream {
+    let attribute = TokenStream2::from(attribute);
+    if !attribute.is_empty() {
+        return syn::Error::new(
+            attribute.span(),
+            "expected `#[hash_magic]` on the enum, names belong on its variants",
+        )
+        .into_compile_error()
+        .into();
+    }
+
+    let mut input = parse_macro_input!(item as DeriveInput);
+    match hash_magic_enum(&mut input) {
+        Ok(()) => quote!(#input).into(),
+        Err(error) => error.into_compile_error().into(),
+    }
+}
+
+fn hash_magic_enum(input: &mut DeriveInput) -> syn::Result<()> {
+    let Data::Enum(data) = &mut input.data else {
+        return Err(syn::Error::new(input.span(), "only enums are supported"));
+    };
+
+    for variant in &mut data.variants {
+        let mut names = variant
+            .attrs
+            .iter()
+            .filter(|attribute| attribute.path().is_ident("hash_magic"))
+            .map(Attribute::parse_args::<LitStr>)
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        let name = match (names.pop(), names.is_empty()) {
+            (Some(name), true) => name,
+            (Some(name), false) => {
+                return Err(syn::Error::new(name.span(), "expected a single name"));
+            }
+            (None, _) => {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    "expected `#[hash_magic(\"Name\")]` on every variant",
+                ));
+            }
+        };
+
+        let hash = HashString::from_str(&name.value()).hash();
+        let hash = LitInt::new(&format!("{hash}u32"), name.span());
+        variant
+            .attrs
+            .retain(|attribute| !attribute.path().is_ident("hash_magic"));
+        variant.attrs.push(syn::parse_quote!(#[brw(magic(#hash))]));
+    }
+
+    Ok(())
+}
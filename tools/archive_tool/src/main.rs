@@ -0,0 +1,185 @@
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use clap::Parser;
+use jc2_file_formats::archive::{GameArchive, StreamArchive, read_dictionaries, relative_path};
+use jc2_hashing::{HashList, HashString};
+
+#[derive(Parser)]
+#[command(about = "Extracts game archives (.tab and .arc), naming entries by their .filelist")]
+struct Args {
+    #[arg(
+        required = true,
+        help = "Archives (.tab) or directories containing them"
+    )]
+    archives: Vec<PathBuf>,
+    #[arg(long, short, help = "Directory to extract to")]
+    output: PathBuf,
+    #[arg(
+        long,
+        help = "Text or binary dictionaries naming entries missing from the .filelist"
+    )]
+    dictionary: Vec<PathBuf>,
+    #[arg(long, help = "Include the bundled dictionary of known names")]
+    jc2: bool,
+    #[arg(
+        long,
+        help = "Also extract stream archives, to a directory next to each"
+    )]
+    recursive: bool,
+    #[arg(
+        long,
+        short,
+        help = "Number of threads to extract with, defaulting to one per core"
+    )]
+    jobs: Option<usize>,
+}
+
+#[derive(Default)]
+struct Counts {
+    extracted: AtomicUsize,
+    unnamed: AtomicUsize,
+    nested: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+/// Reads text and binary dictionaries, optionally including the bundled one.
+fn known_names(paths: &[PathBuf], jc2: bool) -> anyhow::Result<HashList> {
+    let mut result = if jc2 {
+        HashList::jc2()
+    } else {
+        HashList::new()
+    };
+    for collision in read_dictionaries(&mut result, paths)? {
+        eprintln!("{collision}");
+    }
+    Ok(result)
+}
+
+/// Returns where an archive entry is extracted to. Unnamed entries go in a directory of their
+/// own, with an extension guessed from their contents.
+fn entry_path(root: &Path, names: &HashList, hash: HashString, data: &[u8]) -> PathBuf {
+    if let Some(name) = names.find_path(hash) {
+        return root.join(relative_path(&name.to_string_lossy()));
+    }
+
+    let name = format!("{:08X}", hash.hash());
+    let path = root.join("unnamed").join(name);
+    if data.starts_with(b"DDS ") {
+        path.with_extension("dds")
+    } else if data.get(4..8) == Some(b"SARC") {
+        path.with_extension("sarc")
+    } else {
+        path
+    }
+}
+
+/// Writes an entry to `path`, and the contents of stream archives to a directory next to it
+/// when `recursive` is set.
+fn write_entry(path: &Path, data: &[u8], recursive: bool, counts: &Counts) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, data)?;
+
+    if recursive && StreamArchive::is_stream_archive(path, data) {
+        let archive = StreamArchive::read(&mut Cursor::new(data))?;
+        let directory = path.with_extension("");
+        for (name, data) in &archive.entries {
+            write_entry(
+                &directory.join(relative_path(name)),
+                data,
+                recursive,
+                counts,
+            )?;
+            counts.nested.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts every entry of an archive to `root`, sharing the work between `jobs` threads.
+fn extract(
+    archive: &GameArchive,
+    names: &HashList,
+    root: &Path,
+    recursive: bool,
+    jobs: usize,
+) -> anyhow::Result<Counts> {
+    let entries = archive.entries();
+    let next = AtomicUsize::new(0);
+    let counts = Counts::default();
+
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| -> anyhow::Result<()> {
+                    let mut reader = archive.open_data()?;
+                    let mut buffer = vec![];
+                    while let Some((hash, entry)) =
+                        entries.get(next.fetch_add(1, Ordering::Relaxed))
+                    {
+                        GameArchive::read_entry(&mut reader, entry, &mut buffer)?;
+
+                        let path = entry_path(root, names, *hash, &buffer);
+                        if !names.contains(*hash) {
+                            counts.unnamed.fetch_add(1, Ordering::Relaxed);
+                        }
+                        match write_entry(&path, &buffer, recursive, &counts) {
+                            Ok(()) => counts.extracted.fetch_add(1, Ordering::Relaxed),
+                            Err(error) => {
+                                eprintln!("{path:?}: {error:#}");
+                                counts.failed.fetch_add(1, Ordering::Relaxed)
+                            }
+                        };
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+
+        // Carry on panicking with the worker's own message
+        workers.into_iter().try_for_each(|worker| {
+            worker
+                .join()
+                .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+        })
+    })?;
+
+    Ok(counts)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let jobs = match args.jobs {
+        Some(jobs) => jobs.max(1),
+        None => std::thread::available_parallelism().map_or(1, usize::from),
+    };
+    let dictionary = known_names(&args.dictionary, args.jc2)?;
+
+    for (path, relative) in GameArchive::find(&args.archives)? {
+        let archive = GameArchive::open(&path)?;
+        // The .filelist, or the one bundled for the archive, is kept over the dictionary, as it
+        // is specific to the archive
+        let mut names = archive.file_list_or_bundled()?;
+        names.merge(dictionary.clone());
+
+        let root = args.output.join(relative.with_extension(""));
+        let counts = extract(&archive, &names, &root, args.recursive, jobs)?;
+        println!(
+            "{path:?}: extracted {} entries to {root:?}, {} unnamed, {} from stream archives, {} failed",
+            counts.extracted.into_inner(),
+            counts.unnamed.into_inner(),
+            counts.nested.into_inner(),
+            counts.failed.into_inner(),
+        );
+    }
+
+    Ok(())
+}
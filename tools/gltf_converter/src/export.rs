@@ -0,0 +1,606 @@
+use std::{borrow::Cow, io::Write, path::Path};
+
+use anyhow::Context;
+use gltf::binary::{Glb, Header};
+use gltf_json::{
+    Index,
+    buffer::{Stride, Target},
+    image::MimeType,
+    mesh::MorphTarget,
+    validation::Checked,
+};
+use jc2_file_formats::render_block_model::RenderBlockModel;
+use serde_json::{Value, json};
+
+use crate::{
+    helpers::{GltfHelpers, GltfMeshAccessor, GltfSkin},
+    material::MaterialExporter,
+    resolver::FileResolver,
+};
+
+pub type GltfRoot = gltf_json::Root;
+type Buffer = gltf_json::Buffer;
+type BufferIndex = Index<Buffer>;
+type BufferView = gltf_json::buffer::View;
+type BufferViewIndex = Index<BufferView>;
+
+fn create_buffer_view(
+    root: &mut GltfRoot,
+    name: &str,
+    buffer: BufferIndex,
+    length: usize,
+    offset: usize,
+    target: Option<Target>,
+    stride: Option<usize>,
+) -> BufferViewIndex {
+    root.push(BufferView {
+        name: Some(name.into()),
+        target: target.map(Checked::Valid),
+        buffer,
+        byte_length: length.into(),
+        byte_offset: Some(offset.into()),
+        byte_stride: stride.map(Stride),
+        extensions: Default::default(),
+        extras: Default::default(),
+    })
+}
+
+/// A buffer view along with the bytes it covers, so accessors into it can work out their bounds.
+struct ViewData<'a> {
+    index: BufferViewIndex,
+    bytes: &'a [u8],
+    stride: usize,
+}
+
+type MeshPrimitive = gltf_json::mesh::Primitive;
+type Accessor = gltf_json::Accessor;
+type AccessorType = gltf_json::accessor::Type;
+pub type AccessorComponentType = gltf_json::accessor::ComponentType;
+type AccessorIndex = gltf_json::Index<gltf_json::Accessor>;
+
+fn read_component(bytes: &[u8], component_type: AccessorComponentType) -> f64 {
+    match component_type {
+        AccessorComponentType::I8 => f64::from(bytemuck::pod_read_unaligned::<i8>(bytes)),
+        AccessorComponentType::U8 => f64::from(bytes[0]),
+        AccessorComponentType::I16 => f64::from(bytemuck::pod_read_unaligned::<i16>(bytes)),
+        AccessorComponentType::U16 => f64::from(bytemuck::pod_read_unaligned::<u16>(bytes)),
+        AccessorComponentType::U32 => f64::from(bytemuck::pod_read_unaligned::<u32>(bytes)),
+        AccessorComponentType::F32 => f64::from(bytemuck::pod_read_unaligned::<f32>(bytes)),
+    }
+}
+
+/// Returns the smallest and largest value of each component an accessor reads. glTF requires
+/// these for positions, and validators check them against the data for every other accessor.
+fn accessor_bounds(
+    view: &ViewData<'_>,
+    accessor_type: AccessorType,
+    component_type: AccessorComponentType,
+    offset: usize,
+    count: usize,
+) -> Option<(Value, Value)> {
+    if count == 0 {
+        return None;
+    }
+
+    let components = accessor_type.multiplicity();
+    let size = component_type.size();
+    let mut min = vec![f64::INFINITY; components];
+    let mut max = vec![f64::NEG_INFINITY; components];
+    for element in 0..count {
+        for component in 0..components {
+            let start = element * view.stride + offset + component * size;
+            let value = read_component(&view.bytes[start..start + size], component_type);
+            min[component] = min[component].min(value);
+            max[component] = max[component].max(value);
+        }
+    }
+
+    // Integer bounds are written as integers, as validators compare them by type too
+    let to_json = |values: Vec<f64>| -> Value {
+        values
+            .into_iter()
+            .map(|value| match component_type {
+                AccessorComponentType::F32 => json!(value as f32),
+                _ => json!(value as i64),
+            })
+            .collect()
+    };
+    Some((to_json(min), to_json(max)))
+}
+
+fn create_accessor(
+    root: &mut GltfRoot,
+    view: &ViewData<'_>,
+    accessor_type: AccessorType,
+    component_type: AccessorComponentType,
+    offset: usize,
+    count: usize,
+) -> AccessorIndex {
+    let (min, max) = accessor_bounds(view, accessor_type, component_type, offset, count).unzip();
+    root.push(Accessor {
+        buffer_view: Some(view.index),
+        byte_offset: Some((offset).into()),
+        count: count.into(),
+        component_type: Checked::Valid(gltf_json::accessor::GenericComponentType(component_type)),
+        extensions: Default::default(),
+        extras: Default::default(),
+        type_: Checked::Valid(accessor_type),
+        min,
+        max,
+        name: None,
+        normalized: false,
+        sparse: None,
+    })
+}
+
+fn create_attribute_accessor(
+    root: &mut GltfRoot,
+    primitive: &mut MeshPrimitive,
+    view: &ViewData<'_>,
+    accessor: GltfMeshAccessor,
+    count: usize,
+) -> AccessorIndex {
+    let (accessor_type, component_type, semantic, offset) = accessor;
+    let accessor = create_accessor(root, view, accessor_type, component_type, offset, count);
+    primitive
+        .attributes
+        .insert(Checked::Valid(semantic), accessor);
+    accessor
+}
+
+fn create_index_accessor(
+    root: &mut GltfRoot,
+    primitive: &mut MeshPrimitive,
+    view: &ViewData<'_>,
+    count: usize,
+) -> AccessorIndex {
+    let accessor = create_accessor(
+        root,
+        view,
+        AccessorType::Scalar,
+        AccessorComponentType::U16,
+        0,
+        count,
+    );
+    primitive.indices = Some(accessor);
+    accessor
+}
+
+fn create_accessors<T: GltfHelpers>(
+    root: &mut GltfRoot,
+    primitive: &mut MeshPrimitive,
+    view: &ViewData<'_>,
+    block: &T,
+) {
+    let vertex_count = block.vertex_count();
+    for accessor in block.accessors() {
+        create_attribute_accessor(root, primitive, view, accessor.clone(), vertex_count);
+    }
+    if let Some(target_accessors) = block.target_accessors() {
+        let mut target = MorphTarget {
+            positions: None,
+            normals: None,
+            tangents: None,
+        };
+        for (accessor_type, component_type, semantic, offset) in target_accessors {
+            let accessor = create_accessor(
+                root,
+                view,
+                accessor_type,
+                component_type,
+                offset,
+                vertex_count,
+            );
+            match semantic {
+                gltf::Semantic::Positions => target.positions = Some(accessor),
+                gltf::Semantic::Normals => target.normals = Some(accessor),
+                gltf::Semantic::Tangents => target.tangents = Some(accessor),
+                _ => panic!("invalid morph semantic: {semantic:?}"),
+            }
+        }
+        primitive.targets = Some(vec![target]);
+    }
+}
+
+fn create_skin_accessors(
+    root: &mut GltfRoot,
+    primitive: &mut MeshPrimitive,
+    view: &ViewData<'_>,
+    skin: &GltfSkin,
+) {
+    for accessor in skin.accessors() {
+        create_attribute_accessor(root, primitive, view, accessor, skin.influences.len());
+    }
+}
+
+/// Appends `bytes` to the buffer data, aligned so any accessor into them is aligned too.
+fn append(data: &mut Vec<u8>, bytes: &[u8]) -> usize {
+    data.resize((data.len() + 3) & !3, 0);
+    let offset = data.len();
+    data.extend_from_slice(bytes);
+    offset
+}
+
+fn create_views_and_accessors<T: GltfHelpers>(
+    root: &mut GltfRoot,
+    primitive: &mut MeshPrimitive,
+    data: &mut Vec<u8>,
+    block: &T,
+    skin: Option<&GltfSkin>,
+    buffer: BufferIndex,
+) {
+    let idx = root.meshes.len();
+
+    let bytes = block.vertices_as_bytes();
+    let offset = append(data, bytes);
+    let stride = block.vertex_stride();
+    let view = ViewData {
+        index: create_buffer_view(
+            root,
+            &format!("vertex_{idx}"),
+            buffer,
+            bytes.len(),
+            offset,
+            Some(Target::ArrayBuffer),
+            Some(stride),
+        ),
+        bytes,
+        stride,
+    };
+    create_accessors(root, primitive, &view, block);
+
+    if let Some(skin) = skin {
+        let bytes = skin.as_bytes();
+        let offset = append(data, bytes);
+        let view = ViewData {
+            index: create_buffer_view(
+                root,
+                &format!("skin_{idx}"),
+                buffer,
+                bytes.len(),
+                offset,
+                Some(Target::ArrayBuffer),
+                Some(GltfSkin::STRIDE),
+            ),
+            bytes,
+            stride: GltfSkin::STRIDE,
+        };
+        create_skin_accessors(root, primitive, &view, skin);
+    }
+
+    // Index buffer views must not have a stride, as they are always tightly packed
+    let bytes = block.indices_as_bytes();
+    let offset = append(data, bytes);
+    let view = ViewData {
+        index: create_buffer_view(
+            root,
+            &format!("index_{idx}"),
+            buffer,
+            bytes.len(),
+            offset,
+            Some(Target::ElementArrayBuffer),
+            None,
+        ),
+        bytes,
+        stride: block.index_stride(),
+    };
+    create_index_accessor(root, primitive, &view, block.index_count());
+}
+
+/// Names a mesh after its block type and diffuse texture, which is usually enough to tell the
+/// blocks of a model apart.
+fn mesh_name<T: GltfHelpers>(block: &T) -> String {
+    let diffuse = Path::new(block.textures()[0])
+        .file_stem()
+        .map(|stem| stem.to_string_lossy());
+    match diffuse {
+        Some(diffuse) if !diffuse.is_empty() => format!("{}_{diffuse}", block.block_name()),
+        _ => block.block_name().into(),
+    }
+}
+
+pub type Node = gltf_json::Node;
+pub type NodeIndex = Index<Node>;
+type Skin = gltf_json::Skin;
+type SkinIndex = Index<Skin>;
+
+/// Creates a skin with a placeholder node for every joint, so skinned meshes can be bound to a
+/// skeleton later. Joints are in global bone index order.
+fn create_skin(root: &mut GltfRoot, joint_count: usize) -> (NodeIndex, SkinIndex) {
+    let joints: Vec<NodeIndex> = (0..joint_count)
+        .map(|joint| {
+            root.push(Node {
+                mesh: Default::default(),
+                camera: Default::default(),
+                children: Default::default(),
+                extensions: Default::default(),
+                extras: Default::default(),
+                matrix: Default::default(),
+                name: Some(format!("bone_{joint}")),
+                rotation: Default::default(),
+                scale: Default::default(),
+                translation: Default::default(),
+                skin: Default::default(),
+                weights: Default::default(),
+            })
+        })
+        .collect();
+
+    let skeleton = root.push(Node {
+        mesh: Default::default(),
+        camera: Default::default(),
+        children: Some(joints.clone()),
+        extensions: Default::default(),
+        extras: Default::default(),
+        matrix: Default::default(),
+        name: Some("skeleton".into()),
+        rotation: Default::default(),
+        scale: Default::default(),
+        translation: Default::default(),
+        skin: Default::default(),
+        weights: Default::default(),
+    });
+
+    let skin = root.push(Skin {
+        extensions: Default::default(),
+        extras: Default::default(),
+        inverse_bind_matrices: None,
+        joints,
+        name: None,
+        skeleton: Some(skeleton),
+    });
+
+    (skeleton, skin)
+}
+
+type Mesh = gltf_json::Mesh;
+type MeshIndex = Index<Mesh>;
+type Scene = gltf_json::Scene;
+
+/// The meshes created for a model, which can be instanced any number of times.
+#[derive(Clone)]
+pub struct GltfModel {
+    name: String,
+    meshes: Vec<(MeshIndex, Option<SkinIndex>)>,
+}
+
+impl GltfModel {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Builds a glTF file out of render block models, keeping every mesh in a single buffer.
+pub struct GltfExporter {
+    root: GltfRoot,
+    buffer: BufferIndex,
+    data: Vec<u8>,
+    materials: MaterialExporter,
+    resolver: FileResolver,
+    nodes: Vec<NodeIndex>,
+}
+
+impl GltfExporter {
+    pub fn new(resolver: FileResolver) -> Self {
+        let mut root = GltfRoot::default();
+        let buffer = root.push(Buffer {
+            name: Some("buffer".into()),
+            uri: None,
+            byte_length: 0usize.into(),
+            extensions: Default::default(),
+            extras: Default::default(),
+        });
+
+        Self {
+            root,
+            buffer,
+            data: vec![],
+            materials: MaterialExporter::default(),
+            resolver,
+            nodes: vec![],
+        }
+    }
+
+    pub const fn resolver(&self) -> &FileResolver {
+        &self.resolver
+    }
+
+    /// Creates a mesh for every block in the model. Skinned blocks share a single skin, as
+    /// their joints are global bone indices.
+    pub fn add_model(&mut self, name: &str, rbm: &RenderBlockModel) -> anyhow::Result<GltfModel> {
+        let skins: Vec<Option<GltfSkin>> = rbm.blocks.iter().map(GltfHelpers::skin).collect();
+
+        let joint_count = skins
+            .iter()
+            .flatten()
+            .map(GltfSkin::joint_count)
+            .max()
+            .unwrap_or_default();
+        let skin = (joint_count > 0).then(|| {
+            let (skeleton, skin) = create_skin(&mut self.root, joint_count);
+            self.nodes.push(skeleton);
+            skin
+        });
+
+        let mut meshes = Vec::with_capacity(rbm.blocks.len());
+        for (block, block_skin) in rbm.blocks.iter().zip(&skins) {
+            let mut primitive = MeshPrimitive {
+                attributes: Default::default(),
+                extensions: Default::default(),
+                extras: Default::default(),
+                indices: None,
+                material: Some(
+                    self.materials
+                        .export(&mut self.root, &self.resolver, block)?,
+                ),
+                mode: Checked::Valid(block.mesh_mode()),
+                targets: None,
+            };
+
+            create_views_and_accessors(
+                &mut self.root,
+                &mut primitive,
+                &mut self.data,
+                block,
+                block_skin.as_ref(),
+                self.buffer,
+            );
+
+            let mesh = self.root.push(Mesh {
+                extensions: Default::default(),
+                extras: Default::default(),
+                name: Some(mesh_name(block)),
+                primitives: vec![primitive],
+                weights: None,
+            });
+            meshes.push((mesh, block_skin.as_ref().and(skin)));
+        }
+
+        Ok(GltfModel {
+            name: name.into(),
+            meshes,
+        })
+    }
+
+    /// Creates a node holding every mesh of a model, each in a child node named after its mesh.
+    pub fn add_instance(
+        &mut self,
+        model: &GltfModel,
+        name: Option<String>,
+        matrix: Option<[f32; 16]>,
+        extras: Option<Value>,
+    ) -> anyhow::Result<NodeIndex> {
+        let children = model
+            .meshes
+            .iter()
+            .map(|&(mesh, skin)| {
+                let name = self.root.meshes[mesh.value()].name.clone();
+                self.root.push(Node {
+                    mesh: Some(mesh),
+                    camera: Default::default(),
+                    children: Default::default(),
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                    matrix: Default::default(),
+                    name,
+                    rotation: Default::default(),
+                    scale: Default::default(),
+                    translation: Default::default(),
+                    skin,
+                    weights: Default::default(),
+                })
+            })
+            .collect();
+
+        self.add_node(
+            Node {
+                mesh: Default::default(),
+                camera: Default::default(),
+                children: Some(children),
+                extensions: Default::default(),
+                extras: Default::default(),
+                matrix,
+                name: Some(name.unwrap_or_else(|| model.name.clone())),
+                rotation: Default::default(),
+                scale: Default::default(),
+                translation: Default::default(),
+                skin: Default::default(),
+                weights: Default::default(),
+            },
+            extras,
+        )
+    }
+
+    pub fn add_node(&mut self, mut node: Node, extras: Option<Value>) -> anyhow::Result<NodeIndex> {
+        if let Some(extras) = extras {
+            node.extras = Some(serde_json::value::to_raw_value(&extras)?);
+        }
+        Ok(self.root.push(node))
+    }
+
+    /// Adds an extension to a node, marking it as used by the file.
+    pub fn set_extension(&mut self, node: NodeIndex, name: &str, value: Value) {
+        self.root.nodes[node.value()]
+            .extensions
+            .get_or_insert_with(Default::default)
+            .others
+            .insert(name.into(), value);
+        if !self.root.extensions_used.iter().any(|used| used == name) {
+            self.root.extensions_used.push(name.into());
+        }
+    }
+
+    /// Writes the scene made up of `nodes` to `path`. A `.glb` path gets a single binary file
+    /// with the buffer and textures embedded, anything else a `.gltf` file with the buffer and
+    /// textures next to it.
+    pub fn write(mut self, path: &Path, nodes: Vec<NodeIndex>) -> anyhow::Result<()> {
+        let binary = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("glb"));
+        let directory = path.parent().context("invalid path")?;
+
+        for png in std::mem::take(&mut self.materials).into_images() {
+            if binary {
+                let offset = append(&mut self.data, &png.data);
+                let view = create_buffer_view(
+                    &mut self.root,
+                    &png.file_name,
+                    self.buffer,
+                    png.data.len(),
+                    offset,
+                    None,
+                    None,
+                );
+                let image = &mut self.root.images[png.image.value()];
+                image.buffer_view = Some(view);
+                image.mime_type = Some(MimeType("image/png".into()));
+            } else {
+                std::fs::write(directory.join(&png.file_name), &png.data)?;
+                self.root.images[png.image.value()].uri = Some(png.file_name);
+            }
+        }
+        self.data.resize((self.data.len() + 3) & !3, 0);
+
+        self.nodes.extend(nodes);
+        let scene = self.root.push(Scene {
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: None,
+            nodes: self.nodes,
+        });
+        self.root.scene = Some(scene);
+
+        let buffer = &mut self.root.buffers[self.buffer.value()];
+        buffer.byte_length = self.data.len().into();
+
+        if binary {
+            let json = gltf_json::serialize::to_vec(&self.root)?;
+            let glb = Glb {
+                // The length is worked out again when writing
+                header: Header {
+                    magic: *b"glTF",
+                    version: 2,
+                    length: 0,
+                },
+                json: Cow::Owned(json),
+                bin: Some(Cow::Owned(self.data)),
+            };
+            glb.to_writer(std::io::BufWriter::new(std::fs::File::create(path)?))?;
+        } else {
+            let filename = path.with_extension("bin");
+            std::fs::File::create(&filename)?.write_all(&self.data)?;
+            buffer.uri = Some(
+                filename
+                    .file_name()
+                    .context("invalid path")?
+                    .to_string_lossy()
+                    .into(),
+            );
+
+            let writer = std::fs::File::create(path)?;
+            gltf_json::serialize::to_writer_pretty(writer, &self.root)?;
+        }
+
+        Ok(())
+    }
+}
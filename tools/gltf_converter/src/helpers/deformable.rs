@@ -2,7 +2,7 @@ use std::mem::size_of;
 
 use jc2_file_formats::render_block_model::{DeformableVertex, LitDeformableVertex};
 
-use crate::AccessorComponentType;
+use crate::export::AccessorComponentType;
 
 use super::{AccessorType, GltfMeshAccessor, GltfMeshAccessors, MeshSemantic};
 
@@ -0,0 +1,233 @@
+use bitflags::Flags;
+use jc2_file_formats::render_block_model::{
+    BillboardFoliageRenderBlock, CarPaintAttributes, CarPaintFlags, CarPaintRenderBlock,
+    CarPaintSimpleRenderBlock, DeformableWindowRenderBlock, FacadeFlags, FacadeRenderBlock,
+    GeneralFlags, GeneralRenderBlock, HaloRenderBlock, LambertFlags, LambertRenderBlock,
+    SkinnedGeneralFlags, SkinnedGeneralRenderBlock, VegetationBarkRenderBlock,
+    VegetationFoliageFlags, VegetationFoliageRenderBlock, WindowFlags, WindowRenderBlock,
+};
+use serde_json::{Value, json};
+
+pub type GltfAlphaMode = gltf_json::material::AlphaMode;
+
+/// Everything about a render block's material that glTF has no direct field for ends up in
+/// `parameters`, which is written to the material's extras.
+pub struct GltfMaterialInfo {
+    pub alpha_mode: GltfAlphaMode,
+    pub double_sided: bool,
+    pub parameters: Value,
+}
+
+pub trait GltfMaterialInfos {
+    fn material_info(&self) -> GltfMaterialInfo;
+}
+
+#[inline]
+const fn alpha_mode(alpha_test: bool, alpha_blending: bool) -> GltfAlphaMode {
+    if alpha_test {
+        GltfAlphaMode::Mask
+    } else if alpha_blending {
+        GltfAlphaMode::Blend
+    } else {
+        GltfAlphaMode::Opaque
+    }
+}
+
+#[inline]
+fn flags<T: Flags>(flags: &T) -> Vec<&'static str> {
+    flags.iter_names().map(|(name, _)| name).collect()
+}
+
+fn car_paint(attributes: &CarPaintAttributes) -> GltfMaterialInfo {
+    GltfMaterialInfo {
+        alpha_mode: alpha_mode(
+            attributes.flags.contains(CarPaintFlags::ALPHA_TEST),
+            attributes.flags.contains(CarPaintFlags::ALPHA_BLENDING),
+        ),
+        double_sided: attributes.flags.contains(CarPaintFlags::NO_CULLING),
+        parameters: json!({
+            "two_tone_colors": attributes.two_tone_colors.map(<[f32; 3]>::from),
+            "specular_power": attributes.specular_power,
+            "depth_bias": attributes.depth_bias,
+            "reflection_multiplier": attributes.reflection_multiplier,
+            "noise_factors": <[f32; 4]>::from(attributes.noise_factors),
+            "flags": flags(&attributes.flags),
+        }),
+    }
+}
+
+impl GltfMaterialInfos for BillboardFoliageRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        GltfMaterialInfo {
+            alpha_mode: GltfAlphaMode::Mask,
+            double_sided: true,
+            parameters: json!({}),
+        }
+    }
+}
+
+impl GltfMaterialInfos for CarPaintRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        car_paint(&self.attributes)
+    }
+}
+
+impl GltfMaterialInfos for CarPaintSimpleRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        car_paint(&self.attributes)
+    }
+}
+
+impl GltfMaterialInfos for DeformableWindowRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        GltfMaterialInfo {
+            alpha_mode: GltfAlphaMode::Blend,
+            double_sided: true,
+            parameters: json!({
+                "flags": flags(&self.attributes.flags),
+            }),
+        }
+    }
+}
+
+impl GltfMaterialInfos for FacadeRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        let attributes = &self.attributes;
+        GltfMaterialInfo {
+            alpha_mode: alpha_mode(
+                false,
+                attributes.flags.contains(FacadeFlags::ALPHA_BLENDING),
+            ),
+            double_sided: attributes.flags.contains(FacadeFlags::NO_CULLING),
+            parameters: json!({
+                "channel_mask": <[f32; 4]>::from(attributes.channel_mask),
+                "channel_dirt_mask": <[f32; 3]>::from(attributes.channel_dirt_mask),
+                "emissive_multiplier": <[f32; 3]>::from(attributes.emissive_multiplier),
+                "depth_bias": attributes.depth_bias,
+                "specular_power": attributes.specular_power,
+                "scale": attributes.scale,
+                "flags": flags(&attributes.flags),
+            }),
+        }
+    }
+}
+
+impl GltfMaterialInfos for GeneralRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        let attributes = &self.attributes;
+        GltfMaterialInfo {
+            alpha_mode: alpha_mode(
+                attributes.flags.contains(GeneralFlags::ALPHA_TEST),
+                attributes.flags.contains(GeneralFlags::ALPHA_BLENDING),
+            ),
+            double_sided: attributes.flags.contains(GeneralFlags::NO_CULLING),
+            parameters: json!({
+                "channel_mask": <[f32; 4]>::from(attributes.channel_mask),
+                "channel_ambient_occlusion_mask":
+                    <[f32; 4]>::from(attributes.channel_ambient_occlusion_mask),
+                "depth_bias": attributes.depth_bias,
+                "specular_power": attributes.specular_power,
+                "flags": flags(&attributes.flags),
+            }),
+        }
+    }
+}
+
+impl GltfMaterialInfos for HaloRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        GltfMaterialInfo {
+            alpha_mode: GltfAlphaMode::Blend,
+            double_sided: false,
+            parameters: json!({}),
+        }
+    }
+}
+
+impl GltfMaterialInfos for LambertRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        let attributes = &self.attributes;
+        GltfMaterialInfo {
+            alpha_mode: alpha_mode(
+                attributes.flags.contains(LambertFlags::ALPHA_TEST),
+                attributes.flags.contains(LambertFlags::ALPHA_BLENDING),
+            ),
+            double_sided: attributes.flags.contains(LambertFlags::TWO_SIDED),
+            parameters: json!({
+                "depth_bias": attributes.depth_bias,
+                "texture_channel": attributes.texture_channel,
+                "ambient_occlusion_channel": attributes.ambient_occlusion_channel,
+                "flags": flags(&attributes.flags),
+            }),
+        }
+    }
+}
+
+impl GltfMaterialInfos for SkinnedGeneralRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        let attributes = &self.attributes;
+        GltfMaterialInfo {
+            alpha_mode: alpha_mode(
+                attributes.flags.contains(SkinnedGeneralFlags::ALPHA_TEST),
+                attributes
+                    .flags
+                    .contains(SkinnedGeneralFlags::ALPHA_BLENDING),
+            ),
+            double_sided: attributes.flags.contains(SkinnedGeneralFlags::NO_CULLING),
+            parameters: json!({
+                "technique": format!("{:?}", attributes.technique),
+                "specular_power": attributes.specular_power,
+                "rim_intensity": attributes.rim_intensity,
+                "rim_power": attributes.rim_power,
+                "rim_weights": <[f32; 3]>::from(attributes.rim_weights),
+                "flags": flags(&attributes.flags),
+            }),
+        }
+    }
+}
+
+impl GltfMaterialInfos for VegetationBarkRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        GltfMaterialInfo {
+            alpha_mode: GltfAlphaMode::Opaque,
+            double_sided: false,
+            parameters: json!({
+                "flags": flags(&self.attributes.flags),
+            }),
+        }
+    }
+}
+
+impl GltfMaterialInfos for VegetationFoliageRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        let attributes = &self.attributes;
+        GltfMaterialInfo {
+            alpha_mode: alpha_mode(
+                !attributes
+                    .flags
+                    .contains(VegetationFoliageFlags::NO_ALPHA_TEST),
+                false,
+            ),
+            double_sided: true,
+            parameters: json!({
+                "specular_intensity": attributes.specular_intensity,
+                "specular_power": attributes.specular_power,
+                "translucency_mask": <[f32; 3]>::from(attributes.translucency_mask),
+                "flags": flags(&attributes.flags),
+            }),
+        }
+    }
+}
+
+impl GltfMaterialInfos for WindowRenderBlock {
+    fn material_info(&self) -> GltfMaterialInfo {
+        let attributes = &self.attributes;
+        GltfMaterialInfo {
+            alpha_mode: GltfAlphaMode::Blend,
+            double_sided: !attributes.flags.contains(WindowFlags::ONE_SIDED),
+            parameters: json!({
+                "specular_power": attributes.specular_power,
+                "flags": flags(&attributes.flags),
+            }),
+        }
+    }
+}
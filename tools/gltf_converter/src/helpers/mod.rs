@@ -5,10 +5,16 @@ mod deformable;
 mod facade;
 mod general;
 mod halo;
+mod material;
 mod simple;
 mod skinned;
 mod vegetation;
 
+pub use material::{GltfAlphaMode, GltfMaterialInfo};
+pub use skinned::GltfSkin;
+
+use material::GltfMaterialInfos;
+
 type AccessorType = gltf_json::accessor::Type;
 type AccessorComponentType = gltf_json::accessor::ComponentType;
 type MeshSemantic = gltf_json::mesh::Semantic;
@@ -32,11 +38,13 @@ pub trait GltfHelpers {
     fn vertices_as_bytes(&self) -> &[u8];
     fn indices_as_bytes(&self) -> &[u8];
 
-    #[allow(dead_code)]
+    fn block_name(&self) -> &'static str;
     fn textures(&self) -> [&str; 8];
+    fn material_info(&self) -> GltfMaterialInfo;
     fn mesh_mode(&self) -> GltfMeshMode;
     fn accessors(&self) -> Vec<GltfMeshAccessor>;
     fn target_accessors(&self) -> Option<Vec<GltfMeshAccessor>>;
+    fn skin(&self) -> Option<GltfSkin>;
 }
 
 const fn count<T>(value: &[T]) -> usize {
@@ -201,6 +209,24 @@ impl GltfHelpers for RenderBlock {
         }
     }
 
+    #[inline]
+    fn block_name(&self) -> &'static str {
+        match self {
+            RenderBlock::BillboardFoliage(_) => "BillboardFoliage",
+            RenderBlock::CarPaint(_) => "CarPaint",
+            RenderBlock::CarPaintSimple(_) => "CarPaintSimple",
+            RenderBlock::DeformableWindow(_) => "DeformableWindow",
+            RenderBlock::Facade(_) => "Facade",
+            RenderBlock::General(_) => "General",
+            RenderBlock::Halo(_) => "Halo",
+            RenderBlock::Lambert(_) => "Lambert",
+            RenderBlock::SkinnedGeneral(_) => "SkinnedGeneral",
+            RenderBlock::VegetationBark(_) => "VegetationBark",
+            RenderBlock::VegetationFoliage(_) => "VegetationFoliage",
+            RenderBlock::Window(_) => "Window",
+        }
+    }
+
     #[inline]
     fn textures(&self) -> [&str; 8] {
         match self {
@@ -219,6 +245,24 @@ impl GltfHelpers for RenderBlock {
         }
     }
 
+    #[inline]
+    fn material_info(&self) -> GltfMaterialInfo {
+        match self {
+            RenderBlock::BillboardFoliage(data) => data.material_info(),
+            RenderBlock::CarPaint(data) => data.material_info(),
+            RenderBlock::CarPaintSimple(data) => data.material_info(),
+            RenderBlock::DeformableWindow(data) => data.material_info(),
+            RenderBlock::Facade(data) => data.material_info(),
+            RenderBlock::General(data) => data.material_info(),
+            RenderBlock::Halo(data) => data.material_info(),
+            RenderBlock::Lambert(data) => data.material_info(),
+            RenderBlock::SkinnedGeneral(data) => data.material_info(),
+            RenderBlock::VegetationBark(data) => data.material_info(),
+            RenderBlock::VegetationFoliage(data) => data.material_info(),
+            RenderBlock::Window(data) => data.material_info(),
+        }
+    }
+
     #[inline]
     fn mesh_mode(&self) -> GltfMeshMode {
         match self {
@@ -272,4 +316,12 @@ impl GltfHelpers for RenderBlock {
             RenderBlock::Window(data) => target_accessors(&data.vertices),
         }
     }
+
+    #[inline]
+    fn skin(&self) -> Option<GltfSkin> {
+        match self {
+            RenderBlock::SkinnedGeneral(data) => Some(GltfSkin::new(data)),
+            _ => None,
+        }
+    }
 }
@@ -1,55 +1,144 @@
-use std::mem::size_of;
+use jc2_file_formats::render_block_model::{
+    SkinnedGeneralFlags, SkinnedGeneralRenderBlock, SkinnedVertex,
+};
 
-use jc2_file_formats::render_block_model::SkinnedVertex;
+use crate::export::AccessorComponentType;
 
-use crate::AccessorComponentType;
-
-use super::{AccessorType, GltfMeshAccessor, GltfMeshAccessors, MeshSemantic};
+use super::{AccessorType, GltfMeshAccessor, GltfMeshAccessors, MeshSemantic, bytes};
 
 impl GltfMeshAccessors for SkinnedVertex {
     fn accessors() -> Vec<GltfMeshAccessor> {
-        let mut result = vec![(
-            AccessorType::Vec3,
-            AccessorComponentType::F32,
-            MeshSemantic::Positions,
-            std::mem::offset_of!(SkinnedVertex, position),
-        )];
-        for i in 0..8 {
-            result.push((
-                AccessorType::Scalar,
+        // Bone influences are batch-local, see `GltfSkin` for the remapped joints and weights
+        vec![
+            (
+                AccessorType::Vec3,
+                AccessorComponentType::F32,
+                MeshSemantic::Positions,
+                std::mem::offset_of!(SkinnedVertex, position),
+            ),
+            (
+                AccessorType::Vec3,
+                AccessorComponentType::F32,
+                MeshSemantic::Normals,
+                std::mem::offset_of!(SkinnedVertex, normal),
+            ),
+            (
+                AccessorType::Vec3,
+                AccessorComponentType::F32,
+                MeshSemantic::Tangents,
+                std::mem::offset_of!(SkinnedVertex, tangent),
+            ),
+            (
+                AccessorType::Vec2,
                 AccessorComponentType::F32,
-                MeshSemantic::Weights(i),
-                std::mem::offset_of!(SkinnedVertex, bone_weights) + size_of::<f32>() * i as usize,
+                MeshSemantic::TexCoords(0),
+                std::mem::offset_of!(SkinnedVertex, uv0),
+            ),
+        ]
+    }
+
+    fn target_accessors() -> Option<Vec<GltfMeshAccessor>> {
+        None
+    }
+}
+
+/// The joints and weights of a single vertex, with joints referring to global bone indices.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GltfSkinInfluence {
+    pub joints: [u16; 8],
+    pub weights: [f32; 8],
+}
+
+/// Skinning data for a render block, kept apart from the vertices as the remapped joints no
+/// longer fit the game's layout.
+#[derive(Clone, Debug)]
+pub struct GltfSkin {
+    pub influences: Vec<GltfSkinInfluence>,
+    pub eight_bone_influence: bool,
+}
+
+impl GltfSkin {
+    pub const STRIDE: usize = size_of::<GltfSkinInfluence>();
+
+    pub fn new(block: &SkinnedGeneralRenderBlock) -> Self {
+        let eight_bone_influence = block
+            .attributes
+            .flags
+            .contains(SkinnedGeneralFlags::EIGHT_BONE_INFLUENCE);
+        let influence_count = if eight_bone_influence { 8 } else { 4 };
+
+        let mut influences = vec![GltfSkinInfluence::default(); block.vertices.len()];
+
+        // Each batch draws a range of indices, and its vertices index into the batch's palette
+        for batch in block.skin_batches.iter() {
+            let start = batch.offset as usize;
+            let end = start + batch.size as usize;
+            for &index in block.indices.get(start..end).unwrap_or_default() {
+                let index = index as usize;
+                let (Some(vertex), Some(influence)) =
+                    (block.vertices.get(index), influences.get_mut(index))
+                else {
+                    continue;
+                };
+
+                for i in 0..influence_count {
+                    let joint = batch
+                        .bone_indices
+                        .get(vertex.bone_indices[i] as usize)
+                        .copied();
+                    influence.joints[i] = joint.unwrap_or_default();
+                    influence.weights[i] = joint.map_or(0.0, |_| vertex.bone_weights[i]);
+                }
+
+                // Packed weights lose precision, glTF expects them to sum to one
+                let total: f32 = influence.weights.iter().sum();
+                if total > 0.0 {
+                    for weight in &mut influence.weights {
+                        *weight /= total;
+                    }
+                }
+            }
+        }
+
+        Self {
+            influences,
+            eight_bone_influence,
+        }
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        bytes(&self.influences)
+    }
+
+    /// Returns the number of joints referenced, which is one past the highest global bone index.
+    pub fn joint_count(&self) -> usize {
+        self.influences
+            .iter()
+            .flat_map(|influence| influence.joints)
+            .map(|joint| joint as usize + 1)
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn accessors(&self) -> Vec<GltfMeshAccessor> {
+        let sets = if self.eight_bone_influence { 2 } else { 1 };
+        let mut result = Vec::with_capacity(sets * 2);
+        for set in 0..sets {
+            result.push((
+                AccessorType::Vec4,
+                AccessorComponentType::U16,
+                MeshSemantic::Joints(set as u32),
+                std::mem::offset_of!(GltfSkinInfluence, joints) + set * 4 * size_of::<u16>(),
             ));
             result.push((
-                AccessorType::Scalar,
-                AccessorComponentType::U32,
-                MeshSemantic::Joints(i),
-                std::mem::offset_of!(SkinnedVertex, bone_indices) + size_of::<u32>() * i as usize,
+                AccessorType::Vec4,
+                AccessorComponentType::F32,
+                MeshSemantic::Weights(set as u32),
+                std::mem::offset_of!(GltfSkinInfluence, weights) + set * 4 * size_of::<f32>(),
             ));
         }
-        result.push((
-            AccessorType::Vec3,
-            AccessorComponentType::F32,
-            MeshSemantic::Normals,
-            std::mem::offset_of!(SkinnedVertex, normal),
-        ));
-        result.push((
-            AccessorType::Vec3,
-            AccessorComponentType::F32,
-            MeshSemantic::Tangents,
-            std::mem::offset_of!(SkinnedVertex, tangent),
-        ));
-        result.push((
-            AccessorType::Vec2,
-            AccessorComponentType::F32,
-            MeshSemantic::TexCoords(0),
-            std::mem::offset_of!(SkinnedVertex, uv0),
-        ));
         result
     }
-
-    fn target_accessors() -> Option<Vec<GltfMeshAccessor>> {
-        None
-    }
 }
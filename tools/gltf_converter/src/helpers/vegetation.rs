@@ -1,6 +1,6 @@
 use jc2_file_formats::render_block_model::VegetationVertex;
 
-use crate::AccessorComponentType;
+use crate::export::AccessorComponentType;
 
 use super::{AccessorType, GltfMeshAccessor, GltfMeshAccessors, MeshSemantic};
 
@@ -0,0 +1,606 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Context, bail};
+use bitflags::Flags;
+use clap::ValueEnum;
+use gltf::{
+    Document, Node, Primitive, Semantic, accessor::Dimensions, material::AlphaMode, mesh::Mode,
+};
+use jc2_file_formats::{
+    math::{
+        Aabb, Mat4, Vec3, Vec4,
+        ops::{VecCross, VecLength},
+    },
+    render_block_model::{
+        CarPaintAttributes, CarPaintFlags, CarPaintRenderBlock, CarPaintSimpleRenderBlock,
+        GeneralAttributes, GeneralFlags, GeneralRenderBlock, GeneralVertex, GenericVertex,
+        LambertAttributes, LambertFlags, LambertRenderBlock, LitDeformableVertex, Material,
+        PrimitiveType, RenderBlock, RenderBlockModel, RenderBlockModelEndian, SimpleVertex,
+        SkinBatch, SkinnedGeneralAttributes, SkinnedGeneralFlags, SkinnedGeneralRenderBlock,
+        SkinnedVertex, VertexFormat, VertexInfo, WindowAttributes, WindowFlags, WindowRenderBlock,
+    },
+};
+use serde_json::Value;
+
+/// The render blocks a glTF primitive can be imported as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum BlockType {
+    General,
+    Lambert,
+    Window,
+    CarPaint,
+    CarPaintSimple,
+    SkinnedGeneral,
+}
+
+pub struct ImportOptions {
+    /// Used for primitives whose material does not name a block in its extras.
+    pub block: BlockType,
+    /// Whether blocks with a `VertexInfo` should store quantised 16-bit vertices.
+    pub quantise: bool,
+}
+
+/// The most bones a single skin batch can reference, matching the palette `SkinBatch::default`
+/// allocates. Triangles referencing more bones than this can't be batched.
+const MAX_BATCH_BONES: usize = 18;
+
+/// How a glTF material maps onto a render block, read from the extras the exporter writes when
+/// present and from the glTF material otherwise.
+struct BlockMaterial {
+    block: BlockType,
+    material: Material,
+    alpha_mode: AlphaMode,
+    double_sided: bool,
+    flags: Option<Vec<String>>,
+}
+
+impl BlockMaterial {
+    fn new(material: &gltf::Material<'_>, mode: Mode, options: &ImportOptions) -> Self {
+        let extras: Value = material
+            .extras()
+            .as_ref()
+            .and_then(|extras| serde_json::from_str(extras.get()).ok())
+            .unwrap_or_default();
+
+        let block = extras["block"]
+            .as_str()
+            .and_then(|name| {
+                BlockType::value_variants()
+                    .iter()
+                    .find(|block| format!("{block:?}") == name)
+                    .copied()
+            })
+            .unwrap_or(options.block);
+
+        let mut result = Material {
+            primitive_type: match mode {
+                Mode::TriangleStrip => PrimitiveType::IndexedTriangleStrip,
+                Mode::TriangleFan => PrimitiveType::IndexedTriangleFan,
+                _ => PrimitiveType::IndexedTriangleList,
+            },
+            ..Default::default()
+        };
+
+        if let Some(textures) = extras["textures"].as_array() {
+            for (texture, name) in result.textures.iter_mut().zip(textures) {
+                *texture = name.as_str().unwrap_or_default().into();
+            }
+        } else {
+            let pbr = material.pbr_metallic_roughness();
+            let diffuse = pbr.base_color_texture().map(|info| info.texture());
+            let normal = material.normal_texture().map(|info| info.texture());
+            result.textures[0] = diffuse.map(texture_path).unwrap_or_default().into();
+            result.textures[1] = normal.map(texture_path).unwrap_or_default().into();
+        }
+
+        let flags = extras["flags"].as_array().map(|flags| {
+            flags
+                .iter()
+                .filter_map(|flag| flag.as_str().map(str::to_owned))
+                .collect()
+        });
+
+        Self {
+            block,
+            material: result,
+            alpha_mode: material.alpha_mode(),
+            double_sided: material.double_sided(),
+            flags,
+        }
+    }
+
+    /// Returns the flags named in the extras, or `default` with the alpha and culling flags
+    /// derived from the glTF material.
+    fn flags<T: Flags + Copy>(
+        &self,
+        default: T,
+        alpha_test: T,
+        alpha_blending: T,
+        double_sided: T,
+    ) -> T {
+        if let Some(names) = &self.flags {
+            return names
+                .iter()
+                .filter_map(|name| T::from_name(name))
+                .fold(T::empty(), |flags, flag| flags.union(flag));
+        }
+
+        let mut flags = default;
+        match self.alpha_mode {
+            AlphaMode::Mask => flags.insert(alpha_test),
+            AlphaMode::Blend => flags.insert(alpha_blending),
+            AlphaMode::Opaque => {}
+        }
+        if self.double_sided {
+            flags.insert(double_sided);
+        }
+        flags
+    }
+}
+
+/// Returns the DDS path the game should load in place of a glTF texture.
+fn texture_path(texture: gltf::Texture<'_>) -> String {
+    let name = match texture.source().source() {
+        gltf::image::Source::Uri { uri, .. } => uri,
+        gltf::image::Source::View { .. } => texture.source().name().unwrap_or_default(),
+    };
+    Path::new(name)
+        .with_extension("dds")
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Returns every node with a mesh, along with its world transform.
+fn mesh_nodes(document: &Document) -> Vec<(Node<'_>, Mat4<f32>)> {
+    fn visit<'a>(node: Node<'a>, parent: Mat4<f32>, result: &mut Vec<(Node<'a>, Mat4<f32>)>) {
+        let [x, y, z, w] = node.transform().matrix();
+        let transform = parent * Mat4::from_cols(x.into(), y.into(), z.into(), w.into());
+        for child in node.children() {
+            visit(child, transform, result);
+        }
+        if node.mesh().is_some() {
+            result.push((node, transform));
+        }
+    }
+
+    // Files without a scene still have nodes, so fall back to every node that has no parent
+    let roots: Vec<Node<'_>> = if let Some(scene) = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        scene.nodes().collect()
+    } else {
+        let children: Vec<usize> = document
+            .nodes()
+            .flat_map(|node| node.children().map(|child| child.index()))
+            .collect();
+        document
+            .nodes()
+            .filter(|node| !children.contains(&node.index()))
+            .collect()
+    };
+
+    let mut result = Vec::new();
+    for root in roots {
+        visit(root, Mat4::identity(), &mut result);
+    }
+    result
+}
+
+#[inline]
+fn normalize(value: Vec3<f32>) -> Vec3<f32> {
+    let length = value.length();
+    if length > 0.0 { value / length } else { value }
+}
+
+fn read_primitive(
+    primitive: &Primitive<'_>,
+    buffers: &[gltf::buffer::Data],
+    transform: &Mat4<f32>,
+) -> anyhow::Result<(Vec<GenericVertex>, Vec<u32>)> {
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
+
+    let mut vertices: Vec<GenericVertex> = reader
+        .read_positions()
+        .context("primitive has no positions")?
+        .map(|position| GenericVertex {
+            position: transform.transform_point3(position.into()),
+            diffuse_color: Vec4::splat(1.0),
+            ..Default::default()
+        })
+        .collect();
+
+    if let Some(normals) = reader.read_normals() {
+        for (vertex, normal) in vertices.iter_mut().zip(normals) {
+            vertex.normal = normalize(transform.transform_vector3(normal.into()));
+        }
+    }
+
+    // Tangents without a handedness are invalid glTF, and the reader cannot read them anyway
+    let tangents = primitive
+        .get(&Semantic::Tangents)
+        .filter(|accessor| accessor.dimensions() == Dimensions::Vec4)
+        .and_then(|_| reader.read_tangents());
+    if let Some(tangents) = tangents {
+        for (vertex, tangent) in vertices.iter_mut().zip(tangents) {
+            let sign = tangent[3];
+            vertex.tangent = normalize(transform.transform_vector3(Vec4::from(tangent).into()));
+            vertex.binormal = vertex.normal.cross(vertex.tangent) * sign;
+        }
+    }
+
+    if let Some(uvs) = reader.read_tex_coords(0) {
+        for (vertex, uv) in vertices.iter_mut().zip(uvs.into_f32()) {
+            vertex.uv0 = uv.into();
+        }
+    }
+
+    if let Some(uvs) = reader.read_tex_coords(1) {
+        for (vertex, uv) in vertices.iter_mut().zip(uvs.into_f32()) {
+            vertex.uv1 = uv.into();
+        }
+    }
+
+    if let Some(colors) = reader.read_colors(0) {
+        for (vertex, color) in vertices.iter_mut().zip(colors.into_rgba_f32()) {
+            vertex.diffuse_color = color.into();
+        }
+    }
+
+    for set in 0..2 {
+        let (Some(joints), Some(weights)) = (reader.read_joints(set), reader.read_weights(set))
+        else {
+            continue;
+        };
+        let offset = set as usize * 4;
+        for ((vertex, joints), weights) in vertices
+            .iter_mut()
+            .zip(joints.into_u16())
+            .zip(weights.into_f32())
+        {
+            for i in 0..4 {
+                vertex.bone_indices[offset + i] = u32::from(joints[i]);
+                vertex.bone_weights[offset + i] = weights[i];
+            }
+        }
+    }
+
+    let indices: Vec<u32> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..vertices.len() as u32).collect(),
+    };
+
+    if let Some(index) = indices
+        .iter()
+        .find(|&&index| index as usize >= vertices.len())
+    {
+        bail!(
+            "primitive index {index} is out of range for {} vertices",
+            vertices.len()
+        );
+    }
+
+    Ok((vertices, indices))
+}
+
+#[inline]
+fn convert<T: From<GenericVertex>>(vertices: Vec<GenericVertex>) -> Vec<T> {
+    vertices.into_iter().map(T::from).collect()
+}
+
+#[inline]
+fn indices(indices: Vec<u32>) -> anyhow::Result<Vec<u16>> {
+    indices
+        .into_iter()
+        .map(|index| u16::try_from(index).context("too many vertices for a render block"))
+        .collect()
+}
+
+/// Splits skinned triangles into batches that fit the bone palette, remapping each vertex to
+/// batch-local bone indices and duplicating vertices shared between batches.
+fn skin_batches(
+    vertices: &[GenericVertex],
+    indices: &[u32],
+    influences: usize,
+) -> anyhow::Result<(Vec<SkinnedVertex>, Vec<u32>, Vec<SkinBatch>)> {
+    fn bones(vertex: &GenericVertex, influences: usize) -> impl Iterator<Item = u16> + '_ {
+        (0..influences)
+            .filter(|&i| vertex.bone_weights[i] > 0.0)
+            .map(|i| vertex.bone_indices[i] as u16)
+    }
+
+    let mut result_vertices = Vec::with_capacity(vertices.len());
+    let mut result_indices = Vec::with_capacity(indices.len());
+    let mut batches: Vec<SkinBatch> = Vec::new();
+    let mut remap: HashMap<u32, u32> = HashMap::new();
+
+    for triangle in indices.chunks_exact(3) {
+        let mut palette = batches
+            .last()
+            .map(|batch| batch.bone_indices.clone())
+            .unwrap_or_default();
+        for &index in triangle {
+            for bone in bones(&vertices[index as usize], influences) {
+                if !palette.contains(&bone) {
+                    palette.push(bone);
+                }
+            }
+        }
+
+        if batches.is_empty() || palette.len() > MAX_BATCH_BONES {
+            remap.clear();
+            palette.retain(|bone| {
+                triangle.iter().any(|&index| {
+                    bones(&vertices[index as usize], influences).any(|other| other == *bone)
+                })
+            });
+            if palette.len() > MAX_BATCH_BONES {
+                bail!(
+                    "triangle references {} bones, but skin batches hold at most {MAX_BATCH_BONES}",
+                    palette.len()
+                );
+            }
+            batches.push(SkinBatch {
+                size: 0,
+                offset: result_indices.len() as u32,
+                bone_indices: Vec::new(),
+            });
+        }
+
+        let Some(batch) = batches.last_mut() else {
+            continue;
+        };
+        batch.bone_indices = palette;
+        batch.size += 3;
+
+        for &index in triangle {
+            let remapped = *remap.entry(index).or_insert_with(|| {
+                let mut vertex = SkinnedVertex::from(vertices[index as usize].clone());
+                for bone in &mut vertex.bone_indices {
+                    *bone = batch
+                        .bone_indices
+                        .iter()
+                        .position(|other| u32::from(*other) == *bone)
+                        .unwrap_or_default() as u32;
+                }
+                result_vertices.push(vertex);
+                result_vertices.len() as u32 - 1
+            });
+            result_indices.push(remapped);
+        }
+    }
+
+    Ok((result_vertices, result_indices, batches))
+}
+
+fn general_block(
+    block: &BlockMaterial,
+    vertices: Vec<GenericVertex>,
+    indices: Vec<u32>,
+    format: VertexFormat,
+) -> anyhow::Result<RenderBlock> {
+    let mut vertices = convert::<GeneralVertex>(vertices);
+    let vertex_info = VertexInfo::quantise(format, &mut vertices);
+    let default = GeneralAttributes::default();
+    Ok(RenderBlock::General(GeneralRenderBlock {
+        version: Default::default(),
+        attributes: GeneralAttributes {
+            vertex_info,
+            flags: block.flags(
+                default.flags,
+                GeneralFlags::ALPHA_TEST,
+                GeneralFlags::ALPHA_BLENDING,
+                GeneralFlags::NO_CULLING,
+            ),
+            ..default
+        },
+        material: block.material.clone(),
+        vertices: vertices.into(),
+        indices: self::indices(indices)?.into(),
+    }))
+}
+
+fn lambert_block(
+    block: &BlockMaterial,
+    vertices: Vec<GenericVertex>,
+    indices: Vec<u32>,
+    format: VertexFormat,
+) -> anyhow::Result<RenderBlock> {
+    let mut vertices = convert::<GeneralVertex>(vertices);
+    let vertex_info = VertexInfo::quantise(format, &mut vertices);
+    let default = LambertAttributes::default();
+    Ok(RenderBlock::Lambert(LambertRenderBlock {
+        attributes: LambertAttributes {
+            vertex_info,
+            flags: block.flags(
+                default.flags,
+                LambertFlags::ALPHA_TEST,
+                LambertFlags::ALPHA_BLENDING,
+                LambertFlags::TWO_SIDED,
+            ),
+            ..default
+        },
+        material: block.material.clone(),
+        vertices: vertices.into(),
+        indices: self::indices(indices)?.into(),
+        ..Default::default()
+    }))
+}
+
+fn window_block(
+    block: &BlockMaterial,
+    vertices: Vec<GenericVertex>,
+    indices: Vec<u32>,
+) -> anyhow::Result<RenderBlock> {
+    let mut attributes = WindowAttributes::default();
+    attributes.flags = block.flags(
+        attributes.flags,
+        WindowFlags::empty(),
+        WindowFlags::empty(),
+        WindowFlags::empty(),
+    );
+    if block.flags.is_none() {
+        attributes
+            .flags
+            .set(WindowFlags::ONE_SIDED, !block.double_sided);
+    }
+    Ok(RenderBlock::Window(WindowRenderBlock {
+        version: Default::default(),
+        attributes,
+        material: block.material.clone(),
+        vertices: convert::<GeneralVertex>(vertices).into(),
+        indices: self::indices(indices)?.into(),
+    }))
+}
+
+fn car_paint_block(
+    block: &BlockMaterial,
+    vertices: Vec<GenericVertex>,
+    indices: Vec<u32>,
+) -> anyhow::Result<RenderBlock> {
+    let default = CarPaintAttributes::default();
+    let attributes = CarPaintAttributes {
+        flags: block.flags(
+            default.flags,
+            CarPaintFlags::ALPHA_TEST,
+            CarPaintFlags::ALPHA_BLENDING,
+            CarPaintFlags::NO_CULLING,
+        ),
+        ..default
+    };
+
+    if block.block == BlockType::CarPaintSimple {
+        return Ok(RenderBlock::CarPaintSimple(CarPaintSimpleRenderBlock {
+            attributes,
+            material: block.material.clone(),
+            vertices: convert::<SimpleVertex>(vertices).into(),
+            indices: self::indices(indices)?.into(),
+            ..Default::default()
+        }));
+    }
+
+    // Without damage the deformed state is the same as the undeformed one
+    let vertices = vertices
+        .into_iter()
+        .map(|vertex| GenericVertex {
+            morph_position: vertex.position,
+            morph_normal: vertex.normal,
+            morph_tangent: vertex.tangent,
+            morph_binormal: vertex.binormal,
+            ..vertex
+        })
+        .collect();
+    Ok(RenderBlock::CarPaint(CarPaintRenderBlock {
+        attributes,
+        material: block.material.clone(),
+        vertices: convert::<LitDeformableVertex>(vertices).into(),
+        indices: self::indices(indices)?.into(),
+        ..Default::default()
+    }))
+}
+
+fn skinned_general_block(
+    block: &BlockMaterial,
+    vertices: &[GenericVertex],
+    indices: &[u32],
+) -> anyhow::Result<RenderBlock> {
+    if block.material.primitive_type != PrimitiveType::IndexedTriangleList {
+        bail!("skinned primitives must be triangle lists");
+    }
+
+    let eight_bone_influence = vertices
+        .iter()
+        .any(|vertex| vertex.bone_weights[4..].iter().any(|weight| *weight > 0.0));
+    let default = SkinnedGeneralAttributes::default();
+    let mut flags = block.flags(
+        default.flags,
+        SkinnedGeneralFlags::ALPHA_TEST,
+        SkinnedGeneralFlags::ALPHA_BLENDING,
+        SkinnedGeneralFlags::NO_CULLING,
+    );
+    flags.set(
+        SkinnedGeneralFlags::EIGHT_BONE_INFLUENCE,
+        eight_bone_influence,
+    );
+
+    let influences = if eight_bone_influence { 8 } else { 4 };
+    let (vertices, indices, skin_batches) = skin_batches(vertices, indices, influences)?;
+    Ok(RenderBlock::SkinnedGeneral(SkinnedGeneralRenderBlock {
+        attributes: SkinnedGeneralAttributes { flags, ..default },
+        material: block.material.clone(),
+        vertices: vertices.into(),
+        skin_batches: skin_batches.into(),
+        indices: self::indices(indices)?.into(),
+        ..Default::default()
+    }))
+}
+
+fn create_block(
+    block: &BlockMaterial,
+    vertices: Vec<GenericVertex>,
+    indices: Vec<u32>,
+    options: &ImportOptions,
+) -> anyhow::Result<RenderBlock> {
+    let format = if options.quantise {
+        VertexFormat::I16
+    } else {
+        VertexFormat::F32
+    };
+
+    match block.block {
+        BlockType::General => general_block(block, vertices, indices, format),
+        BlockType::Lambert => lambert_block(block, vertices, indices, format),
+        BlockType::Window => window_block(block, vertices, indices),
+        BlockType::CarPaint | BlockType::CarPaintSimple => {
+            car_paint_block(block, vertices, indices)
+        }
+        BlockType::SkinnedGeneral => skinned_general_block(block, &vertices, &indices),
+    }
+}
+
+/// Reads a glTF file and converts every primitive in its scene to a render block.
+pub fn import(path: &Path, options: &ImportOptions) -> anyhow::Result<RenderBlockModel> {
+    // Accessor bounds are often left out by exporters and are recomputed here anyway, so the
+    // file is not validated up front
+    let data = std::fs::read(path)?;
+    let gltf = gltf::Gltf::from_slice_without_validation(&data)?;
+    let buffers = gltf::import_buffers(&gltf.document, path.parent(), gltf.blob.clone())?;
+
+    let mut blocks = Vec::new();
+    let mut bounds: Option<Aabb<f32>> = None;
+
+    for (node, transform) in mesh_nodes(&gltf.document) {
+        let Some(mesh) = node.mesh() else {
+            continue;
+        };
+
+        for primitive in mesh.primitives() {
+            if !matches!(
+                primitive.mode(),
+                Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan
+            ) {
+                bail!("unsupported primitive mode ({:?})", primitive.mode());
+            }
+
+            let (vertices, indices) = read_primitive(&primitive, &buffers, &transform)?;
+            let primitive_bounds = Aabb::from_points(vertices.iter().map(|vertex| vertex.position));
+            bounds = match (bounds, primitive_bounds) {
+                (Some(bounds), Some(other)) => Some(bounds.union(&other)),
+                (bounds, other) => bounds.or(other),
+            };
+
+            let material = BlockMaterial::new(&primitive.material(), primitive.mode(), options);
+            blocks.push(create_block(&material, vertices, indices, options)?);
+        }
+    }
+
+    let bounds = bounds.unwrap_or_default();
+    Ok(RenderBlockModel {
+        endian: RenderBlockModelEndian::Little,
+        version: Vec3::new(1, 13, 0),
+        min: bounds.min,
+        max: bounds.max,
+        blocks: blocks.into(),
+    })
+}
@@ -1,298 +1,111 @@
-use std::{io::Write, path::PathBuf};
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
 
-use anyhow::Context;
+use anyhow::{Context, bail};
 use clap::Parser;
-use gltf_json::{Index, buffer::Stride, mesh::MorphTarget, validation::Checked};
-use helpers::GltfMeshAccessor;
-use jc2_file_formats::render_block_model::RenderBlockModel;
+use jc2_file_formats::{model_collection::ModelCollection, render_block_model::RenderBlockModel};
 
-use crate::helpers::GltfHelpers;
+use crate::{
+    export::GltfExporter,
+    import::{BlockType, ImportOptions},
+    resolver::FileResolver,
+    scene::SceneExporter,
+};
 
+mod export;
 mod helpers;
+mod import;
+mod material;
+mod resolver;
+mod scene;
 
-type GltfRoot = gltf_json::Root;
-type Buffer = gltf_json::Buffer;
-type BufferIndex = Index<Buffer>;
-type BufferView = gltf_json::buffer::View;
-type BufferViewIndex = Index<BufferView>;
-
-fn create_buffer_view(
-    root: &mut GltfRoot,
-    name: &str,
-    buffer: BufferIndex,
-    length: usize,
-    offset: usize,
-    stride: usize,
-) -> BufferViewIndex {
-    root.push(BufferView {
-        name: Some(name.into()),
-        target: Some(Checked::Valid(gltf_json::buffer::Target::ArrayBuffer)),
-        buffer,
-        byte_length: length.into(),
-        byte_offset: Some(offset.into()),
-        byte_stride: Some(Stride(stride)),
-        extensions: Default::default(),
-        extras: Default::default(),
-    })
-}
-
-type MeshPrimitive = gltf_json::mesh::Primitive;
-type Accessor = gltf_json::Accessor;
-type AccessorType = gltf_json::accessor::Type;
-type AccessorComponentType = gltf_json::accessor::ComponentType;
-type AccessorIndex = gltf_json::Index<gltf_json::Accessor>;
-
-fn create_accessor(
-    root: &mut GltfRoot,
-    buffer_view_index: BufferViewIndex,
-    accessor_type: AccessorType,
-    component_type: AccessorComponentType,
-    offset: usize,
-    count: usize,
-) -> AccessorIndex {
-    root.push(Accessor {
-        buffer_view: Some(buffer_view_index),
-        byte_offset: Some((offset).into()),
-        count: count.into(),
-        component_type: Checked::Valid(gltf_json::accessor::GenericComponentType(component_type)),
-        extensions: Default::default(),
-        extras: Default::default(),
-        type_: Checked::Valid(accessor_type),
-        min: None,
-        max: None,
-        name: None,
-        normalized: false,
-        sparse: None,
-    })
-}
-
-fn create_attribute_accessor(
-    root: &mut GltfRoot,
-    primitive: &mut MeshPrimitive,
-    buffer_view_index: BufferViewIndex,
-    accessor: GltfMeshAccessor,
-    count: usize,
-) -> AccessorIndex {
-    let (accessor_type, component_type, semantic, offset) = accessor;
-    let accessor = create_accessor(
-        root,
-        buffer_view_index,
-        accessor_type,
-        component_type,
-        offset,
-        count,
-    );
-    primitive
-        .attributes
-        .insert(Checked::Valid(semantic), accessor);
-    accessor
-}
-
-fn create_index_accessor(
-    root: &mut GltfRoot,
-    primitive: &mut MeshPrimitive,
-    buffer_view_index: BufferViewIndex,
-    count: usize,
-) -> AccessorIndex {
-    let accessor = create_accessor(
-        root,
-        buffer_view_index,
-        AccessorType::Scalar,
-        AccessorComponentType::U16,
-        0,
-        count,
-    );
-    primitive.indices = Some(accessor);
-    accessor
-}
-
-fn create_accessors<T: GltfHelpers>(
-    root: &mut GltfRoot,
-    primitive: &mut MeshPrimitive,
-    buffer_view_index: BufferViewIndex,
-    block: &T,
-) {
-    let vertex_count = block.vertex_count();
-    for accessor in block.accessors() {
-        create_attribute_accessor(
-            root,
-            primitive,
-            buffer_view_index,
-            accessor.clone(),
-            vertex_count,
-        );
-    }
-    if let Some(target_accessors) = block.target_accessors() {
-        let mut target = MorphTarget {
-            positions: None,
-            normals: None,
-            tangents: None,
-        };
-        for (accessor_type, component_type, semantic, offset) in target_accessors {
-            let accessor = create_accessor(
-                root,
-                buffer_view_index,
-                accessor_type,
-                component_type,
-                offset,
-                vertex_count,
-            );
-            match semantic {
-                gltf::Semantic::Positions => target.positions = Some(accessor),
-                gltf::Semantic::Normals => target.normals = Some(accessor),
-                gltf::Semantic::Tangents => target.tangents = Some(accessor),
-                _ => panic!("invalid morph semantic: {semantic:?}"),
-            }
-        }
-        primitive.targets = Some(vec![target]);
-    }
-}
-
-fn create_views_and_accessors<T: GltfHelpers>(
-    root: &mut GltfRoot,
-    primitive: &mut MeshPrimitive,
-    offset: &mut usize,
-    block: &T,
-    buffer: BufferIndex,
-) {
-    let idx = root.meshes.len();
-
-    let length = block.vertices_as_bytes().len();
-    let stride = block.vertex_stride();
-    let view = create_buffer_view(
-        root,
-        &format!("vertex_{idx}"),
-        buffer,
-        length,
-        *offset,
-        stride,
-    );
-    create_accessors(root, primitive, view, block);
-    *offset += length;
-
-    let length = block.indices_as_bytes().len();
-    let stride = block.index_stride();
-    let view = create_buffer_view(
-        root,
-        &format!("index_{idx}"),
-        buffer,
-        length,
-        *offset,
-        stride,
-    );
-    create_index_accessor(root, primitive, view, block.index_count());
-    *offset += length;
-}
-
+/// Converts render block models, model collections and `.lod` files to glTF, or glTF files back
+/// to render block models.
 #[derive(Parser)]
 struct Args {
     #[arg()]
     file: PathBuf,
+    /// Where to write the converted file, which defaults to a `.glb` or `.rbm` next to the input.
+    /// Exporting to a `.gltf` path writes the buffer and textures alongside it instead
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+    /// Archives, or directories of them, to find models and textures in when they are not next
+    /// to the input file
+    #[arg(long, short)]
+    archives: Vec<PathBuf>,
+    /// The render block to import primitives as, unless their material names one
+    #[arg(long, value_enum, default_value_t = BlockType::General)]
+    block: BlockType,
+    /// Store imported vertices as quantised 16-bit values where the block supports it
+    #[arg(long)]
+    quantise: bool,
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let file = std::fs::File::open(args.file.clone())?;
-    let rbm = RenderBlockModel::read(&mut std::io::BufReader::new(file))?;
-
-    // First pass, calculate necessary buffer size, and round up to nearest multiple of 4
-    let mut buffer_size = 0;
-
-    for block in rbm.blocks.iter() {
-        buffer_size += block.vertices_as_bytes().len();
-        buffer_size += block.indices_as_bytes().len();
+fn import(path: &Path, output: Option<PathBuf>, options: &ImportOptions) -> anyhow::Result<()> {
+    let output = output.unwrap_or_else(|| path.with_extension("rbm"));
+    if output.exists() {
+        bail!("{output:?} already exists");
     }
 
-    buffer_size = (buffer_size + 3) & !3;
+    let rbm = import::import(path, options)?;
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(output)?);
+    rbm.write(&mut writer)?;
 
-    // Second pass create the final buffer
-    let mut buffer = Vec::with_capacity(buffer_size);
+    Ok(())
+}
 
-    for block in rbm.blocks.iter() {
-        buffer.extend_from_slice(block.vertices_as_bytes());
-        buffer.extend_from_slice(block.indices_as_bytes());
-    }
+fn export(path: &Path, output: Option<PathBuf>, archives: &[PathBuf]) -> anyhow::Result<()> {
+    let output = output.unwrap_or_else(|| path.with_extension("glb"));
+    let directory = path.parent().context("invalid path")?;
+    let name = path
+        .file_name()
+        .context("invalid path")?
+        .to_string_lossy()
+        .into_owned();
+    let data = std::fs::read(path)?;
+    let mut exporter = GltfExporter::new(FileResolver::new(directory, archives)?);
+
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    let nodes = match extension {
+        Some("cgd") => {
+            let collection = ModelCollection::read(&mut Cursor::new(data))?;
+            let mut scene = SceneExporter::new(exporter);
+            let node = scene.add_collection(&name, &collection)?;
+            return scene.write(&output, vec![node]);
+        }
+        Some("lod") => {
+            let mut scene = SceneExporter::new(exporter);
+            let node = scene.add_lod_file(&name, &data)?;
+            return scene.write(&output, node.into_iter().collect());
+        }
+        _ => {
+            let rbm = RenderBlockModel::read(&mut Cursor::new(data))?;
+            let model = exporter.add_model(&name, &rbm)?;
+            vec![exporter.add_instance(&model, None, None, None)?]
+        }
+    };
 
-    buffer.resize(buffer_size, 0);
+    exporter.write(&output, nodes)
+}
 
-    // Create the gltf buffer
-    let filename = args.file.clone().with_extension("bin");
-    let mut writer = std::fs::File::create(filename.clone())?;
-    writer.write_all(&buffer)?;
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
 
-    let mut root = gltf_json::Root::default();
-    let buffer = root.push(Buffer {
-        name: Some("buffer".into()),
-        uri: Some(
-            filename
-                .file_name()
-                .context("invalid path")?
-                .to_string_lossy()
-                .into(),
+    match args
+        .file
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("gltf" | "glb") => import(
+            &args.file,
+            args.output,
+            &ImportOptions {
+                block: args.block,
+                quantise: args.quantise,
+            },
         ),
-        byte_length: buffer_size.into(),
-        extensions: Default::default(),
-        extras: Default::default(),
-    });
-
-    // Next pass, create the final gltf
-    let mut buffer_offset = 0;
-    let mut nodes = Vec::with_capacity(rbm.blocks.len());
-
-    for block in rbm.blocks.iter() {
-        let mut primitive = MeshPrimitive {
-            attributes: Default::default(),
-            extensions: Default::default(),
-            extras: Default::default(),
-            indices: None,
-            material: None,
-            mode: Checked::Valid(block.mesh_mode()),
-            targets: None,
-        };
-
-        create_views_and_accessors(&mut root, &mut primitive, &mut buffer_offset, block, buffer);
-
-        let mesh = root.push(gltf_json::Mesh {
-            extensions: Default::default(),
-            extras: Default::default(),
-            name: None,
-            primitives: vec![primitive],
-            weights: None,
-        });
-
-        nodes.push(root.push(gltf_json::Node {
-            mesh: Some(mesh),
-            camera: Default::default(),
-            children: Default::default(),
-            extensions: Default::default(),
-            extras: Default::default(),
-            matrix: Default::default(),
-            name: Default::default(),
-            rotation: Default::default(),
-            scale: Default::default(),
-            translation: Default::default(),
-            skin: Default::default(),
-            weights: Default::default(),
-        }));
+        _ => export(&args.file, args.output, &args.archives),
     }
-
-    root.push(gltf_json::Node {
-        mesh: Default::default(),
-        camera: Default::default(),
-        children: Some(nodes),
-        extensions: Default::default(),
-        extras: Default::default(),
-        matrix: Default::default(),
-        name: Default::default(),
-        rotation: Default::default(),
-        scale: Default::default(),
-        translation: Default::default(),
-        skin: Default::default(),
-        weights: Default::default(),
-    });
-
-    let writer = std::fs::File::create(args.file.clone().with_extension("gltf"))?;
-    gltf_json::serialize::to_writer_pretty(writer, &root)?;
-
-    Ok(())
 }
@@ -0,0 +1,164 @@
+use std::{collections::HashMap, io::Cursor, path::Path};
+
+use anyhow::Context;
+use gltf_json::{
+    Index,
+    material::{AlphaCutoff, NormalTexture, PbrMetallicRoughness, StrengthFactor},
+    validation::Checked,
+};
+use jc2_file_formats::texture::DdsTexture;
+use serde_json::json;
+
+use crate::{
+    export::GltfRoot,
+    helpers::{GltfAlphaMode, GltfHelpers},
+    resolver::FileResolver,
+};
+
+type Material = gltf_json::Material;
+type MaterialIndex = Index<Material>;
+type Texture = gltf_json::Texture;
+type TextureIndex = Index<Texture>;
+type TextureInfo = gltf_json::texture::Info;
+type Image = gltf_json::Image;
+type ImageIndex = Index<Image>;
+
+const DIFFUSE_TEXTURE: usize = 0;
+const NORMAL_TEXTURE: usize = 1;
+const PROPERTIES_TEXTURE: usize = 2;
+
+/// A texture decoded to a PNG, which is either embedded in the buffer or written next to the
+/// output file once its location is known.
+pub struct PngImage {
+    pub image: ImageIndex,
+    pub file_name: String,
+    pub data: Vec<u8>,
+}
+
+/// Converts render block materials to glTF materials, decoding each referenced DDS texture to a
+/// PNG.
+#[derive(Default)]
+pub struct MaterialExporter {
+    textures: HashMap<String, Option<TextureIndex>>,
+    images: Vec<PngImage>,
+}
+
+impl MaterialExporter {
+    /// Returns the decoded images, which have no source until they are written out.
+    pub fn into_images(self) -> Vec<PngImage> {
+        self.images
+    }
+
+    pub fn export<T: GltfHelpers>(
+        &mut self,
+        root: &mut GltfRoot,
+        resolver: &FileResolver,
+        block: &T,
+    ) -> anyhow::Result<MaterialIndex> {
+        let textures = block.textures();
+        let info = block.material_info();
+
+        let diffuse = self.texture(root, resolver, textures[DIFFUSE_TEXTURE]);
+        let normal = self.texture(root, resolver, textures[NORMAL_TEXTURE]);
+        let properties = self.texture(root, resolver, textures[PROPERTIES_TEXTURE]);
+
+        // glTF has nowhere to put the properties texture, so it is referenced from the extras
+        // alongside the block type and original texture names, which the importer reads back
+        let mut extras = info.parameters;
+        extras["block"] = json!(block.block_name());
+        extras["textures"] = json!(textures);
+        if let Some(properties) = properties {
+            extras["properties_texture"] = json!(properties.value());
+        }
+
+        Ok(root.push(Material {
+            alpha_cutoff: (info.alpha_mode == GltfAlphaMode::Mask).then_some(AlphaCutoff(0.5)),
+            alpha_mode: Checked::Valid(info.alpha_mode),
+            double_sided: info.double_sided,
+            pbr_metallic_roughness: PbrMetallicRoughness {
+                base_color_texture: diffuse.map(|index| TextureInfo {
+                    index,
+                    tex_coord: 0,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                }),
+                metallic_factor: StrengthFactor(0.0),
+                ..Default::default()
+            },
+            normal_texture: normal.map(|index| NormalTexture {
+                index,
+                scale: 1.0,
+                tex_coord: 0,
+                extensions: Default::default(),
+                extras: Default::default(),
+            }),
+            extras: Some(serde_json::value::to_raw_value(&extras)?),
+            ..Default::default()
+        }))
+    }
+
+    /// Returns the texture for `name`, converting it on first use. Textures that cannot be
+    /// converted are reported once and left out of the material.
+    fn texture(
+        &mut self,
+        root: &mut GltfRoot,
+        resolver: &FileResolver,
+        name: &str,
+    ) -> Option<TextureIndex> {
+        if name.is_empty() {
+            return None;
+        }
+
+        if let Some(texture) = self.textures.get(name) {
+            return *texture;
+        }
+
+        let texture = match convert(resolver, name) {
+            Ok((file_name, data)) => {
+                let source = root.push(Image {
+                    buffer_view: None,
+                    mime_type: None,
+                    name: Some(name.into()),
+                    uri: None,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                });
+                self.images.push(PngImage {
+                    image: source,
+                    file_name,
+                    data,
+                });
+                Some(root.push(Texture {
+                    name: Some(name.into()),
+                    sampler: None,
+                    source,
+                    extensions: Default::default(),
+                    extras: Default::default(),
+                }))
+            }
+            Err(error) => {
+                eprintln!("{name}: {error:#}");
+                None
+            }
+        };
+
+        self.textures.insert(name.into(), texture);
+        texture
+    }
+}
+
+fn convert(resolver: &FileResolver, name: &str) -> anyhow::Result<(String, Vec<u8>)> {
+    let data = resolver.read(name)?;
+    let image = DdsTexture::read(&mut Cursor::new(data))?.decode(0, 0)?;
+
+    let file_name = Path::new(name)
+        .with_extension("png")
+        .file_name()
+        .context("invalid texture name")?
+        .to_string_lossy()
+        .into_owned();
+    let mut png = Cursor::new(vec![]);
+    image.write_to(&mut png, image::ImageFormat::Png)?;
+
+    Ok((file_name, png.into_inner()))
+}
@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use jc2_file_formats::archive::GameArchive;
+use jc2_hashing::{HashList, HashString};
+
+/// Finds the files a model references, looking next to the input file first and then in the
+/// game's archives.
+pub struct FileResolver {
+    directory: PathBuf,
+    files: HashMap<HashString, PathBuf>,
+    archives: Vec<GameArchive>,
+    names: HashList,
+}
+
+impl FileResolver {
+    /// Creates a resolver for files in `directory` and every `.tab` archive found in
+    /// `archives`, searching directories recursively.
+    pub fn new(directory: impl Into<PathBuf>, archives: &[PathBuf]) -> anyhow::Result<Self> {
+        let directory = directory.into();
+
+        // Files referenced by hash can only be found on disk if every name is hashed up front
+        let mut files = HashMap::new();
+        let listing = if directory.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            &directory
+        };
+        if let Ok(entries) = std::fs::read_dir(listing) {
+            for entry in entries {
+                let path = entry?.path();
+                if let Some(hash) = HashString::from_path(&path) {
+                    files.insert(hash, path);
+                }
+            }
+        }
+
+        let archives = GameArchive::find(archives)?
+            .into_iter()
+            .map(|(path, _)| {
+                GameArchive::open(&path).with_context(|| format!("failed to read {path:?}"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            directory,
+            files,
+            archives,
+            names: HashList::jc2(),
+        })
+    }
+
+    /// Reads a file by its path, which may be relative to the input file or only name a file in
+    /// the archives.
+    pub fn read(&self, name: &str) -> anyhow::Result<Vec<u8>> {
+        let path = self.directory.join(name);
+        if path.is_file() {
+            return Ok(std::fs::read(path)?);
+        }
+
+        let hash = HashString::from_path(&name).with_context(|| format!("invalid path {name}"))?;
+        self.read_hash(hash)
+            .with_context(|| format!("failed to find {name}"))
+    }
+
+    /// Reads a file that is only known by the hash of its name.
+    pub fn read_hash(&self, hash: HashString) -> anyhow::Result<Vec<u8>> {
+        if let Some(path) = self.files.get(&hash) {
+            return Ok(std::fs::read(path)?);
+        }
+
+        for archive in &self.archives {
+            if let Some(entry) = archive.table.entries.get(&hash) {
+                let mut buffer = vec![];
+                archive
+                    .open_data()
+                    .and_then(|mut reader| GameArchive::read_entry(&mut reader, entry, &mut buffer))
+                    .with_context(|| format!("failed to read {:?}", archive.path))?;
+                return Ok(buffer);
+            }
+        }
+
+        anyhow::bail!("no file with hash 0x{:08X}", hash.hash())
+    }
+
+    /// Returns the file name for a hash if the game is known to use it.
+    pub fn name(&self, hash: HashString) -> Option<String> {
+        self.names
+            .find_path(hash)
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+}
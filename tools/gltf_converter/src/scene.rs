@@ -0,0 +1,271 @@
+use std::{collections::HashMap, f32::consts::TAU, io::Cursor, path::Path};
+
+use anyhow::bail;
+use jc2_file_formats::{
+    math::{Mat3x4, Quat, Vec3},
+    model_collection::{ModelCollection, ModelInstanceFlags},
+    render_block_model::RenderBlockModel,
+};
+use jc2_hashing::HashString;
+use serde_json::json;
+
+use crate::export::{GltfExporter, GltfModel, Node, NodeIndex};
+
+/// The distances the game switches between levels at, before scaling by a `.lod` file's factor.
+///
+/// These, and the rules `parse_lod` follows, are the ones noted by the `.lod` loader in
+/// `godot_jc2` (`resource_loader/formats/lod.rs`), which draws levels the same way.
+const LOD_DISTANCES: [f32; 6] = [0.0, 10.0, 25.0, 50.0, 100.0, 500.0];
+
+/// The model shown by each visible level, along with the distances it is shown between.
+type LodModels = Vec<(GltfModel, f32, f32)>;
+
+/// A run of `.lod` levels that all show the same model.
+struct LodLevel {
+    model: String,
+    begin: f32,
+    end: f32,
+}
+
+/// Parses a `.lod` file, which lists a model for each of the five levels followed by a factor
+/// the distances are scaled by. A model ending in `-` hides the object at that level.
+fn parse_lod(data: &[u8]) -> anyhow::Result<Vec<LodLevel>> {
+    let text = String::from_utf8_lossy(data);
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    if lines.len() < 6 {
+        bail!("expected at least 6 lines, found {}", lines.len());
+    }
+
+    // A factor of -1 means the game only scales by the field of view, which isn't known here,
+    // so it's treated as 1 as the Godot loader does
+    let factor = lines[5].parse::<f32>().unwrap_or(1.0).abs();
+
+    let mut levels: Vec<LodLevel> = Vec::with_capacity(5);
+    for (level, &model) in lines[..5].iter().enumerate() {
+        if let Some(previous) = levels.last_mut() {
+            if previous.model == model && previous.end == LOD_DISTANCES[level] * factor {
+                previous.end = LOD_DISTANCES[level + 1] * factor;
+                continue;
+            }
+        }
+        levels.push(LodLevel {
+            model: model.into(),
+            begin: LOD_DISTANCES[level] * factor,
+            end: LOD_DISTANCES[level + 1] * factor,
+        });
+    }
+    levels.retain(|level| !level.model.ends_with('-'));
+
+    Ok(levels)
+}
+
+/// Builds a single glTF scene out of model collections and `.lod` files, loading each model
+/// once and instancing its meshes wherever it is placed.
+pub struct SceneExporter {
+    exporter: GltfExporter,
+    models: HashMap<String, Option<GltfModel>>,
+    lods: HashMap<HashString, Option<LodModels>>,
+}
+
+impl SceneExporter {
+    pub fn new(exporter: GltfExporter) -> Self {
+        Self {
+            exporter,
+            models: HashMap::new(),
+            lods: HashMap::new(),
+        }
+    }
+
+    /// Returns the meshes for a model, loading it on first use. Models that cannot be loaded
+    /// are reported once and left out of the scene.
+    fn model(&mut self, name: &str) -> Option<GltfModel> {
+        if let Some(model) = self.models.get(name) {
+            return model.clone();
+        }
+
+        let model = self
+            .exporter
+            .resolver()
+            .read(name)
+            .and_then(|data| Ok(RenderBlockModel::read(&mut Cursor::new(data))?))
+            .and_then(|rbm| self.exporter.add_model(name, &rbm));
+        let model = match model {
+            Ok(model) => Some(model),
+            Err(error) => {
+                eprintln!("{name}: {error:#}");
+                None
+            }
+        };
+
+        self.models.insert(name.into(), model.clone());
+        model
+    }
+
+    fn lod_levels(&mut self, name: &str, data: &[u8]) -> LodModels {
+        match parse_lod(data) {
+            Ok(levels) => levels
+                .into_iter()
+                .filter_map(|level| {
+                    self.model(&level.model)
+                        .map(|model| (model, level.begin, level.end))
+                })
+                .collect(),
+            Err(error) => {
+                eprintln!("{name}: {error:#}");
+                vec![]
+            }
+        }
+    }
+
+    /// Creates a node for every visible level, with the first level linking to the rest through
+    /// `MSFT_lod`. The distance range of each level is kept in its extras.
+    fn add_lod(
+        &mut self,
+        name: String,
+        levels: &[(GltfModel, f32, f32)],
+        matrix: Option<[f32; 16]>,
+    ) -> anyhow::Result<Option<NodeIndex>> {
+        let Some(((model, begin, end), rest)) = levels.split_first() else {
+            return Ok(None);
+        };
+
+        let ids = rest
+            .iter()
+            .enumerate()
+            .map(|(level, (model, begin, end))| {
+                self.exporter.add_instance(
+                    model,
+                    Some(format!("{name}_lod{}", level + 1)),
+                    None,
+                    Some(json!({ "lod_range": [begin, end] })),
+                )
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let node = self.exporter.add_instance(
+            model,
+            Some(name),
+            matrix,
+            Some(json!({ "lod_range": [begin, end] })),
+        )?;
+        if !ids.is_empty() {
+            let ids: Vec<usize> = ids.iter().map(|id| id.value()).collect();
+            self.exporter
+                .set_extension(node, "MSFT_lod", json!({ "ids": ids }));
+        }
+
+        Ok(Some(node))
+    }
+
+    /// Adds a `.lod` file at the origin of the scene.
+    pub fn add_lod_file(&mut self, name: &str, data: &[u8]) -> anyhow::Result<Option<NodeIndex>> {
+        let levels = self.lod_levels(name, data);
+        self.add_lod(name.into(), &levels, None)
+    }
+
+    /// Adds every model and vegetation instance in a collection, returning the node holding
+    /// them all.
+    pub fn add_collection(
+        &mut self,
+        name: &str,
+        collection: &ModelCollection,
+    ) -> anyhow::Result<NodeIndex> {
+        let mut children = vec![];
+
+        for (index, instance) in collection.instances().enumerate() {
+            let Some(path) = collection.models.get(usize::from(instance.model_index)) else {
+                bail!(
+                    "instance {index} uses missing model {}",
+                    instance.model_index
+                );
+            };
+            let Some(model) = self.model(path.as_ref()) else {
+                continue;
+            };
+
+            let mut extras = json!({});
+            if let Some((center, distance)) = instance.lod {
+                extras["lod"] = json!({
+                    "center": <[f32; 3]>::from(*center),
+                    "distance": distance,
+                });
+            }
+            if let Some(flags) = instance.flags {
+                extras["shadow"] = json!(flags.contains(ModelInstanceFlags::SHADOW));
+                extras["clip"] = json!(flags.contains(ModelInstanceFlags::CLIP));
+            }
+
+            children.push(self.exporter.add_instance(
+                &model,
+                Some(format!("{}_{index}", model.name())),
+                Some((*instance.transform).into()),
+                Some(extras),
+            )?);
+        }
+
+        for (index, instance) in collection.vegetation_instances.iter().enumerate() {
+            let hash = instance.model_hash;
+            let Some(levels) = self.vegetation_levels(hash) else {
+                continue;
+            };
+
+            // Vegetation is only ever rotated around the up axis, by a fraction of a full turn
+            let yaw = f32::from(instance.yaw) / 256.0 * TAU;
+            let rotation = Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), yaw);
+            let transform = Mat3x4::from_rotation_translation(rotation, instance.position);
+
+            let name = format!("{}_{index}", self.lod_name(hash));
+            if let Some(node) = self.add_lod(name, &levels, Some(transform.to_mat4().into()))? {
+                children.push(node);
+            }
+        }
+
+        self.exporter.add_node(
+            Node {
+                mesh: Default::default(),
+                camera: Default::default(),
+                children: Some(children),
+                extensions: Default::default(),
+                extras: Default::default(),
+                matrix: Default::default(),
+                name: Some(name.into()),
+                rotation: Default::default(),
+                scale: Default::default(),
+                translation: Default::default(),
+                skin: Default::default(),
+                weights: Default::default(),
+            },
+            None,
+        )
+    }
+
+    /// Returns the levels of a vegetation `.lod`, loading it on first use.
+    fn vegetation_levels(&mut self, hash: HashString) -> Option<LodModels> {
+        if let Some(levels) = self.lods.get(&hash) {
+            return levels.clone();
+        }
+
+        let name = self.lod_name(hash);
+        let levels = match self.exporter.resolver().read_hash(hash) {
+            Ok(data) => Some(self.lod_levels(&name, &data)),
+            Err(error) => {
+                eprintln!("{name}: {error:#}");
+                None
+            }
+        };
+
+        self.lods.insert(hash, levels.clone());
+        levels
+    }
+
+    fn lod_name(&self, hash: HashString) -> String {
+        self.exporter
+            .resolver()
+            .name(hash)
+            .unwrap_or_else(|| format!("{:08x}.lod", hash.hash()))
+    }
+
+    pub fn write(self, path: &Path, nodes: Vec<NodeIndex>) -> anyhow::Result<()> {
+        self.exporter.write(path, nodes)
+    }
+}
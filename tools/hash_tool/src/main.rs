@@ -1,11 +1,19 @@
 use std::{
     collections::{HashMap, HashSet},
     fs,
+    path::{Path, PathBuf},
 };
 
 use anyhow::bail;
 use clap::{Parser, Subcommand};
-use jc2_hashing::HashString;
+use jc2_file_formats::archive::{GameArchive, read_dictionaries};
+use jc2_hashing::{HashEntry, HashList, HashString};
+
+mod reverse;
+use reverse::{Patterns, Resolver};
+
+mod scrape;
+use scrape::Scraper;
 
 #[derive(Parser)]
 struct Args {
@@ -20,60 +28,381 @@ enum Commands {
         source: std::path::PathBuf,
         target: std::path::PathBuf,
     },
+    #[command(about = "Recovers the names of unknown hashes in archives")]
+    Reverse {
+        #[arg(help = "Archives (.tab) or directories containing them")]
+        archives: Vec<PathBuf>,
+        #[arg(long, help = "Text or binary dictionaries of known names")]
+        dictionary: Vec<PathBuf>,
+        #[arg(long, help = "Newline separated words to combine into names")]
+        words: Vec<PathBuf>,
+        #[arg(
+            long,
+            default_value_t = 1,
+            value_parser = clap::value_parser!(u8).range(1..=3),
+            help = "Maximum number of words joined into a name"
+        )]
+        depth: u8,
+        #[arg(
+            long,
+            default_value_t = 2,
+            value_parser = clap::value_parser!(u8).range(0..=4),
+            help = "Maximum number of trailing digits renumbered in known names"
+        )]
+        width: u8,
+        #[arg(long, help = "Include the bundled dictionary of known names")]
+        jc2: bool,
+        #[arg(long, help = "Write newly resolved names to a text dictionary")]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Adds the names of files referenced by archive contents to a dictionary")]
+    Scan {
+        #[arg(help = "Archives (.tab) or directories containing them")]
+        archives: Vec<PathBuf>,
+        #[arg(long, help = "Dictionary to add names to, created if it does not exist")]
+        output: PathBuf,
+        #[arg(long, help = "Write a binary dictionary rather than a text one")]
+        binary: bool,
+        #[arg(long, help = "Text or binary dictionaries used to detect formats by name")]
+        dictionary: Vec<PathBuf>,
+        #[arg(long, help = "Include the bundled dictionary of known names")]
+        jc2: bool,
+    },
+    #[command(about = "Hashes strings, or the file names of paths")]
+    Hash {
+        values: Vec<String>,
+        #[arg(long, help = "Hash values as-is, rather than by their lowercased file name")]
+        string: bool,
+    },
+    #[command(about = "Looks up the names of hashes in dictionaries")]
+    Lookup {
+        #[arg(help = "Hashes, in hexadecimal with a 0x prefix or decimal")]
+        hashes: Vec<String>,
+        #[arg(long, help = "Text or binary dictionaries of known names")]
+        dictionary: Vec<PathBuf>,
+        #[arg(long, help = "Include the bundled dictionary of known names")]
+        jc2: bool,
+    },
+    #[command(
+        about = "Reports how many entries of each archive are named by its .filelist, or the bundled one when it has none"
+    )]
+    Coverage {
+        #[arg(help = "Archives (.tab) or directories containing them")]
+        archives: Vec<PathBuf>,
+        #[arg(long, help = "Text or binary dictionaries naming entries missing from the .filelist")]
+        dictionary: Vec<PathBuf>,
+        #[arg(long, help = "List the hashes of unnamed entries")]
+        missing: bool,
+    },
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+fn prune(source: &Path, target: &Path) -> anyhow::Result<()> {
+    if !source.is_file() {
+        bail!("{source:?} is not a file");
+    }
 
-    match args.command {
-        Commands::Prune { source, target } => {
-            if !source.is_file() {
-                bail!("{source:?} is not a file");
-            }
+    if !target.is_file() {
+        bail!("{target:?} is not a file");
+    }
 
-            if !target.is_file() {
-                bail!("{target:?} is not a file");
-            }
+    if source == target {
+        bail!("source and target must not be the same");
+    }
 
-            if source == target {
-                bail!("source and target must not be the same");
-            }
+    let source_set: HashSet<HashString> = fs::read_to_string(source)?
+        .lines()
+        .map(HashString::from)
+        .collect();
 
-            let source_set: HashSet<HashString> = fs::read_to_string(source)?
-                .lines()
-                .map(HashString::from)
-                .collect();
-
-            let (target_set, mut target_map): (HashSet<HashString>, HashMap<HashString, String>) = {
-                let content = fs::read_to_string(&target)?;
-                let hashes: Vec<HashString> = content.lines().map(HashString::from).collect();
-
-                (
-                    hashes.iter().cloned().collect(),
-                    hashes
-                        .into_iter()
-                        .zip(content.lines().map(str::to_string))
-                        .collect(),
-                )
-            };
-
-            let mut removed = 0usize;
-            for conflict in source_set.intersection(&target_set) {
-                target_map.remove(conflict);
-                removed += 1;
-            }
-            println!("Removed {removed} from {target:?}");
+    let (target_set, mut target_map): (HashSet<HashString>, HashMap<HashString, String>) = {
+        let content = fs::read_to_string(target)?;
+        let hashes: Vec<HashString> = content.lines().map(HashString::from).collect();
+
+        (
+            hashes.iter().cloned().collect(),
+            hashes
+                .into_iter()
+                .zip(content.lines().map(str::to_string))
+                .collect(),
+        )
+    };
+
+    let mut removed = 0usize;
+    for conflict in source_set.intersection(&target_set) {
+        target_map.remove(conflict);
+        removed += 1;
+    }
+    println!("Removed {removed} from {target:?}");
+
+    fs::rename(target, target.with_extension("old"))?;
+
+    fs::write(
+        target,
+        target_map
+            .values()
+            .fold(String::new(), |result, value| format!("{result}\n{value}")),
+    )?;
+    Ok(())
+}
+
+fn reverse(
+    archives: &[PathBuf],
+    dictionary: &[PathBuf],
+    words: &[PathBuf],
+    depth: u8,
+    width: u8,
+    jc2: bool,
+    output: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let archives = open_archives(archives)?;
+    let mut known = known_names(dictionary, jc2)?;
+    for archive in &archives {
+        known.merge(archive.file_list()?);
+    }
+
+    let mut patterns = Patterns::default();
+    for entry in known.values() {
+        patterns.learn(Path::new(&entry.to_string()));
+    }
+    for path in words {
+        for word in fs::read_to_string(path)?.lines() {
+            patterns.learn_words(word);
+        }
+    }
+
+    let mut resolver = Resolver::new(
+        archives
+            .iter()
+            .flat_map(hashes)
+            .filter(|hash| !known.contains(*hash)),
+    );
+
+    // Names referenced by files are the most reliable, so are tried first
+    let mut scraper = Scraper::default();
+    scraper.scrape_archives(&archives, &known)?;
+    for name in &scraper.names {
+        let path = Path::new(name);
+        patterns.learn(path);
+        match path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            Some(_) => resolver.try_path(path),
+            None => resolver.try_name(&name.to_lowercase(), &patterns),
+        };
+    }
+
+    resolver.guess(&patterns, depth.into(), width.into());
+
+    for archive in &archives {
+        let resolved = hashes(archive)
+            .into_iter()
+            .filter_map(|hash| resolver.resolved.find(hash).map(|entry| (hash, entry)))
+            .collect::<Vec<_>>();
+
+        println!("{:?}: resolved {}", archive.path, resolved.len());
+        for (hash, entry) in resolved {
+            println!("  0x{:08X} {entry}", hash.hash());
+        }
+    }
+
+    if let Some(output) = output {
+        resolver.resolved.write_text(fs::File::create(output)?)?;
+    }
+    Ok(())
+}
+
+fn scan(
+    archives: &[PathBuf],
+    output: &Path,
+    binary: bool,
+    dictionary: &[PathBuf],
+    jc2: bool,
+) -> anyhow::Result<()> {
+    let archives = open_archives(archives)?;
+    let mut known = known_names(dictionary, jc2)?;
+    for archive in &archives {
+        known.merge(archive.file_list()?);
+    }
 
-            fs::rename(&target, target.with_extension("old"))?;
+    let mut result = if output.is_file() {
+        known_names(&[output.into()], false)?
+    } else {
+        HashList::new()
+    };
+    known.merge(result.clone());
 
-            fs::write(
-                target,
-                target_map
-                    .values()
-                    .fold(String::new(), |result, value| format!("{result}\n{value}")),
-            )?;
+    let mut scraper = Scraper::default();
+    scraper.scrape_archives(&archives, &known)?;
+
+    let previous = result.len();
+    for name in &scraper.names {
+        if let Err(collision) = result.insert_unique(HashEntry::Path(name.into())) {
+            eprintln!("{collision}");
+        }
+    }
+    println!(
+        "scanned {} names, {} new",
+        scraper.names.len(),
+        result.len() - previous
+    );
+
+    for archive in &archives {
+        let names = archive.file_list()?;
+        let found = hashes(archive)
+            .iter()
+            .filter(|hash| !names.contains(**hash) && result.contains(**hash))
+            .count();
+        println!(
+            "{:?}: {found} entries named by the dictionary but not the .filelist",
+            archive.path
+        );
+    }
+
+    let mut writer = std::io::BufWriter::new(fs::File::create(output)?);
+    if binary {
+        result.write_binary(&mut writer)?;
+    } else {
+        result.write_text(&mut writer)?;
+    }
+    Ok(())
+}
+
+fn hash(values: &[String], string: bool) {
+    for value in values {
+        let hash = if string {
+            Some(HashString::from_str(value))
+        } else {
+            HashString::from_path(&value.replace('\\', "/"))
+        };
+
+        match hash {
+            Some(hash) => println!("0x{:08X} {value}", hash.hash()),
+            None => eprintln!("{value:?} has no file name"),
         }
     }
+}
+
+fn lookup(hashes: &[String], dictionary: &[PathBuf], jc2: bool) -> anyhow::Result<()> {
+    let known = known_names(dictionary, jc2)?;
+    for text in hashes {
+        let Some(hash) = parse_hash(text) else {
+            bail!("{text:?} is not a valid hash");
+        };
+
+        match known.find(hash) {
+            Some(entry) => println!("0x{:08X} {entry}", hash.hash()),
+            None => println!("0x{:08X} unknown", hash.hash()),
+        }
+    }
+    Ok(())
+}
+
+fn coverage(archives: &[PathBuf], dictionary: &[PathBuf], missing: bool) -> anyhow::Result<()> {
+    let known = known_names(dictionary, false)?;
+    let (mut named, mut total) = (0usize, 0usize);
+    for (path, _) in GameArchive::find(archives)? {
+        let archive = GameArchive::open(&path)?;
+        let names = archive.file_list_or_bundled()?;
+        let (found, unnamed): (Vec<_>, Vec<_>) = hashes(&archive)
+            .into_iter()
+            .partition(|hash| names.contains(*hash) || known.contains(*hash));
+
+        let count = found.len() + unnamed.len();
+        println!(
+            "{path:?}: {} of {count} named ({:.1}%)",
+            found.len(),
+            percentage(found.len(), count)
+        );
+        if missing {
+            for hash in &unnamed {
+                println!("  0x{:08X}", hash.hash());
+            }
+        }
 
+        named += found.len();
+        total += count;
+    }
+    println!(
+        "total: {named} of {total} named ({:.1}%)",
+        percentage(named, total)
+    );
     Ok(())
 }
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    match args.command {
+        Commands::Prune { source, target } => prune(&source, &target),
+        Commands::Reverse {
+            archives,
+            dictionary,
+            words,
+            depth,
+            width,
+            jc2,
+            output,
+        } => reverse(&archives, &dictionary, &words, depth, width, jc2, output),
+        Commands::Scan {
+            archives,
+            output,
+            binary,
+            dictionary,
+            jc2,
+        } => scan(&archives, &output, binary, &dictionary, jc2),
+        Commands::Hash { values, string } => {
+            hash(&values, string);
+            Ok(())
+        }
+        Commands::Lookup {
+            hashes,
+            dictionary,
+            jc2,
+        } => lookup(&hashes, &dictionary, jc2),
+        Commands::Coverage {
+            archives,
+            dictionary,
+            missing,
+        } => coverage(&archives, &dictionary, missing),
+    }
+}
+
+/// Reads text and binary dictionaries, optionally including the bundled one.
+fn known_names(paths: &[PathBuf], jc2: bool) -> anyhow::Result<HashList> {
+    let mut result = if jc2 { HashList::jc2() } else { HashList::new() };
+    for collision in read_dictionaries(&mut result, paths)? {
+        eprintln!("{collision}");
+    }
+    Ok(result)
+}
+
+/// Opens every archive found in the given paths.
+fn open_archives(paths: &[PathBuf]) -> anyhow::Result<Vec<GameArchive>> {
+    let mut result = vec![];
+    for (path, _) in GameArchive::find(paths)? {
+        result.push(GameArchive::open(&path)?);
+    }
+    Ok(result)
+}
+
+/// Returns every hash in an archive, in a stable order.
+fn hashes(archive: &GameArchive) -> Vec<HashString> {
+    let mut hashes = archive.table.entries.keys().copied().collect::<Vec<_>>();
+    hashes.sort_unstable();
+    hashes
+}
+
+/// Parses a hash in hexadecimal with a `0x` prefix, or in decimal.
+fn parse_hash(text: &str) -> Option<HashString> {
+    let hash = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => text.parse().ok()?,
+    };
+    Some(HashString::new(hash))
+}
+
+fn percentage(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use jc2_hashing::{HashEntry, HashList, HashString};
+
+/// Names and naming conventions learned from known files, used to guess unknown ones.
+#[derive(Default)]
+pub struct Patterns {
+    /// File names without their extension, such as `pc0`.
+    pub stems: BTreeSet<String>,
+    /// The parts of stems split on separators, such as `pc` and `0` in `pc_0`.
+    pub words: BTreeSet<String>,
+    /// Extensions without a leading dot, such as `rbm`.
+    pub extensions: BTreeSet<String>,
+    /// The directory files with a given extension are most often found in.
+    directories: HashMap<String, BTreeMap<PathBuf, usize>>,
+}
+
+impl Patterns {
+    /// Learns the stem, words, extension and directory of a path.
+    pub fn learn(&mut self, path: &Path) {
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy().to_lowercase()) else {
+            return;
+        };
+
+        let (stem, extension) = match name.rsplit_once('.') {
+            Some((stem, extension)) => (stem, Some(extension)),
+            None => (name.as_str(), None),
+        };
+
+        if !stem.is_empty() {
+            self.stems.insert(stem.into());
+            self.learn_words(stem);
+        }
+
+        if let Some(extension) = extension.filter(|extension| !extension.is_empty()) {
+            self.extensions.insert(extension.into());
+            if let Some(directory) = path.parent().filter(|path| !path.as_os_str().is_empty()) {
+                *self
+                    .directories
+                    .entry(extension.into())
+                    .or_default()
+                    .entry(directory.into())
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    pub fn learn_words(&mut self, text: &str) {
+        for word in text.split(['_', '-', ' ', '.', '/']) {
+            if !word.is_empty() {
+                self.words.insert(word.to_lowercase());
+            }
+        }
+    }
+
+    /// Returns the directory a file with the given name most likely belongs in.
+    pub fn directory(&self, name: &str) -> Option<&Path> {
+        let (_, extension) = name.rsplit_once('.')?;
+        self.directories
+            .get(extension)?
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(directory, _)| directory.as_path())
+    }
+}
+
+/// Matches candidate names against a set of unknown hashes.
+pub struct Resolver {
+    unknown: HashSet<HashString>,
+    pub resolved: HashList,
+}
+
+impl Resolver {
+    pub fn new(unknown: impl IntoIterator<Item = HashString>) -> Self {
+        Self {
+            unknown: unknown.into_iter().collect(),
+            resolved: HashList::new(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.unknown.is_empty()
+    }
+
+    /// Tries a path, resolving its hash if it is unknown. Only the file name is hashed.
+    pub fn try_path(&mut self, path: &Path) -> bool {
+        let Some(hash) = HashString::from_path(&path) else {
+            return false;
+        };
+
+        if self.unknown.remove(&hash) {
+            self.resolved.insert(HashEntry::Path(path.into()));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Tries a file name, placing it in the directory its extension is most often found in.
+    pub fn try_name(&mut self, name: &str, patterns: &Patterns) -> bool {
+        if !self.unknown.contains(&HashString::from_str(name)) {
+            return false;
+        }
+
+        let path = match patterns.directory(name) {
+            Some(directory) => directory.join(name),
+            None => PathBuf::from(name),
+        };
+        self.try_path(&path)
+    }
+
+    /// Tries every candidate generated from the patterns, joining up to `depth` words and
+    /// renumbering stems ending in up to `width` digits.
+    ///
+    /// Both grow the number of candidates exponentially, as every word is tried at each depth
+    /// and every number of a width, each with every known extension.
+    pub fn guess(&mut self, patterns: &Patterns, depth: usize, width: usize) {
+        // Known stems with every known extension
+        for stem in &patterns.stems {
+            self.try_stem(stem, patterns);
+        }
+
+        // Known stems ending in a number, with every other number of the same width. Many stems
+        // share a prefix, such as `pc0` and `pc1`, so each is only renumbered once, skipping the
+        // stems already tried above
+        let prefixes = patterns
+            .stems
+            .iter()
+            .filter_map(|stem| {
+                let prefix = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+                let digits = stem.len() - prefix.len();
+                (1..=width).contains(&digits).then_some((prefix, digits))
+            })
+            .collect::<BTreeSet<_>>();
+        for (prefix, digits) in prefixes {
+            for number in 0..10usize.pow(digits as u32) {
+                let stem = format!("{prefix}{number:0digits$}");
+                if !patterns.stems.contains(&stem) {
+                    self.try_stem(&stem, patterns);
+                }
+            }
+        }
+
+        // Words joined by underscores
+        let words = patterns.words.iter().map(String::as_str).collect::<Vec<_>>();
+        self.try_words(&mut String::new(), &words, depth, patterns);
+    }
+
+    fn try_words(&mut self, stem: &mut String, words: &[&str], depth: usize, patterns: &Patterns) {
+        if depth == 0 {
+            return;
+        }
+
+        for word in words {
+            if self.is_done() {
+                return;
+            }
+
+            let length = stem.len();
+            if !stem.is_empty() {
+                stem.push('_');
+            }
+            stem.push_str(word);
+            if !patterns.stems.contains(stem.as_str()) {
+                self.try_stem(stem, patterns);
+            }
+            self.try_words(stem, words, depth - 1, patterns);
+            stem.truncate(length);
+        }
+    }
+
+    fn try_stem(&mut self, stem: &str, patterns: &Patterns) {
+        for extension in &patterns.extensions {
+            if self.is_done() {
+                return;
+            }
+            self.try_name(&format!("{stem}.{extension}"), patterns);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::{collections::BTreeSet, io::Cursor, path::Path};
+
+use jc2_file_formats::{
+    BinRead,
+    archive::{GameArchive, StreamArchive},
+    model_collection::ModelCollection,
+    property_container::{PropertyBlockFile, PropertyContainer, PropertyFile, PropertyValue},
+    render_block_model::RenderBlockModel,
+};
+use jc2_hashing::HashList;
+
+/// Extensions of property files, which have no magic to detect them by.
+const PROPERTY_FILE_EXTENSIONS: [&str; 3] = ["bin", "blo", "epe"];
+
+/// Collects the names of files referenced by the formats we can parse.
+#[derive(Default)]
+pub struct Scraper {
+    pub names: BTreeSet<String>,
+}
+
+impl Scraper {
+    /// Scrapes every entry of the archives, naming entries with the known names if possible.
+    pub fn scrape_archives(
+        &mut self,
+        archives: &[GameArchive],
+        known: &HashList,
+    ) -> anyhow::Result<()> {
+        for archive in archives {
+            archive.for_each_entry(|hash, data| {
+                let name = known.find(hash).map(ToString::to_string);
+                self.scrape(name.as_deref(), data);
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Scrapes a file, using its name to detect formats without a magic when it is known.
+    ///
+    /// Data that fails to parse is skipped, as scraping is best effort.
+    pub fn scrape(&mut self, name: Option<&str>, data: &[u8]) {
+        let extension = name
+            .and_then(|name| Path::new(name).extension())
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        let extension = extension.as_deref();
+
+        if data.starts_with(b"PCBB") {
+            if let Ok(file) = PropertyBlockFile::read_le(&mut Cursor::new(data)) {
+                self.scrape_containers(file.into());
+            }
+        } else if data.get(4..9) == Some(b"RBMDL") {
+            if let Ok(model) = RenderBlockModel::read(&mut Cursor::new(data)) {
+                for block in model.blocks.iter() {
+                    for texture in block.texture_paths() {
+                        self.insert(texture);
+                    }
+                }
+            }
+        } else if data.starts_with(b"\x14\x03\x03\x83") || data.starts_with(b"\x83\x00\x00\x14") {
+            if let Ok(collection) = ModelCollection::read(&mut Cursor::new(data)) {
+                for model in &collection.models {
+                    self.insert(model);
+                }
+            }
+        } else if StreamArchive::is_stream_archive(Path::new(name.unwrap_or_default()), data) {
+            if let Ok(archive) = StreamArchive::read(&mut Cursor::new(data)) {
+                let mut entries = archive.entries.into_iter().collect::<Vec<_>>();
+                entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                for (name, data) in entries {
+                    self.insert(&name);
+                    self.scrape(Some(&name), &data);
+                }
+            }
+        } else if extension.is_some_and(|extension| PROPERTY_FILE_EXTENSIONS.contains(&extension))
+        {
+            if let Ok(file) = PropertyFile::read_le(&mut Cursor::new(data)) {
+                self.scrape_containers(file.into());
+            }
+        } else if extension == Some("lod") {
+            // The first five lines are models, with `-` marking a hidden level
+            for line in String::from_utf8_lossy(data).lines().take(5) {
+                if !line.trim().ends_with('-') {
+                    self.insert(line);
+                }
+            }
+        }
+    }
+
+    /// Inserts a name, normalising separators and ignoring anything that can't be a file name.
+    ///
+    /// Only names with an extension or a directory are kept, as most other strings are labels
+    /// or identifiers rather than references to files.
+    pub fn insert(&mut self, name: &str) {
+        let name = name.trim().replace('\\', "/");
+        if name.is_empty() || name.ends_with('/') || name.contains(['\0', '\n', '\r', '\t']) {
+            return;
+        }
+
+        let extension = Path::new(&name)
+            .extension()
+            .is_some_and(|extension| !extension.is_empty());
+        if extension || name.contains('/') {
+            self.names.insert(name);
+        }
+    }
+
+    fn scrape_containers(&mut self, containers: Vec<PropertyContainer>) {
+        fn visit(scraper: &mut Scraper, container: &PropertyContainer) {
+            for value in container.values() {
+                if let PropertyValue::String(value) = value {
+                    scraper.insert(value);
+                }
+            }
+            for container in container.containers() {
+                visit(scraper, container);
+            }
+        }
+
+        for container in &containers {
+            visit(self, container);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_path_like_names() {
+        let mut scraper = Scraper::default();
+        for name in [
+            "models\\jc_design_tools\\a.lod",
+            "b.dds",
+            "settings/hud",
+            "CDamageableObject",
+            "no extension.",
+            "directory/",
+            "",
+        ] {
+            scraper.insert(name);
+        }
+
+        let names = scraper.names.iter().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(names, ["b.dds", "models/jc_design_tools/a.lod", "settings/hud"]);
+    }
+}
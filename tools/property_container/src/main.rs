@@ -1,34 +1,272 @@
-use std::io::{Read, Seek};
+use std::{
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+};
 
-use clap::Parser;
+use anyhow::bail;
+use clap::{Parser, Subcommand, ValueEnum};
 use jc2_file_formats::{
-    BinRead,
-    property_container::{PropertyBlockFile, PropertyContainer, PropertyFile},
+    BinRead, BinWrite,
+    property_container::{
+        PropertyBlockFile, PropertyContainer, PropertyFile, PropertyFileKeys, PropertyPathDisplay,
+        text::{self, PropertyFileFormat},
+    },
 };
+use jc2_hashing::{HashEntryKind, HashList};
 
 #[derive(Parser)]
 struct Args {
-    #[arg()]
-    file: std::path::PathBuf,
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    #[command(about = "Prints the debug representation of a property file")]
+    Print { file: PathBuf },
+    #[command(about = "Converts a property file to text")]
+    Export {
+        file: PathBuf,
+        #[arg(short, long, value_enum, default_value_t = Format::Xml)]
+        format: Format,
+        #[arg(
+            short,
+            long,
+            help = "Defaults to the input with the format extension appended"
+        )]
+        output: Option<PathBuf>,
+        #[arg(long, help = "Newline separated names used to resolve hashes")]
+        hashes: Vec<PathBuf>,
+    },
+    #[command(about = "Converts text back to the kind of property file it was exported from")]
+    Import {
+        file: PathBuf,
+        #[arg(
+            short,
+            long,
+            help = "Defaults to the input with the format extension removed"
+        )]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Prints the differences between two property files")]
+    Diff {
+        from: PathBuf,
+        to: PathBuf,
+        #[arg(long, help = "Newline separated names used to resolve hashes")]
+        hashes: Vec<PathBuf>,
+    },
+    #[command(about = "Merges the changes made to a common base by two property files")]
+    Merge {
+        base: PathBuf,
+        ours: PathBuf,
+        theirs: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(
+            long,
+            help = "Writes a property block file (PCBB) instead of a property file"
+        )]
+        block: bool,
+        #[arg(long, help = "Newline separated names used to resolve hashes")]
+        hashes: Vec<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Xml,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Self::Json),
+            "xml" => Some(Self::Xml),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Xml => "xml",
+        }
+    }
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    if args.file.is_file() {
-        let file = std::fs::File::open(args.file.clone())?;
-        let mut reader = std::io::BufReader::new(file);
+    match args.command {
+        Commands::Print { file } => {
+            let (properties, ..) = read_properties(&file)?;
+            println!("{properties:#?}");
+        }
+        Commands::Export {
+            file,
+            format,
+            output,
+            hashes: hash_paths,
+        } => {
+            let (properties, names, file_format) = read_properties(&file)?;
+            let mut hashes = HashList::new();
+            read_hashes(&hash_paths, &mut hashes)?;
+
+            let (names, hashes) = (Some(&names), Some(&hashes));
+            let text = match format {
+                Format::Json => text::json::to_string(&properties, file_format, names, hashes)?,
+                Format::Xml => text::xml::to_string(&properties, file_format, names, hashes),
+            };
+
+            let output = output.unwrap_or_else(|| {
+                let mut output = file.into_os_string();
+                output.push(".");
+                output.push(format.extension());
+                output.into()
+            });
+            std::fs::write(output, text)?;
+        }
+        Commands::Import { file, output } => {
+            let Some(format) = Format::from_path(&file) else {
+                bail!("{file:?} is not a json or xml file");
+            };
 
-        let mut header = [0u8; 4];
-        reader.read_exact(&mut header)?;
-        reader.seek(std::io::SeekFrom::Start(0))?;
+            let mut names = HashList::new();
+            let text = std::fs::read_to_string(&file)?;
+            let (properties, file_format) = match format {
+                Format::Json => text::json::from_str(&text, Some(&mut names))?,
+                Format::Xml => text::xml::from_str(&text, Some(&mut names))?,
+            };
 
-        let properties: Vec<PropertyContainer> = match &header {
-            b"PCBB" => PropertyBlockFile::read_le(&mut reader)?.into(),
-            _ => PropertyFile::read_le(&mut reader)?.into(),
-        };
-        println!("{properties:#?}");
+            let output = output.unwrap_or_else(|| file.with_extension(""));
+            let block = file_format == PropertyFileFormat::BlockFile;
+            write_properties(&output, properties, block, &names)?;
+        }
+        Commands::Diff {
+            from,
+            to,
+            hashes: hash_paths,
+        } => {
+            let (from, mut hashes, _) = read_properties(&from)?;
+            let (to, names, _) = read_properties(&to)?;
+            hashes.extend(names);
+            read_hashes(&hash_paths, &mut hashes)?;
+
+            let empty = PropertyContainer::new();
+            for index in 0..from.len().max(to.len()) {
+                let from = from.get(index).unwrap_or(&empty);
+                let to = to.get(index).unwrap_or(&empty);
+                let diff = from.diff(to);
+                if !diff.is_empty() {
+                    println!("container {index}:");
+                    print!("{}", diff.display(Some(&hashes)));
+                }
+            }
+        }
+        Commands::Merge {
+            base,
+            ours,
+            theirs,
+            output,
+            block,
+            hashes: hash_paths,
+        } => {
+            let mut hashes = HashList::new();
+            let [base, ours, theirs] = [base, ours, theirs].map(|path| {
+                read_properties(&path).map(|(properties, names, _)| {
+                    hashes.extend(names);
+                    properties
+                })
+            });
+            let (base, ours, theirs) = (base?, ours?, theirs?);
+            let names = hashes.clone();
+            read_hashes(&hash_paths, &mut hashes)?;
+
+            let properties = merge_properties(&base, &ours, &theirs, &hashes);
+            write_properties(&output, properties, block, &names)?;
+        }
     }
 
     Ok(())
 }
+
+/// Merges each container by index, reporting any conflicts.
+fn merge_properties(
+    base: &[PropertyContainer],
+    ours: &[PropertyContainer],
+    theirs: &[PropertyContainer],
+    hashes: &HashList,
+) -> Vec<PropertyContainer> {
+    let empty = PropertyContainer::new();
+    (0..ours.len().max(theirs.len()))
+        .map(|index| {
+            let merge = PropertyContainer::merge(
+                base.get(index).unwrap_or(&empty),
+                ours.get(index).unwrap_or(&empty),
+                theirs.get(index).unwrap_or(&empty),
+            );
+            for conflict in &merge.conflicts {
+                eprintln!(
+                    "conflict in container {index}: {} (keeping ours)",
+                    PropertyPathDisplay(&conflict.path, Some(hashes))
+                );
+            }
+            merge.container
+        })
+        .collect()
+}
+
+/// Writes a property block file if `block` is set, otherwise a property file with `names` stored
+/// by name.
+fn write_properties(
+    path: &Path,
+    properties: Vec<PropertyContainer>,
+    block: bool,
+    names: &HashList,
+) -> anyhow::Result<()> {
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    if block {
+        PropertyBlockFile::try_from(properties)?.write_le(&mut writer)?;
+    } else {
+        PropertyFile::from_containers(properties, PropertyFileKeys::Named(names))
+            .write_le(&mut writer)?;
+    }
+    Ok(())
+}
+
+fn read_hashes(paths: &[PathBuf], hashes: &mut HashList) -> anyhow::Result<()> {
+    for path in paths {
+        let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        let (list, _) = HashList::read_text(reader, HashEntryKind::String)?;
+        hashes.merge(list);
+    }
+    Ok(())
+}
+
+/// Reads a property file, along with the names of any keys that were stored by name and the
+/// format it was read as.
+fn read_properties(
+    path: &Path,
+) -> anyhow::Result<(Vec<PropertyContainer>, HashList, PropertyFileFormat)> {
+    if !path.is_file() {
+        bail!("{path:?} is not a file");
+    }
+
+    let file = std::fs::File::open(path)?;
+    let mut reader = std::io::BufReader::new(file);
+
+    let mut header = [0u8; 4];
+    reader.read_exact(&mut header)?;
+    reader.seek(std::io::SeekFrom::Start(0))?;
+
+    let mut names = HashList::new();
+    if &header == b"PCBB" {
+        let properties = PropertyBlockFile::read_le(&mut reader)?.into();
+        Ok((properties, names, PropertyFileFormat::BlockFile))
+    } else {
+        let file = PropertyFile::read_le(&mut reader)?;
+        file.collect_names(&mut names);
+        Ok((file.into(), names, PropertyFileFormat::PropertyFile))
+    }
+}
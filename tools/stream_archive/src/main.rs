@@ -1,40 +1,332 @@
-use std::path::PathBuf;
+use std::{
+    io::{BufReader, Cursor},
+    path::{Component, Path, PathBuf},
+};
 
-use clap::Parser;
+use anyhow::{Context, bail};
+use clap::{Parser, Subcommand, ValueEnum};
 use jc2_file_formats::archive::StreamArchive;
 
+mod pattern;
+use pattern::matches_any;
+
 #[derive(Parser)]
 struct Args {
-    #[arg()]
-    file: PathBuf,
+    #[command(subcommand)]
+    command: Commands,
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+#[derive(Subcommand)]
+enum Commands {
+    #[command(about = "Lists the entries of an archive and their sizes")]
+    List {
+        archive: PathBuf,
+        #[arg(help = "Only list entries matching these patterns, such as *.rbm")]
+        patterns: Vec<String>,
+    },
+    #[command(about = "Extracts the entries of an archive to a directory")]
+    Extract {
+        archive: PathBuf,
+        #[arg(help = "Only extract entries matching these patterns, such as *.rbm")]
+        patterns: Vec<String>,
+        #[arg(
+            long,
+            short,
+            help = "Directory to extract to, defaulting to the archive without its extension"
+        )]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Creates an archive from the files in a directory")]
+    Create {
+        directory: PathBuf,
+        #[arg(
+            long,
+            short,
+            help = "Archive to write, defaulting to the directory with the chosen extension"
+        )]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = Extension::Ee, help = "Extension of the archive")]
+        extension: Extension,
+        #[arg(
+            long,
+            help = "Compress the archive, which is implied by extensions ending in z"
+        )]
+        compress: bool,
+    },
+    #[command(about = "Adds files to an archive in place")]
+    Add {
+        archive: PathBuf,
+        files: Vec<PathBuf>,
+        #[arg(long, help = "Replace entries that already exist")]
+        replace: bool,
+    },
+    #[command(about = "Replaces existing entries of an archive in place")]
+    Replace {
+        archive: PathBuf,
+        files: Vec<PathBuf>,
+    },
+    #[command(about = "Removes entries from an archive in place")]
+    Remove {
+        archive: PathBuf,
+        #[arg(
+            required = true,
+            help = "Patterns of the entries to remove, such as *.rbm"
+        )]
+        patterns: Vec<String>,
+    },
+    #[command(about = "Compares the entries of an archive with the files in a directory")]
+    Verify {
+        archive: PathBuf,
+        directory: PathBuf,
+    },
+}
 
-    if args.file.is_dir() {
-        let mut archive = StreamArchive::default();
-        for file in std::fs::read_dir(args.file.clone())? {
-            let file = file?;
-            if !file.path().is_file() {
-                continue;
-            }
-            let file_name = file.file_name();
-            archive.entries.insert(
-                file_name.to_string_lossy().into(),
-                std::fs::read(file.path())?,
-            );
+#[derive(Clone, Copy, ValueEnum)]
+enum Extension {
+    Bl,
+    Ee,
+    Fl,
+    Nl,
+}
+
+impl Extension {
+    const fn as_str(self, compressed: bool) -> &'static str {
+        match (self, compressed) {
+            (Self::Bl, false) => "bl",
+            (Self::Bl, true) => "blz",
+            (Self::Ee, false) => "ee",
+            (Self::Ee, true) => "eez",
+            (Self::Fl, false) => "fl",
+            (Self::Fl, true) => "flz",
+            (Self::Nl, false) => "nl",
+            (Self::Nl, true) => "nlz",
         }
-        archive.write(&mut std::fs::File::create(args.file.with_extension("ee"))?)?;
-    } else if args.file.is_file() {
-        let file = std::fs::File::open(args.file.clone())?;
-        let archive = StreamArchive::read(&mut std::io::BufReader::new(file))?;
-        let root = args.file.with_extension("");
-        std::fs::create_dir(&root)?;
-        for (name, data) in &archive.entries {
-            std::fs::write(root.clone().join(name), data)?;
+    }
+}
+
+/// Reads an archive, and whether it is compressed.
+fn read_archive(path: &Path) -> anyhow::Result<(StreamArchive, bool)> {
+    let mut reader = BufReader::new(
+        std::fs::File::open(path).with_context(|| format!("failed to open {path:?}"))?,
+    );
+    let compressed = StreamArchive::is_compressed(&mut reader)?;
+    let archive =
+        StreamArchive::read(&mut reader).with_context(|| format!("failed to read {path:?}"))?;
+    Ok((archive, compressed))
+}
+
+/// Writes an archive, only replacing the file once it has been written in full.
+///
+/// The archive is written to a file next to it, which is then renamed over the original, so a
+/// failed write leaves the original untouched.
+fn write_archive(path: &Path, archive: &StreamArchive, compressed: bool) -> anyhow::Result<()> {
+    let mut buffer = Cursor::new(vec![]);
+    if compressed {
+        archive.write_compressed(&mut buffer)?;
+    } else {
+        archive.write(&mut buffer)?;
+    }
+
+    let mut name = path.file_name().context("invalid path")?.to_owned();
+    name.push(".tmp");
+    let temporary = path.with_file_name(name);
+    let result = std::fs::write(&temporary, buffer.into_inner())
+        .and_then(|()| std::fs::rename(&temporary, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temporary);
+    }
+    result.with_context(|| format!("failed to write {path:?}"))
+}
+
+/// Returns the names of an archive's entries matching `patterns`, in order.
+fn entry_names<'a>(archive: &'a StreamArchive, patterns: &[String]) -> Vec<&'a String> {
+    let mut names: Vec<_> = archive
+        .entries
+        .keys()
+        .filter(|name| matches_any(patterns, name))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Converts an entry name to a relative path, dropping anything that would leave the
+/// output directory.
+fn entry_path(name: &str) -> PathBuf {
+    Path::new(name)
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect()
+}
+
+fn file_name(path: &Path) -> anyhow::Result<String> {
+    Ok(path
+        .file_name()
+        .with_context(|| format!("{path:?} is not a file"))?
+        .to_string_lossy()
+        .into_owned())
+}
+
+fn list(path: &Path, patterns: &[String]) -> anyhow::Result<()> {
+    let (archive, compressed) = read_archive(path)?;
+
+    let names = entry_names(&archive, patterns);
+    let mut total = 0;
+    for name in &names {
+        let size = archive.entries[*name].len();
+        total += size;
+        println!("{size:>12}  {name}");
+    }
+    println!(
+        "{} of {} entries, {total} bytes{}",
+        names.len(),
+        archive.entries.len(),
+        if compressed { ", compressed" } else { "" }
+    );
+
+    Ok(())
+}
+
+fn extract(path: &Path, patterns: &[String], output: Option<PathBuf>) -> anyhow::Result<()> {
+    let (archive, _) = read_archive(path)?;
+    let root = output.unwrap_or_else(|| path.with_extension(""));
+
+    let names = entry_names(&archive, patterns);
+    if names.is_empty() {
+        bail!("no entries to extract");
+    }
+
+    for name in &names {
+        let path = root.join(entry_path(name));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
         }
+        std::fs::write(&path, &archive.entries[*name])?;
     }
+    println!("Extracted {} entries to {root:?}", names.len());
 
     Ok(())
 }
+
+fn create(
+    directory: &Path,
+    output: Option<PathBuf>,
+    extension: Extension,
+    compress: bool,
+) -> anyhow::Result<()> {
+    let output = output.unwrap_or_else(|| directory.with_extension(extension.as_str(compress)));
+    let compress = compress
+        || output
+            .extension()
+            .is_some_and(|extension| extension.to_string_lossy().ends_with('z'));
+
+    let mut archive = StreamArchive::default();
+    for file in std::fs::read_dir(directory)? {
+        let path = file?.path();
+        if path.is_file() {
+            archive
+                .entries
+                .insert(file_name(&path)?, std::fs::read(&path)?);
+        }
+    }
+
+    write_archive(&output, &archive, compress)?;
+    println!("Wrote {} entries to {output:?}", archive.entries.len());
+
+    Ok(())
+}
+
+fn add(path: &Path, files: &[PathBuf], replace: bool, add: bool) -> anyhow::Result<()> {
+    let (mut archive, compressed) = read_archive(path)?;
+
+    for file in files {
+        let name = file_name(file)?;
+        let exists = archive.entries.contains_key(&name);
+        if exists && !replace {
+            bail!("{name} is already in the archive");
+        } else if !exists && !add {
+            bail!("{name} is not in the archive");
+        }
+
+        let data = std::fs::read(file).with_context(|| format!("failed to read {file:?}"))?;
+        archive.entries.insert(name, data);
+    }
+
+    write_archive(path, &archive, compressed)
+}
+
+fn remove(path: &Path, patterns: &[String]) -> anyhow::Result<()> {
+    let (mut archive, compressed) = read_archive(path)?;
+
+    let names: Vec<String> = entry_names(&archive, patterns)
+        .into_iter()
+        .cloned()
+        .collect();
+    if names.is_empty() {
+        bail!("no entries match");
+    }
+
+    for name in &names {
+        archive.entries.remove(name);
+        println!("Removed {name}");
+    }
+
+    write_archive(path, &archive, compressed)
+}
+
+fn verify(path: &Path, directory: &Path) -> anyhow::Result<()> {
+    let (archive, _) = read_archive(path)?;
+
+    let mut differences = 0;
+    for name in entry_names(&archive, &[]) {
+        let file = directory.join(entry_path(name));
+        if !file.is_file() {
+            println!("missing: {name}");
+            differences += 1;
+        } else if std::fs::read(&file)? != archive.entries[name] {
+            println!("differs: {name}");
+            differences += 1;
+        }
+    }
+
+    for file in std::fs::read_dir(directory)? {
+        let file = file?.path();
+        if file.is_file() && !archive.entries.contains_key(&file_name(&file)?) {
+            println!("extra: {}", file_name(&file)?);
+            differences += 1;
+        }
+    }
+
+    if differences > 0 {
+        bail!("{differences} differences between {path:?} and {directory:?}");
+    }
+    println!("All {} entries match", archive.entries.len());
+
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    match args.command {
+        Commands::List { archive, patterns } => list(&archive, &patterns),
+        Commands::Extract {
+            archive,
+            patterns,
+            output,
+        } => extract(&archive, &patterns, output),
+        Commands::Create {
+            directory,
+            output,
+            extension,
+            compress,
+        } => create(&directory, output, extension, compress),
+        Commands::Add {
+            archive,
+            files,
+            replace,
+        } => add(&archive, &files, replace, true),
+        Commands::Replace { archive, files } => add(&archive, &files, true, false),
+        Commands::Remove { archive, patterns } => remove(&archive, &patterns),
+        Commands::Verify { archive, directory } => verify(&archive, &directory),
+    }
+}
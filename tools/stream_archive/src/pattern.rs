@@ -0,0 +1,78 @@
+/// Matches a name against a glob pattern, where `*` matches any run of characters and `?`
+/// matches any one character. Names are compared case insensitively, as the game does.
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    // Where to resume after the last `*`, should the rest of the pattern fail to match
+    let mut backtrack = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, skipped)) => {
+                    backtrack = Some((star, skipped + 1));
+                    p = star + 1;
+                    n = skipped + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Matches a name against any of `patterns`, or anything if there are none.
+pub fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.is_empty() || patterns.iter().any(|pattern| matches(pattern, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_pattern() {
+        assert!(matches("", ""));
+        assert!(!matches("", "a.dds"));
+        assert!(matches("*", ""));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(matches("*.dds", "a.dds"));
+        assert!(matches("*.dds", ".dds"));
+        assert!(!matches("*.dds", "a.ddsc"));
+        assert!(matches("a*", "a"));
+        assert!(matches("a*", "a.dds"));
+        assert!(matches("a**b", "ab"));
+        assert!(matches("**.lod", "models/a.lod"));
+        assert!(matches("?.dds", "a.dds"));
+        assert!(!matches("?.dds", ".dds"));
+        assert!(matches("*a*a", "banana"));
+        assert!(!matches("*a*b", "banana"));
+    }
+
+    #[test]
+    fn ignore_case() {
+        assert!(matches("*.DDS", "Rico_Dif.dds"));
+        assert!(matches("rico_*", "RICO_DIF.DDS"));
+    }
+
+    #[test]
+    fn match_any() {
+        assert!(matches_any(&[], "a.dds"));
+        let patterns: [String; 2] = ["*.lod".into(), "*.rbm".into()];
+        assert!(matches_any(&patterns, "a.rbm"));
+        assert!(!matches_any(&patterns, "a.dds"));
+    }
+}
@@ -0,0 +1,232 @@
+use std::{io::Write, path::Path};
+
+use anyhow::Context;
+use gltf_json::{
+    Index, Root,
+    accessor::{ComponentType, GenericComponentType, Type},
+    buffer::{Target, View},
+    material::{PbrMetallicRoughness, StrengthFactor},
+    mesh::{Mode, Primitive, Semantic},
+    validation::Checked,
+};
+use serde_json::json;
+
+use crate::{
+    Chunk,
+    mesh::{CHUNK_SIZE, WORLD_OFFSET},
+};
+
+/// Appends `bytes` to the buffer data as a view of its own, returning the view.
+fn push_view(root: &mut Root, data: &mut Vec<u8>, bytes: &[u8], target: Target) -> Index<View> {
+    data.resize((data.len() + 3) & !3, 0);
+    let offset = data.len();
+    data.extend_from_slice(bytes);
+
+    root.push(View {
+        buffer: Index::new(0),
+        byte_length: bytes.len().into(),
+        byte_offset: Some(offset.into()),
+        byte_stride: None,
+        name: None,
+        target: Some(Checked::Valid(target)),
+        extensions: Default::default(),
+        extras: Default::default(),
+    })
+}
+
+fn push_accessor(
+    root: &mut Root,
+    view: Index<View>,
+    count: usize,
+    component_type: ComponentType,
+    type_: Type,
+    bounds: Option<([f32; 3], [f32; 3])>,
+) -> Index<gltf_json::Accessor> {
+    root.push(gltf_json::Accessor {
+        buffer_view: Some(view),
+        byte_offset: None,
+        count: count.into(),
+        component_type: Checked::Valid(GenericComponentType(component_type)),
+        extensions: Default::default(),
+        extras: Default::default(),
+        type_: Checked::Valid(type_),
+        min: bounds.map(|(min, _)| json!(min)),
+        max: bounds.map(|(_, max)| json!(max)),
+        name: None,
+        normalized: false,
+        sparse: None,
+    })
+}
+
+fn bounds(positions: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    positions.iter().fold(
+        ([f32::MAX; 3], [f32::MIN; 3]),
+        |(mut min, mut max), position| {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+            (min, max)
+        },
+    )
+}
+
+fn push_material(root: &mut Root, chunk: &Chunk) -> Index<gltf_json::Material> {
+    let texture = chunk.texture.as_ref().map(|uri| {
+        let source = root.push(gltf_json::Image {
+            buffer_view: None,
+            mime_type: None,
+            name: None,
+            uri: Some(uri.clone()),
+            extensions: Default::default(),
+            extras: Default::default(),
+        });
+        root.push(gltf_json::Texture {
+            name: None,
+            sampler: None,
+            source,
+            extensions: Default::default(),
+            extras: Default::default(),
+        })
+    });
+
+    root.push(gltf_json::Material {
+        name: Some(chunk.name.clone()),
+        pbr_metallic_roughness: PbrMetallicRoughness {
+            base_color_texture: texture.map(|index| gltf_json::texture::Info {
+                index,
+                tex_coord: 0,
+                extensions: Default::default(),
+                extras: Default::default(),
+            }),
+            metallic_factor: StrengthFactor(0.0),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
+
+fn push_mesh(root: &mut Root, data: &mut Vec<u8>, chunk: &Chunk) -> Index<gltf_json::Mesh> {
+    let mesh = &chunk.mesh;
+    let count = mesh.positions.len();
+
+    let view = push_view(
+        root,
+        data,
+        bytemuck::cast_slice(&mesh.positions),
+        Target::ArrayBuffer,
+    );
+    let positions = push_accessor(
+        root,
+        view,
+        count,
+        ComponentType::F32,
+        Type::Vec3,
+        Some(bounds(&mesh.positions)),
+    );
+    let view = push_view(
+        root,
+        data,
+        bytemuck::cast_slice(&mesh.normals),
+        Target::ArrayBuffer,
+    );
+    let normals = push_accessor(root, view, count, ComponentType::F32, Type::Vec3, None);
+    let view = push_view(
+        root,
+        data,
+        bytemuck::cast_slice(&mesh.uvs),
+        Target::ArrayBuffer,
+    );
+    let uvs = push_accessor(root, view, count, ComponentType::F32, Type::Vec2, None);
+    let view = push_view(
+        root,
+        data,
+        bytemuck::cast_slice(&mesh.indices),
+        Target::ElementArrayBuffer,
+    );
+    let indices = push_accessor(
+        root,
+        view,
+        mesh.indices.len(),
+        ComponentType::U32,
+        Type::Scalar,
+        None,
+    );
+
+    let material = push_material(root, chunk);
+    root.push(gltf_json::Mesh {
+        name: Some(chunk.name.clone()),
+        primitives: vec![Primitive {
+            attributes: [
+                (Checked::Valid(Semantic::Positions), positions),
+                (Checked::Valid(Semantic::Normals), normals),
+                (Checked::Valid(Semantic::TexCoords(0)), uvs),
+            ]
+            .into(),
+            indices: Some(indices),
+            material: Some(material),
+            mode: Checked::Valid(Mode::Triangles),
+            targets: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+        }],
+        weights: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    })
+}
+
+/// Writes chunks to a glTF file, with a node for each chunk placed where it is in the world.
+pub fn write(path: &Path, chunks: &[Chunk]) -> anyhow::Result<()> {
+    let mut root = Root::default();
+    let mut data = vec![];
+    let mut nodes = vec![];
+
+    for chunk in chunks {
+        let mesh = push_mesh(&mut root, &mut data, chunk);
+
+        let [x, z] = chunk.position.map(|axis| axis as f32 * CHUNK_SIZE.0);
+        nodes.push(root.push(gltf_json::Node {
+            mesh: Some(mesh),
+            camera: None,
+            children: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+            matrix: None,
+            name: Some(chunk.name.clone()),
+            rotation: None,
+            scale: None,
+            translation: Some([x + WORLD_OFFSET, 0.0, z + WORLD_OFFSET]),
+            skin: None,
+            weights: None,
+        }));
+    }
+
+    data.resize((data.len() + 3) & !3, 0);
+    let bin = path.with_extension("bin");
+    std::fs::File::create(&bin)?.write_all(&data)?;
+    root.push(gltf_json::Buffer {
+        byte_length: data.len().into(),
+        name: None,
+        uri: Some(
+            bin.file_name()
+                .context("invalid path")?
+                .to_string_lossy()
+                .into(),
+        ),
+        extensions: Default::default(),
+        extras: Default::default(),
+    });
+
+    let scene = root.push(gltf_json::Scene {
+        name: None,
+        nodes,
+        extensions: Default::default(),
+        extras: Default::default(),
+    });
+    root.scene = Some(scene);
+
+    let writer = std::fs::File::create(path)?;
+    gltf_json::serialize::to_writer_pretty(writer, &root)?;
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use jc2_file_formats::{BinWrite, terrain::TerrainChunk};
+
+use crate::{
+    chunk_position,
+    mesh::{HEIGHT_SIZE, rebuild_lods},
+    read_chunk,
+};
+
+/// The distance between the height samples of neighbouring chunks.
+const CHUNK_SAMPLES: isize = 128;
+
+/// Reads a height map as 16-bit PNG, or as raw little endian samples like `extract` writes.
+fn read_height_map(path: &Path) -> anyhow::Result<Vec<u16>> {
+    let is_png = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+
+    let samples = if is_png {
+        let image = image::open(path)?.into_luma16();
+        if image.dimensions() != (HEIGHT_SIZE as u32, HEIGHT_SIZE as u32) {
+            bail!("height map must be {HEIGHT_SIZE}x{HEIGHT_SIZE} pixels");
+        }
+        image.into_raw()
+    } else {
+        let data = std::fs::read(path)?;
+        if data.len() != HEIGHT_SIZE * HEIGHT_SIZE * 2 {
+            bail!("height map must be {HEIGHT_SIZE}x{HEIGHT_SIZE} 16-bit samples");
+        }
+        data.chunks_exact(2)
+            .map(|sample| u16::from_le_bytes([sample[0], sample[1]]))
+            .collect()
+    };
+
+    Ok(samples)
+}
+
+/// Copies the samples `source` shares with a chunk `offset` chunks away from it into `target`.
+fn copy_border(source: &[u16], target: &mut [u16], offset: [isize; 2]) {
+    let size = HEIGHT_SIZE as isize;
+    for z in 0..size {
+        for x in 0..size {
+            let [sx, sz] = [x + offset[0] * CHUNK_SAMPLES, z + offset[1] * CHUNK_SAMPLES];
+            if (0..size).contains(&sx) && (0..size).contains(&sz) {
+                target[(x + z * size) as usize] = source[(sx + sz * size) as usize];
+            }
+        }
+    }
+}
+
+fn write_chunk(path: &Path, chunk: &TerrainChunk) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    chunk.write_le(&mut writer)?;
+    Ok(())
+}
+
+fn update_neighbour(
+    path: &Path,
+    output: &Path,
+    height_map: &[u16],
+    position: [u32; 2],
+    offset: [isize; 2],
+    tolerance: f32,
+) -> anyhow::Result<()> {
+    let mut chunk = read_chunk(path).with_context(|| format!("failed to read {path:?}"))?;
+    copy_border(height_map, &mut chunk.height_map, offset);
+    rebuild_lods(&mut chunk, position, tolerance);
+    write_chunk(output, &chunk)
+}
+
+/// Replaces the height map of a chunk and rebuilds its meshes. Neighbouring chunks next to it
+/// get the samples they share with it, so their edges still meet.
+pub fn import(
+    file: &Path,
+    height_map: &Path,
+    output: Option<&Path>,
+    tolerance: f32,
+) -> anyhow::Result<()> {
+    let directory = file.parent().context("invalid path")?;
+    let output = output.unwrap_or(directory);
+    let position = chunk_position(file)
+        .with_context(|| format!("{file:?} does not end in a chunk position"))?;
+    let stem = file.file_stem().context("invalid path")?.to_string_lossy();
+    let prefix = stem.rsplitn(3, '_').nth(2).unwrap_or_default();
+
+    let mut chunk = read_chunk(file).with_context(|| format!("failed to read {file:?}"))?;
+    let samples = read_height_map(height_map)?;
+    chunk.height_map.copy_from_slice(&samples);
+    rebuild_lods(&mut chunk, position, tolerance);
+
+    std::fs::create_dir_all(output)?;
+    write_chunk(
+        &output.join(file.file_name().context("invalid path")?),
+        &chunk,
+    )?;
+
+    for dz in -1..=1 {
+        for dx in -1..=1 {
+            if dx == 0 && dz == 0 {
+                continue;
+            }
+            let (Some(x), Some(z)) = (
+                position[0].checked_add_signed(dx),
+                position[1].checked_add_signed(dz),
+            ) else {
+                continue;
+            };
+
+            let name = PathBuf::from(format!("{prefix}_{x}_{z}.dat"));
+            let path = directory.join(&name);
+            if path.is_file() {
+                update_neighbour(
+                    &path,
+                    &output.join(&name),
+                    &chunk.height_map,
+                    [x, z],
+                    [dx as isize, dz as isize],
+                    tolerance,
+                )?;
+                println!("Updated the edges of {name:?}");
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,48 +1,264 @@
-use clap::Parser;
-use jc2_file_formats::{BinRead, terrain::TerrainChunk};
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use clap::{Parser, Subcommand};
+use jc2_file_formats::{BinRead, terrain::TerrainChunk, texture::DdsTexture};
+
+mod gltf;
+mod import;
+mod mesh;
+use mesh::{ChunkMesh, HEIGHT_SIZE, Lod};
+mod obj;
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
 struct Args {
-    #[arg()]
-    file: std::path::PathBuf,
+    #[command(subcommand)]
+    command: Option<Commands>,
+    #[arg(help = "Chunk (.dat) to extract, as the extract command does")]
+    file: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    #[command(about = "Writes the textures and height map of a chunk next to it")]
+    Extract {
+        #[arg()]
+        file: PathBuf,
+    },
+    #[command(about = "Exports chunks as a mesh, using the game's own triangulation")]
+    Export {
+        #[arg(help = "Chunks (.dat) or directories containing them")]
+        paths: Vec<PathBuf>,
+        #[arg(
+            long,
+            short,
+            help = "File to write, as OBJ for a .obj extension and glTF otherwise"
+        )]
+        output: PathBuf,
+        #[arg(long, value_enum, default_value_t = Lod::High, help = "Level of detail to export")]
+        lod: Lod,
+        #[arg(long, value_name = "X,Z", value_parser = parse_position, help = "Lowest chunk to export")]
+        min: Option<[u32; 2]>,
+        #[arg(long, value_name = "X,Z", value_parser = parse_position, help = "Highest chunk to export")]
+        max: Option<[u32; 2]>,
+    },
+    #[command(about = "Replaces the height map of a chunk and rebuilds its meshes")]
+    Import {
+        #[arg(help = "Chunk (.dat) to update")]
+        file: PathBuf,
+        #[arg(help = "Height map as a 16-bit PNG, or raw samples like extract writes")]
+        height_map: PathBuf,
+        #[arg(
+            long,
+            short,
+            help = "Directory to write the chunk and its neighbours to, defaulting to the chunk's own"
+        )]
+        output: Option<PathBuf>,
+        #[arg(
+            long,
+            default_value_t = 0.5,
+            help = "Largest height error in metres of the most detailed meshes"
+        )]
+        tolerance: f32,
+    },
+}
+
+/// A chunk ready to be written out.
+pub struct Chunk {
+    pub name: String,
+    pub position: [u32; 2],
+    pub mesh: ChunkMesh,
+    /// The file name of the chunk's map tile, written next to the output.
+    pub texture: Option<String>,
+}
+
+fn read_chunk(path: &Path) -> anyhow::Result<TerrainChunk> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    Ok(TerrainChunk::read_le(&mut reader)?)
+}
+
+fn extract(file: &Path) -> anyhow::Result<()> {
+    let terrain = read_chunk(file)?;
+
+    std::fs::write(
+        file.with_extension("normal.dds"),
+        terrain.textures.normal_map,
+    )?;
+
+    std::fs::write(
+        file.with_extension("material.dds"),
+        terrain.textures.material_map,
+    )?;
+
+    std::fs::write(
+        file.with_extension("weight.dds"),
+        terrain.textures.weight_map,
+    )?;
+
+    std::fs::write(
+        file.with_extension("minimap.dds"),
+        terrain.textures.map_tile,
+    )?;
+
+    let height_map: Vec<u8> = terrain
+        .height_map
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect();
+    std::fs::write(file.with_extension("height.raw"), &height_map)?;
+
+    image::ImageBuffer::<image::Luma<u16>, _>::from_raw(
+        HEIGHT_SIZE as u32,
+        HEIGHT_SIZE as u32,
+        &terrain.height_map[..],
+    )
+    .context("invalid height map")?
+    .save(file.with_extension("height.png"))?;
+
+    Ok(())
+}
+
+/// Reads the grid position of a chunk from the end of its file name, such as `h_12_34.dat`.
+fn chunk_position(path: &Path) -> Option<[u32; 2]> {
+    let stem = path.file_stem()?.to_str()?;
+    let mut parts = stem.rsplit('_');
+    let z = parts.next()?.parse().ok()?;
+    let x = parts.next()?.parse().ok()?;
+    Some([x, z])
+}
+
+fn parse_position(value: &str) -> Result<[u32; 2], String> {
+    let (x, z) = value
+        .split_once(',')
+        .ok_or_else(|| "expected a position such as 12,34".to_owned())?;
+    let parse = |axis: &str| axis.trim().parse().map_err(|error| format!("{error}"));
+    Ok([parse(x)?, parse(z)?])
+}
+
+/// Finds every chunk in the given paths, searching directories recursively.
+fn find_chunks(paths: &[PathBuf]) -> anyhow::Result<Vec<(PathBuf, [u32; 2])>> {
+    fn visit(path: &Path, result: &mut Vec<(PathBuf, [u32; 2])>) -> anyhow::Result<()> {
+        if path.is_dir() {
+            let mut entries = std::fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.sort();
+            for entry in entries {
+                visit(&entry, result)?;
+            }
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("dat"))
+        {
+            let position = chunk_position(path)
+                .with_context(|| format!("{path:?} does not end in a chunk position"))?;
+            result.push((path.into(), position));
+        }
+        Ok(())
+    }
+
+    let mut result = vec![];
+    for path in paths {
+        visit(path, &mut result)?;
+    }
+    Ok(result)
+}
+
+/// Decodes a chunk's map tile to a PNG in `directory`, returning its file name.
+fn write_texture(directory: &Path, name: &str, chunk: &TerrainChunk) -> anyhow::Result<String> {
+    let texture = DdsTexture::read(&mut Cursor::new(&chunk.textures.map_tile[..]))?;
+    let file_name = format!("{name}.png");
+    texture.decode(0, 0)?.save(directory.join(&file_name))?;
+    Ok(file_name)
+}
+
+fn export(
+    paths: &[PathBuf],
+    output: &Path,
+    lod: Lod,
+    min: [u32; 2],
+    max: [u32; 2],
+) -> anyhow::Result<()> {
+    let directory = output.parent().context("invalid path")?;
+
+    let mut chunks = vec![];
+    for (path, position) in find_chunks(paths)? {
+        if (0..2).any(|axis| position[axis] < min[axis] || position[axis] > max[axis]) {
+            continue;
+        }
+
+        let name = path
+            .file_stem()
+            .context("invalid path")?
+            .to_string_lossy()
+            .into_owned();
+        let terrain = read_chunk(&path).with_context(|| format!("failed to read {path:?}"))?;
+
+        let texture = match write_texture(directory, &name, &terrain) {
+            Ok(texture) => Some(texture),
+            Err(error) => {
+                eprintln!("{path:?}: {error:#}");
+                None
+            }
+        };
+
+        chunks.push(Chunk {
+            mesh: ChunkMesh::new(&terrain, position, lod)
+                .with_context(|| format!("failed to triangulate {path:?}"))?,
+            name,
+            position,
+            texture,
+        });
+    }
+
+    if chunks.is_empty() {
+        bail!("no chunks to export");
+    }
+
+    let is_obj = output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("obj"));
+    if is_obj {
+        obj::write(output, &chunks)
+    } else {
+        gltf::write(output, &chunks)
+    }
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    if args.file.is_file() {
-        let file = std::fs::File::open(args.file.clone())?;
-        let mut reader = std::io::BufReader::new(file);
-
-        let terrain = TerrainChunk::read_le(&mut reader)?;
-
-        std::fs::write(
-            args.file.with_extension("normal.dds"),
-            terrain.textures.normal_map,
-        )?;
-
-        std::fs::write(
-            args.file.with_extension("material.dds"),
-            terrain.textures.material_map,
-        )?;
-
-        std::fs::write(
-            args.file.with_extension("weight.dds"),
-            terrain.textures.weight_map,
-        )?;
-
-        std::fs::write(
-            args.file.with_extension("minimap.dds"),
-            terrain.textures.map_tile,
-        )?;
-
-        let height_map: Vec<u8> = terrain
-            .height_map
-            .iter()
-            .flat_map(|x| x.to_le_bytes())
-            .collect();
-        std::fs::write(args.file.with_extension("height.raw"), &height_map)?;
-    }
+    // A bare chunk is extracted, as it was before the tool had commands
+    let command = match (args.command, args.file) {
+        (Some(command), _) => command,
+        (None, Some(file)) => Commands::Extract { file },
+        (None, None) => bail!("expected a command or a chunk to extract"),
+    };
 
-    Ok(())
+    match command {
+        Commands::Extract { file } => extract(&file),
+        Commands::Export {
+            paths,
+            output,
+            lod,
+            min,
+            max,
+        } => export(
+            &paths,
+            &output,
+            lod,
+            min.unwrap_or([u32::MIN; 2]),
+            max.unwrap_or([u32::MAX; 2]),
+        ),
+        Commands::Import {
+            file,
+            height_map,
+            output,
+            tolerance,
+        } => import::import(&file, &height_map, output.as_deref(), tolerance),
+    }
 }
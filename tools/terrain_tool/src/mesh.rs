@@ -0,0 +1,161 @@
+use clap::ValueEnum;
+use jc2_file_formats::terrain::{
+    TERRAIN_MESH_DIVISIONS, TerrainChunk, TerrainMesh, TerrainMeshData,
+};
+
+/// The width of a chunk, and the height of its highest possible point.
+pub const CHUNK_SIZE: (f32, f32) = (512.0, 2200.0);
+/// The world is centred on the origin, with chunk 0, 0 in its north-west corner.
+pub const WORLD_OFFSET: f32 = -16384.0;
+
+const DIVISIONS: f32 = TERRAIN_MESH_DIVISIONS as f32;
+const WIDTH_SCALE: f32 = CHUNK_SIZE.0 / DIVISIONS;
+const HEIGHT_SCALE: f32 = CHUNK_SIZE.1 / u16::MAX as f32;
+/// The number of height samples along each side of a chunk.
+pub const HEIGHT_SIZE: usize = 132;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Lod {
+    /// A single mesh for the whole chunk
+    Low,
+    /// 2x2 meshes
+    Medium,
+    /// 4x4 meshes
+    High,
+}
+
+impl Lod {
+    const fn level(self) -> u32 {
+        match self {
+            Self::Low => 0,
+            Self::Medium => 1,
+            Self::High => 2,
+        }
+    }
+
+    fn meshes(self, lods: &TerrainMeshData) -> &[TerrainMesh] {
+        match self {
+            Self::Low => &lods.low,
+            Self::Medium => &lods.medium,
+            Self::High => &lods.high,
+        }
+    }
+
+    fn meshes_mut(self, lods: &mut TerrainMeshData) -> &mut [TerrainMesh] {
+        match self {
+            Self::Low => &mut lods.low,
+            Self::Medium => &mut lods.medium,
+            Self::High => &mut lods.high,
+        }
+    }
+
+    const fn stride(self) -> usize {
+        1 << self.level()
+    }
+
+    /// Whether the mesh at `index` within the chunk at `position` is flipped. Each mesh
+    /// alternates diagonals with its neighbours across the whole world.
+    const fn is_flipped(self, position: [u32; 2], index: usize) -> bool {
+        let level = self.level();
+        let stride = self.stride();
+        let x = (position[0] << level) as usize + index % stride;
+        let z = (position[1] << level) as usize + index / stride;
+        (x + z) % 2 == 1
+    }
+
+    /// Converts a point on the grid of the mesh at `index` to a point on the chunk's grid.
+    fn chunk_point(self, index: usize, vertex: [u8; 2]) -> [f32; 2] {
+        let stride = self.stride();
+        let size = DIVISIONS / stride as f32;
+        [
+            (index % stride) as f32 * size + f32::from(vertex[0]) / stride as f32,
+            (index / stride) as f32 * size + f32::from(vertex[1]) / stride as f32,
+        ]
+    }
+}
+
+/// A terrain chunk triangulated the way the game draws it, positioned relative to the
+/// chunk's north-west corner.
+#[derive(Default)]
+pub struct ChunkMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+impl ChunkMesh {
+    pub fn new(chunk: &TerrainChunk, position: [u32; 2], lod: Lod) -> anyhow::Result<Self> {
+        let mut result = Self::default();
+        for (index, mesh) in lod.meshes(&chunk.lods).iter().enumerate() {
+            let triangles = mesh.triangulate(lod.is_flipped(position, index))?;
+
+            let base = result.positions.len() as u32;
+            for &vertex in &triangles.vertices {
+                let [x, z] = lod.chunk_point(index, vertex);
+                result.positions.push([
+                    x * WIDTH_SCALE,
+                    height(&chunk.height_map, x, z) * HEIGHT_SCALE,
+                    z * WIDTH_SCALE,
+                ]);
+                result.normals.push(normal(&chunk.height_map, x, z));
+                result.uvs.push([x / DIVISIONS, z / DIVISIONS]);
+            }
+            result.indices.extend(
+                triangles
+                    .triangles()
+                    .flatten()
+                    .map(|&vertex| base + u32::from(vertex)),
+            );
+        }
+
+        Ok(result)
+    }
+}
+
+/// Rebuilds every level of detail of a chunk from its height map. `tolerance` is the largest
+/// height error in metres of the most detailed meshes, and doubles for each level below.
+pub fn rebuild_lods(chunk: &mut TerrainChunk, position: [u32; 2], tolerance: f32) {
+    for lod in [Lod::Low, Lod::Medium, Lod::High] {
+        let tolerance = tolerance * (1 << (2 - lod.level())) as f32 / HEIGHT_SCALE;
+        for (index, mesh) in lod.meshes_mut(&mut chunk.lods).iter_mut().enumerate() {
+            *mesh = TerrainMesh::from_heights(
+                |vertex| {
+                    let [x, z] = lod.chunk_point(index, vertex);
+                    height(&chunk.height_map, x, z)
+                },
+                lod.is_flipped(position, index),
+                tolerance,
+            );
+        }
+    }
+}
+
+/// Samples the height map at a point on the mesh grid. Heights are stored every half a cell,
+/// with a border of two samples shared with neighbouring chunks.
+fn height(map: &[u16; HEIGHT_SIZE * HEIGHT_SIZE], x: f32, z: f32) -> f32 {
+    let max = (HEIGHT_SIZE - 1) as f32;
+    let (x, z) = (
+        (2.0 + x * 2.0).clamp(0.0, max),
+        (2.0 + z * 2.0).clamp(0.0, max),
+    );
+    let (x0, z0) = (x.floor(), z.floor());
+    let (x1, z1) = ((x0 + 1.0).min(max), (z0 + 1.0).min(max));
+    let sample = |x: f32, z: f32| f32::from(map[x as usize + z as usize * HEIGHT_SIZE]);
+
+    let (tx, tz) = (x - x0, z - z0);
+    let top = sample(x0, z0) + (sample(x1, z0) - sample(x0, z0)) * tx;
+    let bottom = sample(x0, z1) + (sample(x1, z1) - sample(x0, z1)) * tx;
+    top + (bottom - top) * tz
+}
+
+fn normal(map: &[u16; HEIGHT_SIZE * HEIGHT_SIZE], x: f32, z: f32) -> [f32; 3] {
+    // Half a cell is the distance between height samples
+    const STEP: f32 = 0.5;
+    let slope = |a: f32, b: f32| (b - a) * HEIGHT_SCALE / (2.0 * STEP * WIDTH_SCALE);
+    let dx = slope(height(map, x - STEP, z), height(map, x + STEP, z));
+    let dz = slope(height(map, x, z - STEP), height(map, x, z + STEP));
+
+    let length = (dx * dx + 1.0 + dz * dz).sqrt();
+    [-dx / length, 1.0 / length, -dz / length]
+}
@@ -0,0 +1,64 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+
+use crate::{
+    Chunk,
+    mesh::{CHUNK_SIZE, WORLD_OFFSET},
+};
+
+/// Writes chunks to an OBJ file in world space, with a material library next to it.
+pub fn write(path: &Path, chunks: &[Chunk]) -> anyhow::Result<()> {
+    let library = path.with_extension("mtl");
+    let mut materials = BufWriter::new(File::create(&library)?);
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writeln!(
+        writer,
+        "mtllib {}",
+        library
+            .file_name()
+            .context("invalid path")?
+            .to_string_lossy()
+    )?;
+
+    // OBJ indices are 1-based and count every vertex written so far
+    let mut base = 1;
+    for chunk in chunks {
+        writeln!(materials, "newmtl {}", chunk.name)?;
+        writeln!(materials, "Kd 1 1 1")?;
+        if let Some(texture) = &chunk.texture {
+            writeln!(materials, "map_Kd {texture}")?;
+        }
+
+        let [ox, oz] = chunk
+            .position
+            .map(|axis| axis as f32 * CHUNK_SIZE.0 + WORLD_OFFSET);
+
+        writeln!(writer, "o {}", chunk.name)?;
+        writeln!(writer, "usemtl {}", chunk.name)?;
+        let mesh = &chunk.mesh;
+        for [x, y, z] in &mesh.positions {
+            writeln!(writer, "v {} {y} {}", x + ox, z + oz)?;
+        }
+        for [x, y, z] in &mesh.normals {
+            writeln!(writer, "vn {x} {y} {z}")?;
+        }
+        // OBJ texture coordinates start from the bottom of the image
+        for [u, v] in &mesh.uvs {
+            writeln!(writer, "vt {u} {}", 1.0 - v)?;
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| index + base);
+            writeln!(writer, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+        }
+
+        base += mesh.positions.len() as u32;
+    }
+
+    Ok(())
+}
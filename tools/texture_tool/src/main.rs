@@ -0,0 +1,278 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use clap::{Parser, Subcommand, ValueEnum};
+use image::RgbaImage;
+use jc2_file_formats::{
+    archive::{GameArchive, StreamArchive, relative_path},
+    texture::{DdsFormat, DdsTexture},
+};
+use jc2_hashing::{HashList, HashString};
+
+#[derive(Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    #[command(about = "Converts DDS textures to images, or images to DDS textures")]
+    Convert {
+        #[arg(help = "DDS, PNG or TGA files")]
+        files: Vec<PathBuf>,
+        #[arg(
+            long,
+            short,
+            help = "Directory to write to, rather than next to each input"
+        )]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ImageFormat::Png, help = "Image format DDS textures are converted to")]
+        image: ImageFormat,
+        #[arg(long, value_enum, default_value_t = Compression::Auto, help = "DDS format images are converted to")]
+        format: Compression,
+        #[arg(
+            long,
+            help = "Only store the full size image, rather than a full mip chain"
+        )]
+        no_mipmaps: bool,
+    },
+    #[command(
+        about = "Extracts every texture in archives and the stream archives within them to images, named by their .filelist"
+    )]
+    Extract {
+        #[arg(help = "Archives (.tab) or directories containing them")]
+        archives: Vec<PathBuf>,
+        #[arg(long, short, help = "Directory to extract to")]
+        output: PathBuf,
+        #[arg(long, value_enum, default_value_t = ImageFormat::Png, help = "Image format textures are converted to")]
+        image: ImageFormat,
+        #[arg(
+            long,
+            help = "Name entries missing from the .filelist with the bundled dictionary"
+        )]
+        jc2: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ImageFormat {
+    Png,
+    Tga,
+}
+
+impl ImageFormat {
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Tga => "tga",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Compression {
+    /// DXT1 for opaque images, DXT5 for anything with transparency
+    Auto,
+    #[value(alias = "dxt1")]
+    Bc1,
+    #[value(alias = "dxt3")]
+    Bc2,
+    #[value(alias = "dxt5")]
+    Bc3,
+    #[value(alias = "ati1")]
+    Bc4,
+    #[value(alias = "ati2")]
+    Bc5,
+    Bgra8,
+    L8,
+}
+
+impl Compression {
+    fn format(self, image: &RgbaImage) -> DdsFormat {
+        match self {
+            Self::Auto if image.pixels().all(|pixel| pixel.0[3] == u8::MAX) => DdsFormat::Bc1,
+            Self::Auto | Self::Bc3 => DdsFormat::Bc3,
+            Self::Bc1 => DdsFormat::Bc1,
+            Self::Bc2 => DdsFormat::Bc2,
+            Self::Bc4 => DdsFormat::Bc4,
+            Self::Bc5 => DdsFormat::Bc5,
+            Self::Bgra8 => DdsFormat::BGRA8,
+            Self::L8 => DdsFormat::L8,
+        }
+    }
+}
+
+/// The names cubemap faces are saved with, in the order DDS stores them.
+const CUBEMAP_FACES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Saves the full size image of every face of a texture. Cubemap faces are saved separately,
+/// with the face appended to the file name.
+fn save_images(texture: &DdsTexture, path: &Path) -> anyhow::Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+
+    if !texture.cubemap {
+        texture.decode(0, 0)?.save(path)?;
+        return Ok(());
+    }
+
+    let stem = path.file_stem().context("invalid path")?.to_string_lossy();
+    let extension = path.extension().context("invalid path")?.to_string_lossy();
+    for (face, name) in CUBEMAP_FACES.iter().enumerate() {
+        texture
+            .decode(face, 0)?
+            .save(path.with_file_name(format!("{stem}_{name}.{extension}")))?;
+    }
+
+    Ok(())
+}
+
+fn convert(
+    path: &Path,
+    output: Option<&Path>,
+    image: ImageFormat,
+    compression: Compression,
+    mipmaps: bool,
+) -> anyhow::Result<()> {
+    let is_dds = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("dds"));
+    let extension = if is_dds { image.extension() } else { "dds" };
+
+    let mut target = path.with_extension(extension);
+    if let Some(output) = output {
+        target = output.join(target.file_name().context("invalid path")?);
+    }
+
+    if is_dds {
+        let data = std::fs::read(path)?;
+        let texture = DdsTexture::read(&mut Cursor::new(data))?;
+        save_images(&texture, &target)
+    } else {
+        let image = image::open(path)?.into_rgba8();
+        let texture = DdsTexture::encode(
+            std::slice::from_ref(&image),
+            compression.format(&image),
+            mipmaps,
+        )?;
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(target)?);
+        Ok(texture.write(&mut writer)?)
+    }
+}
+
+/// Returns where an archive entry is extracted to, keeping the directories of its name but
+/// never leaving `output`. Unnamed entries go in a directory of their own.
+fn entry_path(output: &Path, names: &HashList, hash: HashString) -> PathBuf {
+    match names.find_path(hash) {
+        Some(name) => output.join(relative_path(&name.to_string_lossy())),
+        None => output.join("unnamed").join(format!("{:08X}", hash.hash())),
+    }
+}
+
+#[derive(Default)]
+struct Counts {
+    extracted: usize,
+    failed: usize,
+}
+
+/// Saves the texture in an entry as an image at `path`. Stream archives are searched for
+/// textures too, which are saved to a directory named after the archive as
+/// `archive_tool --recursive` does, and any other entry is skipped.
+fn extract_entry(path: &Path, data: &[u8], image: ImageFormat, counts: &mut Counts) {
+    let result = if data.starts_with(b"DDS ") {
+        DdsTexture::read(&mut Cursor::new(data))
+            .map_err(anyhow::Error::from)
+            .and_then(|texture| save_images(&texture, &path.with_extension(image.extension())))
+            .map(|()| counts.extracted += 1)
+    } else if StreamArchive::is_stream_archive(path, data) {
+        StreamArchive::read(&mut Cursor::new(data))
+            .map_err(anyhow::Error::from)
+            .map(|archive| {
+                let directory = path.with_extension("");
+                for (name, data) in &archive.entries {
+                    extract_entry(&directory.join(relative_path(name)), data, image, counts);
+                }
+            })
+    } else {
+        Ok(())
+    };
+
+    if let Err(error) = result {
+        eprintln!("{path:?}: {error:#}");
+        counts.failed += 1;
+    }
+}
+
+fn extract(
+    archives: &[PathBuf],
+    output: &Path,
+    image: ImageFormat,
+    jc2: bool,
+) -> anyhow::Result<()> {
+    let dictionary = if jc2 {
+        HashList::jc2()
+    } else {
+        HashList::new()
+    };
+
+    for (path, _) in GameArchive::find(archives)? {
+        let archive = GameArchive::open(&path)?;
+        // The .filelist is kept over the dictionary, as it is specific to the archive
+        let mut names = archive.file_list()?;
+        names.merge(dictionary.clone());
+
+        let mut counts = Counts::default();
+        archive.for_each_entry(|hash, data| {
+            extract_entry(&entry_path(output, &names, hash), data, image, &mut counts);
+        })?;
+
+        println!(
+            "{path:?}: extracted {} textures, {} failed",
+            counts.extracted, counts.failed
+        );
+    }
+
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    match args.command {
+        Commands::Convert {
+            files,
+            output,
+            image,
+            format,
+            no_mipmaps,
+        } => {
+            if let Some(output) = &output {
+                std::fs::create_dir_all(output)?;
+            }
+
+            let mut failed = false;
+            for file in &files {
+                if let Err(error) = convert(file, output.as_deref(), image, format, !no_mipmaps) {
+                    eprintln!("{file:?}: {error:#}");
+                    failed = true;
+                }
+            }
+            if failed {
+                bail!("some files could not be converted");
+            }
+        }
+        Commands::Extract {
+            archives,
+            output,
+            image,
+            jc2,
+        } => extract(&archives, &output, image, jc2)?,
+    }
+
+    Ok(())
+}